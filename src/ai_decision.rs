@@ -1,17 +1,23 @@
 use std::collections::HashMap;
 use std::fmt;
 
+use std::time::Duration;
+
 use anyhow::{Context, Result, anyhow};
+use chrono::Local;
 use serde::Deserialize;
 use serde::de::{self, Deserializer, Visitor};
 use tokio::sync::{RwLock, broadcast, mpsc};
+use tokio::time;
 
 use crate::command::{
-    AccountSnapshot, CancelOrderRequest, Command, PositionInfo, SetLeverageRequest, TradeOperator,
-    TradeOrderKind, TradeOrderType, TradeRequest, TradeSide, TradingCommand,
+    AccountSnapshot, AiDecisionProposal, CancelOrderRequest, Command, PositionInfo,
+    SetLeverageRequest, TradeEvent, TradeOperator, TradeOrderKind, TradeOrderType, TradeRequest,
+    TradeSide, TradingCommand,
 };
+use crate::config::AiAutoTradeConfig;
 use crate::error_log::ErrorLogStore;
-use crate::okx::{MarketInfo, SharedAccountState};
+use crate::okx::{self, MarketInfo, SharedAccountState};
 use crate::okx_analytics::MarketDataFetcher;
 
 pub const AI_TAG_ENTRY: &str = "dsentry";
@@ -19,38 +25,57 @@ pub const AI_TAG_STOP_LOSS: &str = "dssl";
 pub const AI_TAG_TAKE_PROFIT: &str = "dstp";
 pub const AI_TAG_CLOSE: &str = "dsclose";
 pub const LEVERAGE_EPSILON: f64 = 1e-6;
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+const ENTRY_RESULT_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub struct DecisionExecutor<'a> {
     state: SharedAccountState,
     tx: broadcast::Sender<Command>,
     inst_ids: &'a [String],
     market: &'a MarketDataFetcher,
+    markets: &'a HashMap<String, MarketInfo>,
     order_tx: Option<mpsc::Sender<TradingCommand>>,
     leverage_cache: &'a RwLock<HashMap<LeverageKey, f64>>,
     error_log: ErrorLogStore,
     operator_name: String,
+    autotrade: Option<AiAutoTradeConfig>,
+}
+
+// 决策的交易范围与自动下单上限：可交易合约、合约信息、决策来源名称与 autotrade 配置
+pub struct DecisionScope<'a> {
+    pub inst_ids: &'a [String],
+    pub markets: &'a HashMap<String, MarketInfo>,
+    pub operator_name: String,
+    pub autotrade: Option<AiAutoTradeConfig>,
 }
 
 impl<'a> DecisionExecutor<'a> {
     pub fn new(
         state: SharedAccountState,
         tx: broadcast::Sender<Command>,
-        inst_ids: &'a [String],
         market: &'a MarketDataFetcher,
         order_tx: Option<mpsc::Sender<TradingCommand>>,
         leverage_cache: &'a RwLock<HashMap<LeverageKey, f64>>,
         error_log: ErrorLogStore,
-        operator_name: String,
+        scope: DecisionScope<'a>,
     ) -> Self {
+        let DecisionScope {
+            inst_ids,
+            markets,
+            operator_name,
+            autotrade,
+        } = scope;
         DecisionExecutor {
             state,
             tx,
             inst_ids,
             market,
+            markets,
             order_tx,
             leverage_cache,
             error_log,
             operator_name,
+            autotrade,
         }
     }
 
@@ -59,7 +84,7 @@ impl<'a> DecisionExecutor<'a> {
     }
 
     pub async fn execute(&self, response: &str) -> Result<()> {
-        let Some(_) = &self.order_tx else {
+        let (Some(_), Some(autotrade)) = (&self.order_tx, self.autotrade) else {
             return Ok(());
         };
         let decisions = match parse_ai_decisions(response) {
@@ -69,6 +94,40 @@ impl<'a> DecisionExecutor<'a> {
                 return Err(anyhow!("解析 AI 决策失败: {err}"));
             }
         };
+        // 任一字段不合法时整批决策都不执行，避免只执行了其中一部分
+        let mut errors = decision_field_errors(&decisions);
+        for (idx, decision) in decisions.iter().enumerate() {
+            if matches!(decision.signal, DecisionSignal::Hold | DecisionSignal::Wait)
+                || decision.coin.trim().is_empty()
+            {
+                continue;
+            }
+            if self.resolve_inst_id(&decision.coin).is_none() {
+                errors.push(format!(
+                    "第 {} 条: c {} 不在交易范围内",
+                    idx + 1,
+                    decision.coin
+                ));
+            }
+        }
+        if !errors.is_empty() {
+            let err = anyhow!(errors.join("; "));
+            self.log_decision_parse_failure(&err, response);
+            return Err(anyhow!("AI 决策校验失败，未执行: {err}"));
+        }
+        let decisions: Vec<_> = decisions
+            .into_iter()
+            .filter(|decision| {
+                !matches!(decision.signal, DecisionSignal::Hold | DecisionSignal::Wait)
+            })
+            .filter(|decision| self.within_limits(decision, &autotrade))
+            .collect();
+        if decisions.is_empty() {
+            return Ok(());
+        }
+        if !autotrade.unattended && !self.confirm(&decisions).await {
+            return Ok(());
+        }
         for decision in decisions {
             match decision.signal {
                 DecisionSignal::Hold => continue,
@@ -83,6 +142,67 @@ impl<'a> DecisionExecutor<'a> {
         Ok(())
     }
 
+    // 超出上限的开仓决策直接拒绝，原因经错误通道写入日志
+    fn within_limits(&self, decision: &AiDecisionPayload, limits: &AiAutoTradeConfig) -> bool {
+        if !matches!(
+            decision.signal,
+            DecisionSignal::BuyToEnter | DecisionSignal::SellToEnter
+        ) {
+            return true;
+        }
+        let reason = match (limits.max_order_size, limits.max_leverage) {
+            (Some(max), _) if decision.quantity > max => {
+                format!("数量 {} 超过上限 {max}", decision.quantity)
+            }
+            (_, Some(max)) if decision.leverage > max => {
+                format!("杠杆 {}x 超过上限 {max}x", decision.leverage)
+            }
+            _ => return true,
+        };
+        let _ = self.tx.send(Command::Error(format!(
+            "{} 决策已拒绝: {} {reason}",
+            self.operator_name, decision.coin
+        )));
+        false
+    }
+
+    async fn confirm(&self, decisions: &[AiDecisionPayload]) -> bool {
+        let mut rx = self.tx.subscribe();
+        let id = Local::now().timestamp_millis();
+        let proposal = AiDecisionProposal {
+            id,
+            operator: self.operator_name.clone(),
+            operations: decisions.iter().map(AiDecisionPayload::describe).collect(),
+            expires_at_ms: id + CONFIRM_TIMEOUT.as_millis() as i64,
+        };
+        if self.tx.send(Command::AiDecisionProposed(proposal)).is_err() {
+            return false;
+        }
+        let reply = time::timeout(CONFIRM_TIMEOUT, async {
+            loop {
+                match rx.recv().await {
+                    Ok(Command::AiDecisionResolved(reply_id, approved)) if reply_id == id => {
+                        return approved;
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return false,
+                }
+            }
+        })
+        .await;
+        match reply {
+            Ok(approved) => approved,
+            Err(_) => {
+                let _ = self.tx.send(Command::AiDecisionResolved(id, false));
+                let _ = self.tx.send(Command::Error(format!(
+                    "{} 决策等待确认超时，已放弃执行",
+                    self.operator_name
+                )));
+                false
+            }
+        }
+    }
+
     pub async fn capture_leverage_from_snapshot(&self, snapshot: &AccountSnapshot) {
         if snapshot.positions.is_empty() && snapshot.open_orders.is_empty() {
             return;
@@ -166,13 +286,31 @@ impl<'a> DecisionExecutor<'a> {
                 None
             },
             kind: TradeOrderKind::Regular,
+            margin_mode: None,
+            callback: None,
+            bracket: None,
+            cl_ord_id: Some(okx::new_cl_ord_id()),
         };
+        if let Some(limits) = self.autotrade.map(|autotrade| autotrade.order_limits) {
+            let ct_val = self
+                .markets
+                .get(&request.inst_id)
+                .map(|market| market.ct_val)
+                .unwrap_or(1.0);
+            if let Err(reason) = limits.check(price * request.size * ct_val, request.leverage) {
+                let _ = self.tx.send(Command::Error(format!(
+                    "{} 决策已拒绝: {} {reason}",
+                    self.operator_name, request.inst_id
+                )));
+                return Ok(());
+            }
+        }
         if let Some(target_leverage) = request.leverage {
             let pos_side = determine_entry_pos_side(&request.inst_id, request.side);
             self.ensure_leverage_alignment(&request.inst_id, pos_side, target_leverage)
                 .await?;
         }
-        self.submit_trade_request(request.clone()).await?;
+        self.submit_entry_order(request.clone()).await?;
         self.place_protective_orders(&request, decision).await
     }
 
@@ -201,6 +339,10 @@ impl<'a> DecisionExecutor<'a> {
                     operator: self.ai_operator(),
                     leverage,
                     kind: TradeOrderKind::StopLoss,
+                    margin_mode: entry.margin_mode,
+                    callback: None,
+                    bracket: None,
+                    cl_ord_id: None,
                 };
                 self.submit_trade_request(request).await?;
             } else {
@@ -226,6 +368,10 @@ impl<'a> DecisionExecutor<'a> {
                     operator: self.ai_operator(),
                     leverage,
                     kind: TradeOrderKind::TakeProfit,
+                    margin_mode: entry.margin_mode,
+                    callback: None,
+                    bracket: None,
+                    cl_ord_id: None,
                 };
                 self.submit_trade_request(request).await?;
             } else {
@@ -280,6 +426,10 @@ impl<'a> DecisionExecutor<'a> {
             operator: self.ai_operator(),
             leverage: position.lever,
             kind: TradeOrderKind::Regular,
+            margin_mode: position.mgn_mode,
+            callback: None,
+            bracket: None,
+            cl_ord_id: None,
         };
         self.submit_trade_request(request).await
     }
@@ -357,6 +507,7 @@ impl<'a> DecisionExecutor<'a> {
             inst_id: inst_id.to_string(),
             lever: desired,
             pos_side: pos_side.clone(),
+            margin_mode: None,
         };
         order_tx
             .send(TradingCommand::SetLeverage(request))
@@ -400,6 +551,38 @@ impl<'a> DecisionExecutor<'a> {
         Ok(())
     }
 
+    // 按 clOrdId 等待开仓回报，明确失败时不再挂止盈/止损；超时仍按原流程继续
+    async fn submit_entry_order(&self, request: TradeRequest) -> Result<()> {
+        if self.order_tx.is_none() {
+            return Ok(());
+        }
+        let mut rx = self.tx.subscribe();
+        let inst_id = request.inst_id.clone();
+        let cl_ord_id = request.cl_ord_id.clone();
+        self.submit_trade_request(request).await?;
+        let reply = time::timeout(ENTRY_RESULT_TIMEOUT, async {
+            loop {
+                match rx.recv().await {
+                    Ok(Command::TradeResult(TradeEvent::Order(response)))
+                        if response.cl_ord_id == cl_ord_id =>
+                    {
+                        return Some(response);
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .await;
+        match reply {
+            Ok(Some(response)) if !response.success => Err(anyhow!(
+                "{inst_id} 开仓委托失败，未挂止盈/止损: {}",
+                response.message
+            )),
+            _ => Ok(()),
+        }
+    }
+
     fn warn_invalid_protective_price(
         &self,
         inst_id: &str,
@@ -547,6 +730,27 @@ fn ai_operator_name(name: &str) -> TradeOperator {
     }
 }
 
+// 按输出格式规范严格校验决策，错误信息逐条列出字段
+pub fn validate_ai_decisions(raw: &str) -> std::result::Result<(), String> {
+    let decisions = parse_ai_decisions(raw).map_err(|err| err.to_string())?;
+    let errors = decision_field_errors(&decisions);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+fn decision_field_errors(decisions: &[AiDecisionPayload]) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (idx, decision) in decisions.iter().enumerate() {
+        for error in decision.field_errors() {
+            errors.push(format!("第 {} 条: {error}", idx + 1));
+        }
+    }
+    errors
+}
+
 fn parse_ai_decisions(raw: &str) -> Result<Vec<AiDecisionPayload>> {
     let value = parse_json_value(raw)?;
     match parse_decision_value(&value) {
@@ -594,6 +798,83 @@ struct AiDecisionPayload {
     justification: String,
 }
 
+impl AiDecisionPayload {
+    fn field_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.coin.trim().is_empty() {
+            errors.push("c 不能为空".to_string());
+        }
+        let numbers = [
+            ("qty", self.quantity),
+            ("lev", self.leverage),
+            ("ep", self.entry_price),
+            ("tp", self.profit_target),
+            ("sl", self.stop_loss),
+        ];
+        for (field, value) in numbers {
+            if !value.is_finite() || value < 0.0 {
+                errors.push(format!("{field} 必须为非负数 (当前 {value})"));
+            }
+        }
+        match self.signal {
+            DecisionSignal::BuyToEnter | DecisionSignal::SellToEnter => {
+                if self.quantity == 0.0 {
+                    errors.push("开仓缺少 qty 或 qty 为 0".to_string());
+                }
+                if self.entry_price == 0.0 {
+                    errors.push("开仓缺少 ep 或 ep 为 0".to_string());
+                }
+            }
+            DecisionSignal::CancelOrder
+                if self.cancel_orders.as_ref().is_none_or(Vec::is_empty) =>
+            {
+                errors.push("撤单缺少 cancel_orders".to_string());
+            }
+            _ => {}
+        }
+        errors
+    }
+
+    fn describe(&self) -> String {
+        let mut parts = vec![
+            match self.signal {
+                DecisionSignal::BuyToEnter => "开多",
+                DecisionSignal::SellToEnter => "开空",
+                DecisionSignal::Close => "平仓",
+                DecisionSignal::CancelOrder => "撤单",
+                DecisionSignal::Hold | DecisionSignal::Wait => "观望",
+            }
+            .to_string(),
+            self.coin.clone(),
+        ];
+        match self.signal {
+            DecisionSignal::BuyToEnter | DecisionSignal::SellToEnter => {
+                parts.push(format!("数量 {}", self.quantity));
+                if self.leverage > 0.0 {
+                    parts.push(format!("杠杆 {}x", self.leverage));
+                }
+                parts.push(format!("入场 {}", self.entry_price));
+                if self.profit_target > 0.0 {
+                    parts.push(format!("止盈 {}", self.profit_target));
+                }
+                if self.stop_loss > 0.0 {
+                    parts.push(format!("止损 {}", self.stop_loss));
+                }
+            }
+            DecisionSignal::Close if self.quantity > 0.0 => {
+                parts.push(format!("数量 {}", self.quantity));
+            }
+            DecisionSignal::Close => parts.push("全部".to_string()),
+            DecisionSignal::CancelOrder => {
+                let count = self.cancel_orders.as_ref().map_or(0, Vec::len);
+                parts.push(format!("{count} 笔"));
+            }
+            DecisionSignal::Hold | DecisionSignal::Wait => {}
+        }
+        parts.join(" ")
+    }
+}
+
 fn deserialize_f64<'de, D>(deserializer: D) -> std::result::Result<f64, D::Error>
 where
     D: Deserializer<'de>,
@@ -754,6 +1035,28 @@ mod tests {
         assert!((decision.entry_price - 90000.5).abs() < 1e-9);
     }
 
+    #[test]
+    fn describes_decisions_for_confirmation() {
+        let raw = r#"[
+            {"sig":"bte","c":"BTC-USDT-SWAP","qty":0.01,"lev":3,"ep":90000.5,"tp":90500,"sl":89000},
+            {"sig":"c","c":"ETH-USDT-SWAP"},
+            {"sig":"c","c":"ETH-USDT-SWAP","qty":2}
+        ]"#;
+        let descriptions: Vec<String> = parse_ai_decisions(raw)
+            .expect("should parse decisions")
+            .iter()
+            .map(AiDecisionPayload::describe)
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                "开多 BTC-USDT-SWAP 数量 0.01 杠杆 3x 入场 90000.5 止盈 90500 止损 89000",
+                "平仓 ETH-USDT-SWAP 全部",
+                "平仓 ETH-USDT-SWAP 数量 2",
+            ]
+        );
+    }
+
     #[test]
     fn parses_string_wrapped_response_array() {
         let raw = r#"{"response": "[{\"sig\":\"h\",\"c\":\"ETH-USDT-SWAP\"}]" }"#;
@@ -762,6 +1065,29 @@ mod tests {
         assert!(matches!(decisions[0].signal, DecisionSignal::Hold));
         assert_eq!(decisions[0].coin, "ETH-USDT-SWAP");
     }
+
+    #[test]
+    fn validation_reports_each_invalid_field() {
+        let valid = r#"[{"sig":"bte","c":"BTC-USDT-SWAP","qty":1,"lev":3,"ep":90000},
+            {"sig":"h","c":"ETH-USDT-SWAP"}]"#;
+        assert_eq!(validate_ai_decisions(valid), Ok(()));
+
+        let invalid = r#"[{"sig":"h","c":"ETH-USDT-SWAP"},
+            {"sig":"ste","c":"BTC-USDT-SWAP","qty":"-1","lev":"abc"}]"#;
+        let err = validate_ai_decisions(invalid).unwrap_err();
+        assert!(err.contains("第 2 个"), "{err}");
+
+        let invalid = r#"[{"sig":"ste","c":" ","qty":-1},{"sig":"cancel","c":"BTC"}]"#;
+        let err = validate_ai_decisions(invalid).unwrap_err();
+        assert_eq!(
+            err,
+            "第 1 条: c 不能为空; 第 1 条: qty 必须为非负数 (当前 -1); \
+             第 1 条: 开仓缺少 ep 或 ep 为 0; 第 2 条: 撤单缺少 cancel_orders"
+        );
+
+        let err = validate_ai_decisions(r#"[{"sig":"buy_more","c":"BTC"}]"#).unwrap_err();
+        assert!(err.contains("未知的决策信号"), "{err}");
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -1,4 +1,6 @@
+#[cfg(not(feature = "sqlite"))]
 use std::fs::{self, File, OpenOptions};
+#[cfg(not(feature = "sqlite"))]
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 
@@ -6,7 +8,10 @@ use anyhow::Result;
 use chrono::{DateTime, Local, LocalResult, TimeZone};
 use serde::{Deserialize, Serialize};
 
-use crate::command::AiInsightRecord;
+use crate::ai_decision::validate_ai_decisions;
+use crate::command::{AiInsightRecord, AiTokenUsage};
+#[cfg(feature = "sqlite")]
+use crate::sqlite_store::SqliteLogTable;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct AiDecisionRecord {
@@ -16,6 +21,7 @@ pub struct AiDecisionRecord {
     pub response: String,
     pub operations: Vec<AiDecisionOperation>,
     pub analysis_error: Option<String>,
+    pub usage: Option<AiTokenUsage>,
 }
 
 impl AiDecisionRecord {
@@ -32,6 +38,7 @@ impl AiDecisionRecord {
             response: payload.response,
             operations,
             analysis_error,
+            usage: payload.usage,
         }
     }
 
@@ -63,6 +70,7 @@ impl AiDecisionRecord {
         response: String,
         operations: Vec<AiDecisionOperation>,
         analysis_error: Option<String>,
+        usage: Option<AiTokenUsage>,
     ) -> Self {
         let timestamp = match Local.timestamp_millis_opt(timestamp_ms) {
             LocalResult::Single(dt) => dt,
@@ -82,6 +90,7 @@ impl AiDecisionRecord {
             response,
             operations,
             analysis_error,
+            usage,
         }
     }
 
@@ -91,7 +100,7 @@ impl AiDecisionRecord {
 
     fn analyze_response(response: &str) -> (Vec<AiDecisionOperation>, Option<String>) {
         let (parsed, analysis_error) = match Self::parse_json_block(response) {
-            Ok(parsed) => (Some(parsed), None),
+            Ok(parsed) => (Some(parsed), validate_ai_decisions(response).err()),
             Err(err) => (None, Some(err)),
         };
         let operations = Self::derive_operations(parsed.as_ref());
@@ -159,17 +168,24 @@ impl AiDecisionRecord {
 
 #[derive(Clone, Debug)]
 pub struct AiDecisionStore {
+    #[cfg(not(feature = "sqlite"))]
     path: PathBuf,
-    max_entries: usize,
+    #[cfg(feature = "sqlite")]
+    table: SqliteLogTable,
 }
 
 impl AiDecisionStore {
+    #[cfg(not(feature = "sqlite"))]
     const TAIL_CHUNK_SIZE: usize = 8 * 1024;
+    // 最短决策周期 30s，一天最多约 2880 条
+    const USAGE_SCAN_LIMIT: usize = 4096;
 
     pub fn new(path: PathBuf) -> Self {
         AiDecisionStore {
+            #[cfg(feature = "sqlite")]
+            table: SqliteLogTable::new(&path),
+            #[cfg(not(feature = "sqlite"))]
             path,
-            max_entries: 512,
         }
     }
 
@@ -177,13 +193,8 @@ impl AiDecisionStore {
         PathBuf::from("ai_decisions.jsonl")
     }
 
-    pub fn load(&self) -> Result<Vec<AiDecisionRecord>> {
-        let mut file = match File::open(&self.path) {
-            Ok(file) => file,
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
-            Err(err) => return Err(err.into()),
-        };
-        let lines = Self::read_tail_lines(&mut file, self.max_entries)?;
+    pub fn load_recent(&self, limit: usize) -> Result<Vec<AiDecisionRecord>> {
+        let lines = self.read_recent_lines(limit)?;
         let mut entries = Vec::with_capacity(lines.len());
         for line in lines {
             if line.trim().is_empty() {
@@ -196,7 +207,55 @@ impl AiDecisionStore {
         Ok(entries)
     }
 
+    // sqlite 中 LIMIT 为负数表示不限制，usize::MAX 转换后正好是 -1
+    pub fn load_all(&self) -> Result<Vec<AiDecisionRecord>> {
+        self.load_recent(usize::MAX)
+    }
+
+    // 只解析时间与用量字段，统计时不必还原完整的决策记录
+    pub fn usage_since(&self, start_timestamp_ms: i64) -> Result<AiTokenUsage> {
+        let mut total = AiTokenUsage::default();
+        for line in self.read_recent_lines(Self::USAGE_SCAN_LIMIT)? {
+            let Ok(stored) = serde_json::from_str::<StoredAiUsage>(&line) else {
+                continue;
+            };
+            if stored.timestamp_ms < start_timestamp_ms {
+                continue;
+            }
+            if let Some(usage) = stored.usage {
+                total.add(&usage);
+            }
+        }
+        Ok(total)
+    }
+
     pub fn append(&self, entry: &AiDecisionRecord) -> Result<()> {
+        let line = serde_json::to_string(&StoredAiDecision::from(entry))?;
+        self.append_line(entry.timestamp.timestamp_millis(), &line)
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn read_recent_lines(&self, limit: usize) -> Result<Vec<String>> {
+        self.table.load_recent(limit)
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn append_line(&self, timestamp_ms: i64, line: &str) -> Result<()> {
+        self.table.append(timestamp_ms, line)
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn read_recent_lines(&self, limit: usize) -> Result<Vec<String>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        Self::read_tail_lines(&mut file, limit)
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn append_line(&self, _timestamp_ms: i64, line: &str) -> Result<()> {
         if let Some(parent) = self.path.parent() {
             if !parent.as_os_str().is_empty() {
                 fs::create_dir_all(parent)?;
@@ -206,11 +265,12 @@ impl AiDecisionStore {
             .create(true)
             .append(true)
             .open(&self.path)?;
-        serde_json::to_writer(&mut file, &StoredAiDecision::from(entry))?;
+        file.write_all(line.as_bytes())?;
         file.write_all(b"\n")?;
         Ok(())
     }
 
+    #[cfg(not(feature = "sqlite"))]
     fn read_tail_lines(file: &mut File, max_lines: usize) -> Result<Vec<String>> {
         if max_lines == 0 {
             return Ok(Vec::new());
@@ -247,6 +307,7 @@ impl AiDecisionStore {
         Ok(lines)
     }
 
+    #[cfg(not(feature = "sqlite"))]
     fn push_pending_line(pending: &mut Vec<u8>, lines: &mut Vec<String>) -> bool {
         if pending.is_empty() {
             return false;
@@ -271,6 +332,15 @@ struct StoredAiDecision {
     legacy_operation: Option<AiDecisionOperation>,
     #[serde(default)]
     analysis_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    usage: Option<AiTokenUsage>,
+}
+
+#[derive(Deserialize)]
+struct StoredAiUsage {
+    timestamp_ms: i64,
+    #[serde(default)]
+    usage: Option<AiTokenUsage>,
 }
 
 impl StoredAiDecision {
@@ -289,6 +359,7 @@ impl StoredAiDecision {
             self.response,
             operations,
             self.analysis_error,
+            self.usage,
         )
     }
 }
@@ -303,6 +374,7 @@ impl From<&AiDecisionRecord> for StoredAiDecision {
             operations: value.operations.clone(),
             legacy_operation: None,
             analysis_error: value.analysis_error.clone(),
+            usage: value.usage,
         }
     }
 }
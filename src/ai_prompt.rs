@@ -1,13 +1,15 @@
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use chrono::{DateTime, Local, TimeZone};
 use serde_json::{Value, json};
 
 use crate::ai_decision::{AI_TAG_CLOSE, AI_TAG_ENTRY, AI_TAG_STOP_LOSS, AI_TAG_TAKE_PROFIT};
 use crate::command::AccountSnapshot;
 use crate::config::ConfiguredTimeZone;
+use crate::indicators::{self, DEFAULT_MACD_FAST, DEFAULT_MACD_SIGNAL, DEFAULT_MACD_SLOW};
 use crate::okx::{LongShortRatio, MarketInfo};
 use crate::okx_analytics::{InstrumentAnalytics, KlineRecord, TakerVolume};
 
@@ -15,6 +17,53 @@ pub const SYSTEM_PROMPT_PATH: &str = "prompt/system.md";
 const MAX_POSITIONS: usize = 12;
 const MAX_ORDERS: usize = 12;
 const MAX_BALANCES: usize = 12;
+pub const PROMPT_PLACEHOLDERS: [&str; 7] = [
+    "time",
+    "instruments",
+    "indicators",
+    "balance",
+    "performance",
+    "positions",
+    "orders",
+];
+pub const PROMPT_INDICATORS: [&str; 5] = ["ema", "macd", "rsi", "atr", "change"];
+// 5 分钟 K 线 12 根即近 1 小时
+const PRICE_CHANGE_LOOKBACK: usize = 12;
+const LATEST_RSI_PERIOD: usize = 14;
+const DEFAULT_PROMPT_TEMPLATE: &str = "当前时间: {time}
+
+下方为您提供 k线, ema20, ema50, rsi7, rsi7 等数据，助您发掘超额收益。再下方是您当前的账户信息，包括账户价值、业绩、持仓等。
+
+所有数组、K线均按时间从旧 → 新排列（与系统说明一致）。
+{indicators}## 账户情况:
+```json
+{balance}
+```
+
+## 业绩情况:
+```json
+{performance}
+```
+
+## 持仓情况:
+持仓字段说明: 
+- side: 持仓方向
+- size: 持仓数量(张数)
+- avg_price: 持仓均价(买入价格 USDT)
+- margin: 占用保证金(USDT)
+- unrealized_pnl: 未实现盈亏(USDT)
+- pnl_ratio_pct: 盈亏比例（百分比）
+```json
+{positions}
+```
+
+## 挂单情况:
+```json
+{orders}
+```
+
+
+根据以上数据，请以要求的 JSON 格式提供您的交易决策。";
 
 #[derive(Debug, Clone, Default)]
 pub struct InstrumentLeverage {
@@ -68,120 +117,266 @@ pub fn load_system_prompt() -> Result<String> {
         .with_context(|| format!("读取系统提示词模板失败: {}", SYSTEM_PROMPT_PATH))
 }
 
-pub fn build_snapshot_prompt(
-    snapshot: &AccountSnapshot,
-    analytics: &[InstrumentAnalytics],
-    performance: Option<&PerformanceSummary>,
-    inst_ids: &[String],
-    markets: &HashMap<String, MarketInfo>,
-    leverages: &[InstrumentLeverage],
-    timezone: ConfiguredTimeZone,
-) -> String {
-    let data = build_snapshot(
-        snapshot,
-        analytics,
-        performance,
-        inst_ids,
-        markets,
-        leverages,
-        timezone,
-    );
-    data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndicatorSet {
+    ema: bool,
+    macd: bool,
+    rsi: bool,
+    atr: bool,
+    change: bool,
+}
+
+impl Default for IndicatorSet {
+    fn default() -> Self {
+        IndicatorSet::from_names(&PROMPT_INDICATORS)
+    }
+}
+
+impl IndicatorSet {
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Self {
+        let has = |indicator: &str| {
+            names
+                .iter()
+                .any(|name| name.as_ref().trim().eq_ignore_ascii_case(indicator))
+        };
+        IndicatorSet {
+            ema: has("ema"),
+            macd: has("macd"),
+            rsi: has("rsi"),
+            atr: has("atr"),
+            change: has("change"),
+        }
+    }
+
+    fn keeps_field(&self, key: &str) -> bool {
+        match key {
+            key if key.starts_with("ema") => self.ema,
+            key if key.starts_with("macd") => self.macd,
+            key if key.starts_with("rsi") => self.rsi,
+            key if key.starts_with("atr") => self.atr,
+            key if key.starts_with("change") => self.change,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    text: String,
+}
+
+impl Default for PromptTemplate {
+    fn default() -> Self {
+        PromptTemplate {
+            text: DEFAULT_PROMPT_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl PromptTemplate {
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(PromptTemplate::default());
+        };
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("读取提示词模板失败: {}", path.display()))?;
+        PromptTemplate::parse(text).with_context(|| format!("提示词模板无效: {}", path.display()))
+    }
+
+    pub fn parse(text: String) -> Result<Self> {
+        let mut unknown: Vec<&str> = scan_placeholders(&text)
+            .into_iter()
+            .map(|(_, _, name)| name)
+            .filter(|name| !PROMPT_PLACEHOLDERS.contains(name))
+            .collect();
+        unknown.sort_unstable();
+        unknown.dedup();
+        if !unknown.is_empty() {
+            let unknown: Vec<String> = unknown.iter().map(|name| format!("{{{name}}}")).collect();
+            let known: Vec<String> = PROMPT_PLACEHOLDERS
+                .iter()
+                .map(|name| format!("{{{name}}}"))
+                .collect();
+            bail!(
+                "未知占位符 {}，可用占位符: {}",
+                unknown.join(", "),
+                known.join(", ")
+            );
+        }
+        Ok(PromptTemplate { text })
+    }
+
+    pub fn render(&self, sections: &PromptSections) -> String {
+        let mut output = String::with_capacity(self.text.len());
+        let mut cursor = 0;
+        for (start, end, name) in scan_placeholders(&self.text) {
+            output.push_str(&self.text[cursor..start]);
+            output.push_str(sections.get(name));
+            cursor = end;
+        }
+        output.push_str(&self.text[cursor..]);
+        output
+    }
 }
 
-fn build_snapshot(
-    snapshot: &AccountSnapshot,
+// 仅把 {小写标识符} 视为占位符，其余花括号（如 JSON 示例）原样保留
+fn scan_placeholders(text: &str) -> Vec<(usize, usize, &str)> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find('{').map(|idx| offset + idx) {
+        let rest = &text[start + 1..];
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'))
+            .unwrap_or(rest.len());
+        if name_len > 0 && rest[name_len..].starts_with('}') {
+            let end = start + name_len + 2;
+            found.push((start, end, &rest[..name_len]));
+            offset = end;
+        } else {
+            offset = start + 1;
+        }
+    }
+    found
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PromptSections {
+    time: String,
+    instruments: String,
+    indicators: String,
+    balance: String,
+    performance: String,
+    positions: String,
+    orders: String,
+}
+
+impl PromptSections {
+    pub fn collect(
+        snapshot: &AccountSnapshot,
+        performance: Option<&PerformanceSummary>,
+        inst_ids: &[String],
+        markets: &HashMap<String, MarketInfo>,
+        leverages: &[InstrumentLeverage],
+        timezone: ConfiguredTimeZone,
+    ) -> Self {
+        let time = timezone
+            .format_timestamp(Local::now().timestamp_millis(), "%Y-%m-%d %H:%M:%S")
+            .unwrap_or_else(|| "未知".to_string());
+        let performance = match performance {
+            Some(summary) => json!({
+                "overall": summary.overall.as_ref().map(build_performance_stats_json),
+                "recent": summary.recent.as_ref().map(build_performance_stats_json),
+            }),
+            None => json!({}),
+        };
+        PromptSections {
+            time,
+            instruments: build_instruments_json(inst_ids, markets, leverages).to_string(),
+            indicators: String::new(),
+            balance: build_balance_json(snapshot).to_string(),
+            performance: performance.to_string(),
+            positions: build_positions_json(snapshot, timezone).to_string(),
+            orders: build_orders_json(snapshot, timezone).to_string(),
+        }
+    }
+
+    pub fn with_market_analytics(
+        mut self,
+        analytics: &[InstrumentAnalytics],
+        indicators: IndicatorSet,
+        timezone: ConfiguredTimeZone,
+    ) -> Self {
+        self.indicators = build_market_analytics_section(analytics, indicators, timezone);
+        self
+    }
+
+    fn get(&self, name: &str) -> &str {
+        match name {
+            "time" => &self.time,
+            "instruments" => &self.instruments,
+            "indicators" => &self.indicators,
+            "balance" => &self.balance,
+            "performance" => &self.performance,
+            "positions" => &self.positions,
+            "orders" => &self.orders,
+            _ => "",
+        }
+    }
+}
+
+fn build_market_analytics_section(
     analytics: &[InstrumentAnalytics],
-    performance: Option<&PerformanceSummary>,
-    _inst_ids: &[String],
-    _markets: &HashMap<String, MarketInfo>,
-    _leverages: &[InstrumentLeverage],
+    indicators: IndicatorSet,
     timezone: ConfiguredTimeZone,
 ) -> String {
-    let current_time = timezone
-        .format_timestamp(Local::now().timestamp_millis(), "%Y-%m-%d %H:%M:%S")
-        .unwrap_or_else(|| "未知".to_string());
-
     let mut data = String::new();
-    data.push_str("当前时间: ");
-    data.push_str(&current_time);
-    data.push_str("\n\n");
-    data.push_str("下方为您提供 k线, ema20, ema50, rsi7, rsi7 等数据，助您发掘超额收益。再下方是您当前的账户信息，包括账户价值、业绩、持仓等。\n\n");
-    data.push_str("所有数组、K线均按时间从旧 → 新排列（与系统说明一致）。\n");
-
-    // Market analytics
-    if !analytics.is_empty() {
-        data.push_str("## 市场分析:\n");
-        data.push_str("指标字段说明: \n");
-        data.push_str("- current_price: 当前价格\n");
-        data.push_str("- open_interest_latest: 最新持仓量\n");
-        data.push_str("- open_interest_average: 平均持仓量\n");
-        data.push_str("- funding_rate: 资金费率\n");
-        data.push_str("- interval: 数据时间间隔\n");
-        data.push_str("- recent_candles: 最近 K 线数据\n");
-        data.push_str("- close_prices: 收盘价序列\n");
+    if analytics.is_empty() {
+        return data;
+    }
+    data.push_str("## 市场分析:\n");
+    data.push_str("指标字段说明: \n");
+    data.push_str("- current_price: 当前价格\n");
+    data.push_str("- open_interest_latest: 最新持仓量\n");
+    data.push_str("- open_interest_average: 平均持仓量\n");
+    data.push_str("- funding_rate: 资金费率\n");
+    data.push_str("- interval: 数据时间间隔\n");
+    data.push_str("- recent_candles: 最近 K 线数据\n");
+    data.push_str("- close_prices: 收盘价序列\n");
+    data.push_str("- latest: 基于 5 分钟收盘价计算的最新指标快照\n");
+    if indicators.ema {
         data.push_str("- ema20/ema50: 指数移动平均线\n");
-        data.push_str("- macd: 移动平均收敛散度指标\n");
+    }
+    if indicators.macd {
+        data.push_str("- macd: 移动平均收敛散度指标（latest 中含 signal 与 histogram）\n");
+    }
+    if indicators.rsi {
         data.push_str("- rsi7/rsi14: 相对强弱指数\n");
+    }
+    if indicators.atr {
         data.push_str("- atr3/atr14: 平均真实波幅\n");
-        data.push_str("- taker_volume: 合约主动买入/卖出情况\n");
-        data.push_str("- long_short_account_ratio: 多空持仓账户数比率\n\n");
-        data.push_str("\n");
-        for entry in analytics.iter() {
-            data.push_str(&format!("### 产品: {} ({})\n", entry.inst_id, entry.symbol));
-            let market_json = build_market_analytics_json(&entry, timezone);
-            data.push_str("```json\n");
-            data.push_str(&market_json.to_string());
-            data.push_str("\n```\n\n");
-        }
     }
-
-    // Balance
-    data.push_str("## 账户情况:\n");
-    let balance_json = build_balance_json(snapshot);
-    data.push_str("```json\n");
-    data.push_str(&balance_json.to_string());
-    data.push_str("\n```\n\n");
-
-    data.push_str("## 业绩情况:\n");
-    if let Some(summary) = performance {
-        let perf_json = json!({
-            "overall": summary.overall.as_ref().map(build_performance_stats_json),
-            "recent": summary.recent.as_ref().map(build_performance_stats_json),
-        });
-        data.push_str("```json\n");
-        data.push_str(&perf_json.to_string());
-        data.push_str("\n```\n\n");
-    } else {
+    if indicators.change {
+        data.push_str("- change_pct_1h: 近 1 小时涨跌幅（百分比）\n");
+    }
+    data.push_str("- taker_volume: 合约主动买入/卖出情况\n");
+    data.push_str("- long_short_account_ratio: 多空持仓账户数比率\n\n");
+    data.push_str("\n");
+    for entry in analytics.iter() {
+        data.push_str(&format!("### 产品: {} ({})\n", entry.inst_id, entry.symbol));
+        let market_json = build_market_analytics_json(&entry, indicators, timezone);
         data.push_str("```json\n");
-        data.push_str("{}\n");
+        data.push_str(&market_json.to_string());
         data.push_str("\n```\n\n");
     }
-
-    // Positions
-    data.push_str("## 持仓情况:\n");
-    let positions_json = build_positions_json(snapshot, timezone);
-    data.push_str("持仓字段说明: \n");
-    data.push_str("- side: 持仓方向\n");
-    data.push_str("- size: 持仓数量(张数)\n");
-    data.push_str("- avg_price: 持仓均价(买入价格 USDT)\n");
-    data.push_str("- margin: 占用保证金(USDT)\n");
-    data.push_str("- unrealized_pnl: 未实现盈亏(USDT)\n");
-    data.push_str("- pnl_ratio_pct: 盈亏比例（百分比）\n");
-    data.push_str("```json\n");
-    data.push_str(&positions_json.to_string());
-    data.push_str("\n```\n\n");
-
-    // Open orders
-    data.push_str("## 挂单情况:\n");
-    data.push_str("```json\n");
-    data.push_str(&build_orders_json(snapshot, timezone).to_string());
-    data.push_str("\n```\n\n");
-
-    data.push_str("\n根据以上数据，请以要求的 JSON 格式提供您的交易决策。");
     data
 }
 
+fn build_instruments_json(
+    inst_ids: &[String],
+    markets: &HashMap<String, MarketInfo>,
+    leverages: &[InstrumentLeverage],
+) -> Value {
+    let instruments: Vec<Value> = inst_ids
+        .iter()
+        .map(|inst_id| {
+            let mut obj = json!({ "inst_id": inst_id });
+            if let Some(market) = markets.get(inst_id) {
+                obj["ct_val"] = json!(market.ct_val);
+                obj["max_leverage"] = json!(market.lever);
+            }
+            if let Some(leverage) = leverages.iter().find(|entry| &entry.inst_id == inst_id) {
+                obj["leverage"] = json!({
+                    "net": leverage.net,
+                    "long": leverage.long,
+                    "short": leverage.short,
+                });
+            }
+            obj
+        })
+        .collect();
+    json!(instruments)
+}
+
 fn build_balance_json(snapshot: &AccountSnapshot) -> Value {
     let mut balance_list = Vec::new();
     for balance in snapshot.balance.delta.iter().take(MAX_BALANCES) {
@@ -315,6 +510,9 @@ fn build_orders_json(snapshot: &AccountSnapshot, timezone: ConfiguredTimeZone) -
                     json!(format_float_2(trigger_px)),
                 );
             }
+            if let Some(callback) = &order.callback {
+                map.insert("trailing_callback".to_string(), json!(callback.label()));
+            }
             if let Some(limit_px) = order.price {
                 map.insert("limit_price".to_string(), json!(format_float_2(limit_px)));
             }
@@ -337,9 +535,10 @@ fn build_orders_json(snapshot: &AccountSnapshot, timezone: ConfiguredTimeZone) -
 
 fn build_market_analytics_json(
     entry: &InstrumentAnalytics,
+    indicators: IndicatorSet,
     _timezone: ConfiguredTimeZone,
 ) -> Value {
-    json!({
+    let mut value = json!({
         "symbol": entry.symbol,
         "inst_id": entry.inst_id,
         "current_price": optional_float(entry.current_price),
@@ -400,6 +599,46 @@ fn build_market_analytics_json(
                 "rsi14": format_series_json_2(&entry.swing_rsi14),
             }
         ]
+    });
+    value["latest"] = build_latest_indicators_json(&entry.intraday_5m_history);
+    if let Some(intervals) = value["indicators"].as_array_mut() {
+        for interval in intervals.iter_mut().filter_map(Value::as_object_mut) {
+            interval.retain(|key, _| indicators.keeps_field(key));
+        }
+    }
+    if let Some(latest) = value["latest"].as_object_mut() {
+        latest.retain(|key, _| indicators.keeps_field(key));
+    }
+    value
+}
+
+fn build_latest_indicators_json(closes: &[f64]) -> Value {
+    let series: Vec<(f64, f64)> = closes
+        .iter()
+        .enumerate()
+        .map(|(idx, close)| (idx as f64, *close))
+        .collect();
+    let macd = indicators::macd(
+        &series,
+        DEFAULT_MACD_FAST,
+        DEFAULT_MACD_SLOW,
+        DEFAULT_MACD_SIGNAL,
+    );
+    let latest = |points: &[(f64, f64)]| points.last().map(|(_, value)| format_float(*value));
+    let change_pct = closes
+        .len()
+        .checked_sub(PRICE_CHANGE_LOOKBACK + 1)
+        .map(|idx| closes[idx])
+        .filter(|base| *base > 0.0)
+        .zip(closes.last())
+        .map(|(base, last)| format_float_2((last - base) / base * 100.0));
+    json!({
+        "interval": "5m",
+        "rsi14": indicators::latest_rsi(&series, LATEST_RSI_PERIOD).map(format_float_2),
+        "macd": latest(&macd.macd),
+        "macd_signal": latest(&macd.signal),
+        "macd_histogram": latest(&macd.histogram),
+        "change_pct_1h": change_pct,
     })
 }
 fn build_long_short_account_ratio_json(ratios: &Vec<LongShortRatio>) -> Value {
@@ -492,6 +731,27 @@ mod tests {
     use super::*;
     use crate::command::{AccountBalance, AccountBalanceDelta, PendingOrderInfo, PositionInfo};
 
+    fn build_snapshot_prompt(
+        snapshot: &AccountSnapshot,
+        analytics: &[InstrumentAnalytics],
+        performance: Option<&PerformanceSummary>,
+        inst_ids: &[String],
+        markets: &HashMap<String, MarketInfo>,
+        leverages: &[InstrumentLeverage],
+        timezone: ConfiguredTimeZone,
+    ) -> String {
+        let sections = PromptSections::collect(
+            snapshot,
+            performance,
+            inst_ids,
+            markets,
+            leverages,
+            timezone,
+        )
+        .with_market_analytics(analytics, IndicatorSet::default(), timezone);
+        PromptTemplate::default().render(&sections)
+    }
+
     fn create_test_snapshot() -> AccountSnapshot {
         AccountSnapshot {
             positions: vec![PositionInfo {
@@ -504,6 +764,7 @@ mod tests {
                 upl_ratio: Some(0.01),
                 imr: 5000.0,
                 create_time: Some(1700000000000),
+                mgn_mode: None,
             }],
             open_orders: vec![PendingOrderInfo {
                 inst_id: "BTC-USDT-SWAP".to_string(),
@@ -512,6 +773,7 @@ mod tests {
                 pos_side: Some("long".to_string()),
                 price: Some(49000.0),
                 size: 1.0,
+                filled_size: 0.0,
                 state: "live".to_string(),
                 reduce_only: false,
                 tag: Some(AI_TAG_ENTRY.to_string()),
@@ -519,6 +781,7 @@ mod tests {
                 trigger_price: None,
                 kind: crate::command::TradeOrderKind::Regular,
                 create_time: Some(1700000000000),
+                callback: None,
             }],
             balance: AccountBalance {
                 total_equity: Some(10000.0),
@@ -551,6 +814,7 @@ mod tests {
             intraday_3m_rsi7: vec![60.0, 62.0, 65.0],
             intraday_3m_rsi14: vec![58.0, 60.0, 63.0],
             intraday_5m_prices: vec![50000.0, 50200.0, 50500.0],
+            intraday_5m_history: (0..60).map(|idx| 50000.0 + idx as f64 * 10.0).collect(),
             intraday_5m_ema20: vec![49900.0, 50000.0, 50100.0],
             intraday_5m_macd: vec![8.0, 9.0, 10.5],
             intraday_5m_rsi7: vec![60.0, 62.0, 65.0],
@@ -584,6 +848,7 @@ mod tests {
             MarketInfo {
                 ct_val: 0.01,
                 lever: 100.0,
+                ..Default::default()
             },
         );
         let leverages = vec![InstrumentLeverage {
@@ -665,4 +930,58 @@ mod tests {
         assert!(!result.is_empty());
         assert!(result.contains("账户情况"));
     }
+
+    #[test]
+    fn test_prompt_template_rejects_unknown_placeholder() {
+        let err = PromptTemplate::parse("持仓 {positions} 余额 {balanse}".to_string())
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("未知占位符 {balanse}，"));
+    }
+
+    #[test]
+    fn test_prompt_template_renders_placeholders() {
+        let template = PromptTemplate::parse(
+            "规则: 只做多\n{instruments}\n示例 {\"action\": \"hold\"} {}\n{positions}".to_string(),
+        )
+        .unwrap();
+        let sections = PromptSections::collect(
+            &create_test_snapshot(),
+            None,
+            &["BTC-USDT-SWAP".to_string()],
+            &HashMap::new(),
+            &[],
+            ConfiguredTimeZone::Local,
+        );
+        let result = template.render(&sections);
+
+        assert!(result.starts_with("规则: 只做多\n[{\"inst_id\":\"BTC-USDT-SWAP\"}]"));
+        assert!(result.contains("示例 {\"action\": \"hold\"} {}"));
+        assert!(result.contains("\"avg_price\":50000.0"));
+    }
+
+    #[test]
+    fn test_indicator_set_trims_market_analytics() {
+        let analytics = create_test_analytics();
+        let full = build_market_analytics_json(
+            &analytics[0],
+            IndicatorSet::default(),
+            ConfiguredTimeZone::Local,
+        );
+        assert!(full["latest"]["rsi14"].is_number());
+        assert!(full["latest"]["macd_histogram"].is_number());
+        assert!(full["latest"]["change_pct_1h"].as_f64().unwrap() > 0.0);
+
+        let trimmed = build_market_analytics_json(
+            &analytics[0],
+            IndicatorSet::from_names(&["rsi"]),
+            ConfiguredTimeZone::Local,
+        );
+        assert!(trimmed["latest"]["rsi14"].is_number());
+        assert!(trimmed["latest"].get("macd").is_none());
+        assert!(trimmed["latest"].get("change_pct_1h").is_none());
+        assert!(trimmed["indicators"][4].get("ema20").is_none());
+        assert!(trimmed["indicators"][4].get("atr14").is_none());
+        assert!(trimmed["indicators"][4]["rsi14"].is_array());
+    }
 }
@@ -2,17 +2,111 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use crate::okx::MarketInfo;
+use crate::okx::{LongShortRatio, MarketInfo};
 
 #[derive(Debug, Clone)]
 pub enum Command {
     MarkPriceUpdate(String, f64, i64, usize),
     Notify(String, String),
     AiInsight(AiInsightRecord),
+    AiInsightPartial(Option<String>),
     Error(String),
     TradeResult(TradeEvent),
     AccountSnapshot(AccountSnapshot),
     MarketsLoaded(HashMap<String, MarketInfo>),
+    FundingRate(FundingRateInfo),
+    OrderBook(String, OrderBookLevels),
+    // 同一条 WS 消息中的逐笔成交合并为一条命令，避免挤占广播通道
+    PublicTrades(String, Vec<PublicTrade>),
+    Ticker(TickerInfo),
+    LongShortRatio(String, Vec<LongShortRatio>),
+    SetThreshold(String, PriceThreshold),
+    AddInstrument(String),
+    InstrumentAdded(String, f64, usize),
+    InstrumentRejected(String, String),
+    RemoveInstrument(String),
+    CancelAllCompleted(String),
+    // WebSocket 连接状态：频道（公共频道名或 private/business）、连接编号与是否已连接
+    ConnState(String, u32, bool),
+    ResyncAccount,
+    // true 时账户快照与推送不再按图表合约过滤
+    ShowAllPositions(bool),
+    // 图表外合约（如全部持仓模式下的持仓）补充拉取的合约信息
+    MarketsAdded(HashMap<String, MarketInfo>),
+    FetchOrderHistory(HistoryRequest),
+    OrderHistory(Result<OrderHistory, String>),
+    AiDecisionProposed(AiDecisionProposal),
+    AiDecisionResolved(i64, bool),
+    AiAnalyzeNow,
+    AiAnalysisRunning(bool),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PriceThreshold {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lower: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upper: Option<f64>,
+    // 相对设置时价格的涨跌幅（百分比），触发后以当前价重新计算
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_pct: Option<f64>,
+    // 逐笔 ATR 超过该值（价格单位）时提醒波动放大
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub atr: Option<f64>,
+    // 覆盖全局 --alert-cooldown，同一上下限在该秒数内只提醒一次
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<u64>,
+}
+
+impl PriceThreshold {
+    pub fn is_empty(&self) -> bool {
+        self.lower.is_none()
+            && self.upper.is_none()
+            && self.change_pct.is_none()
+            && self.atr.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OrderBookLevels {
+    pub bids: Vec<BookLevel>,
+    pub asks: Vec<BookLevel>,
+    pub ts: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingRateInfo {
+    pub inst_id: String,
+    pub rate: f64,
+    pub next_rate: Option<f64>,
+    pub funding_time: i64,
+    pub next_funding_time: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PublicTrade {
+    pub price: f64,
+    pub size: f64,
+    pub side: TradeSide,
+    pub ts: i64,
+}
+
+// 24 小时行情统计，成交量统一按基础币计
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickerInfo {
+    pub inst_id: String,
+    pub last: Option<f64>,
+    pub open_24h: Option<f64>,
+    pub high_24h: Option<f64>,
+    pub low_24h: Option<f64>,
+    pub volume_24h: Option<f64>,
+    pub ts: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +115,32 @@ pub struct AiInsightRecord {
     pub system_prompt: String,
     pub user_prompt: String,
     pub response: String,
+    pub usage: Option<AiTokenUsage>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AiDecisionProposal {
+    pub id: i64,
+    pub operator: String,
+    pub operations: Vec<String>,
+    pub expires_at_ms: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AiTokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    // 按配置的每千 token 单价估算，未配置单价时为 0
+    #[serde(default)]
+    pub cost: f64,
+}
+
+impl AiTokenUsage {
+    pub fn add(&mut self, other: &AiTokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,6 +178,7 @@ pub enum TradeOrderKind {
     Regular,
     TakeProfit,
     StopLoss,
+    TrailingStop,
 }
 
 impl Default for TradeOrderKind {
@@ -66,6 +187,55 @@ impl Default for TradeOrderKind {
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TrailingCallback {
+    Ratio(f64),
+    Spread(f64),
+}
+
+impl TrailingCallback {
+    pub fn label(&self) -> String {
+        match self {
+            TrailingCallback::Ratio(ratio) => format!("{:.2}%", ratio * 100.0),
+            TrailingCallback::Spread(spread) => format!("{spread}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct BracketOrder {
+    pub take_profit: Option<f64>,
+    pub stop_loss: Option<f64>,
+}
+
+impl BracketOrder {
+    pub fn is_empty(&self) -> bool {
+        self.take_profit.is_none() && self.stop_loss.is_none()
+    }
+
+    // 止盈须在盈利方向、止损须在亏损方向
+    pub fn validate(&self, side: TradeSide, entry_price: f64) -> Result<(), &'static str> {
+        if entry_price <= 0.0 {
+            return Err("缺少入场价格，无法校验止盈/止损");
+        }
+        if let Some(tp) = self.take_profit {
+            match side {
+                TradeSide::Buy if tp <= entry_price => return Err("买入时止盈价须高于入场价"),
+                TradeSide::Sell if tp >= entry_price => return Err("卖出时止盈价须低于入场价"),
+                _ => {}
+            }
+        }
+        if let Some(sl) = self.stop_loss {
+            match side {
+                TradeSide::Buy if sl >= entry_price => return Err("买入时止损价须低于入场价"),
+                TradeSide::Sell if sl <= entry_price => return Err("卖出时止损价须高于入场价"),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TradeOperator {
     Manual,
@@ -85,10 +255,108 @@ impl TradeOperator {
         }
     }
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 pub enum TradeOrderType {
     Market,
+    #[default]
     Limit,
+    PostOnly,
+    Ioc,
+    Fok,
+}
+
+impl TradeOrderType {
+    pub fn as_okx_type(&self) -> &'static str {
+        match self {
+            TradeOrderType::Market => "market",
+            TradeOrderType::Limit => "limit",
+            TradeOrderType::PostOnly => "post_only",
+            TradeOrderType::Ioc => "ioc",
+            TradeOrderType::Fok => "fok",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TradeOrderType::Market => "市价",
+            TradeOrderType::Limit => "限价",
+            TradeOrderType::PostOnly => "只挂单",
+            TradeOrderType::Ioc => "IOC",
+            TradeOrderType::Fok => "FOK",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            TradeOrderType::Limit => TradeOrderType::Market,
+            TradeOrderType::Market => TradeOrderType::PostOnly,
+            TradeOrderType::PostOnly => TradeOrderType::Ioc,
+            TradeOrderType::Ioc => TradeOrderType::Fok,
+            TradeOrderType::Fok => TradeOrderType::Limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MarginMode {
+    Cross,
+    Isolated,
+}
+
+impl MarginMode {
+    pub fn as_okx_mode(&self) -> &'static str {
+        match self {
+            MarginMode::Cross => "cross",
+            MarginMode::Isolated => "isolated",
+        }
+    }
+
+    pub fn from_okx(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "cross" => Some(MarginMode::Cross),
+            "isolated" => Some(MarginMode::Isolated),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MarginMode::Cross => "全仓",
+            MarginMode::Isolated => "逐仓",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            MarginMode::Cross => MarginMode::Isolated,
+            MarginMode::Isolated => MarginMode::Cross,
+        }
+    }
+}
+
+// 账户持仓模式：双向持仓 (long_short_mode) 需要 posSide，单向持仓 (net_mode) 不传
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PositionMode {
+    #[default]
+    LongShort,
+    Net,
+}
+
+impl PositionMode {
+    pub fn from_okx(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "long_short_mode" => Some(PositionMode::LongShort),
+            "net_mode" => Some(PositionMode::Net),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PositionMode::LongShort => "双向持仓",
+            PositionMode::Net => "单向持仓",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -106,6 +374,15 @@ pub struct TradeRequest {
     pub leverage: Option<f64>,
     #[serde(default)]
     pub kind: TradeOrderKind,
+    #[serde(default)]
+    pub margin_mode: Option<MarginMode>,
+    #[serde(default)]
+    pub callback: Option<TrailingCallback>,
+    #[serde(default)]
+    pub bracket: Option<BracketOrder>,
+    // 客户端订单号，未指定时由交易客户端在发送前生成
+    #[serde(default)]
+    pub cl_ord_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -115,6 +392,8 @@ pub struct TradeResponse {
     pub price: f64,
     pub size: f64,
     pub order_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
     pub message: String,
     pub success: bool,
     pub operator: TradeOperator,
@@ -124,6 +403,16 @@ pub struct TradeResponse {
     pub leverage: Option<f64>,
     #[serde(default)]
     pub kind: TradeOrderKind,
+    #[serde(default)]
+    pub ord_type: Option<TradeOrderType>,
+    #[serde(default)]
+    pub bracket: Option<BracketOrder>,
+    // 失败时 OKX 返回的原始错误码
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    // --dry-run 模式下本地合成的结果，未发送至 OKX
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -147,6 +436,9 @@ pub struct TradeFill {
     pub fee_currency: Option<String>,
     #[serde(default)]
     pub pnl: Option<f64>,
+    // 本笔成交的已实现盈亏；pnl 是订单维度的累计值
+    #[serde(default)]
+    pub fill_pnl: Option<f64>,
     #[serde(default)]
     pub acc_fill_size: Option<f64>,
     #[serde(default)]
@@ -155,19 +447,30 @@ pub struct TradeFill {
     pub leverage: Option<f64>,
     #[serde(default)]
     pub tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cl_ord_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum TradeEvent {
     Order(TradeResponse),
+    Amend(TradeResponse),
     Cancel(CancelResponse),
-    Fill(TradeFill),
+    Fill(Box<TradeFill>),
 }
 
 impl TradeEvent {
+    pub fn inst_id(&self) -> &str {
+        match self {
+            TradeEvent::Order(response) | TradeEvent::Amend(response) => &response.inst_id,
+            TradeEvent::Cancel(cancel) => &cancel.inst_id,
+            TradeEvent::Fill(fill) => &fill.inst_id,
+        }
+    }
+
     pub fn leverage_hint(&self) -> Option<f64> {
         match self {
-            TradeEvent::Order(response) => response.leverage,
+            TradeEvent::Order(response) | TradeEvent::Amend(response) => response.leverage,
             TradeEvent::Cancel(_) => None,
             TradeEvent::Fill(fill) => fill.leverage,
         }
@@ -177,10 +480,28 @@ impl TradeEvent {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TradingCommand {
     Place(TradeRequest),
+    Amend(AmendOrderRequest),
     Cancel(CancelOrderRequest),
+    CancelAll(CancelAllRequest),
     SetLeverage(SetLeverageRequest),
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmendOrderRequest {
+    pub inst_id: String,
+    pub ord_id: String,
+    pub side: TradeSide,
+    pub new_price: f64,
+    pub new_size: f64,
+    pub operator: TradeOperator,
+    pub pos_side: Option<String>,
+    #[serde(default)]
+    pub leverage: Option<f64>,
+    // 止盈/止损策略单走 amend-algos 原地修改触发价
+    #[serde(default)]
+    pub kind: TradeOrderKind,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CancelOrderRequest {
     pub inst_id: String,
@@ -191,6 +512,12 @@ pub struct CancelOrderRequest {
     pub kind: TradeOrderKind,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelAllRequest {
+    pub inst_id: String,
+    pub operator: TradeOperator,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CancelResponse {
     pub inst_id: String,
@@ -200,6 +527,10 @@ pub struct CancelResponse {
     pub operator: TradeOperator,
     #[serde(default)]
     pub pos_side: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -207,6 +538,8 @@ pub struct SetLeverageRequest {
     pub inst_id: String,
     pub lever: f64,
     pub pos_side: Option<String>,
+    #[serde(default)]
+    pub margin_mode: Option<MarginMode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -248,6 +581,8 @@ pub struct PositionInfo {
     pub imr: f64,
     #[serde(default)]
     pub create_time: Option<i64>,
+    #[serde(default)]
+    pub mgn_mode: Option<MarginMode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -258,6 +593,9 @@ pub struct PendingOrderInfo {
     pub pos_side: Option<String>,
     pub price: Option<f64>,
     pub size: f64,
+    // 累计成交数量（accFillSz），部分成交时小于 size，策略委托恒为 0
+    #[serde(default)]
+    pub filled_size: f64,
     pub state: String,
     pub reduce_only: bool,
     pub tag: Option<String>,
@@ -268,4 +606,48 @@ pub struct PendingOrderInfo {
     pub kind: TradeOrderKind,
     #[serde(default)]
     pub create_time: Option<i64>,
+    #[serde(default)]
+    pub callback: Option<TrailingCallback>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryRequest {
+    pub inst_ids: Vec<String>,
+    pub begin_ms: i64,
+    pub end_ms: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OrderHistory {
+    pub orders: Vec<HistoricalOrderInfo>,
+    pub closed_positions: Vec<ClosedPositionInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoricalOrderInfo {
+    pub inst_id: String,
+    pub ord_id: String,
+    pub side: String,
+    pub pos_side: Option<String>,
+    pub ord_type: String,
+    pub price: Option<f64>,
+    pub avg_price: Option<f64>,
+    pub size: f64,
+    pub filled_size: f64,
+    pub state: String,
+    pub pnl: Option<f64>,
+    pub update_time: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClosedPositionInfo {
+    pub inst_id: String,
+    pub pos_side: Option<String>,
+    pub mgn_mode: Option<MarginMode>,
+    pub lever: Option<f64>,
+    pub open_avg_px: Option<f64>,
+    pub close_avg_px: Option<f64>,
+    pub size: f64,
+    pub realized_pnl: Option<f64>,
+    pub close_time: Option<i64>,
 }
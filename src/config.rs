@@ -1,17 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
 use std::io::ErrorKind;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Duration;
 
 use anyhow::{Context, Result as AnyResult, anyhow};
-use chrono::{FixedOffset, Local, TimeZone, Utc};
+use chrono::{FixedOffset, Local, SecondsFormat, TimeZone, Utc};
 use chrono_tz::Tz;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
-#[derive(Parser, Clone, Debug)]
+use crate::command::{PositionMode, PriceThreshold};
+use crate::export;
+use crate::okx;
+use crate::theme::{THEME_NAMES, Theme};
+use crate::trade_log::TradeLogRetention;
+
+#[derive(Parser, Clone)]
 pub struct CliParams {
     /// Instrument IDs to monitor; comma separated or pass multiple times
     #[clap(
@@ -27,10 +34,70 @@ pub struct CliParams {
     #[clap(long = "threshold", value_name = "INST:LOWER:UPPER")]
     pub thresholds: Vec<ThresholdSpec>,
 
-    /// Amount of history the TUI keeps in memory (e.g., 15m, 1h, 1d)
+    /// Amount of history bootstrapped from OKX at launch (e.g., 15m, 1h, 1d)
     #[clap(long = "window", value_name = "DURATION", default_value = "15m")]
     pub window: DurationSpec,
 
+    /// Amount of price history the chart keeps live in memory (defaults to --window)
+    #[clap(long = "retention", env = "CHART_RETENTION", value_name = "DURATION")]
+    pub retention: Option<DurationSpec>,
+
+    /// Force the candle bar used to bootstrap history (e.g., 1m, 15m, 1H); auto when unset
+    #[clap(
+        long = "history-bar",
+        env = "HISTORY_BAR",
+        value_name = "BAR",
+        value_parser = parse_history_bar
+    )]
+    pub history_bar: Option<String>,
+
+    /// Proxy for all OKX REST and WebSocket traffic (http://, https://, socks5:// or socks5h:// URL)
+    #[clap(long = "proxy", env = "HTTPS_PROXY", value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// Sampling period for the contract long/short account ratio (5m, 1H or 1D)
+    #[clap(
+        long = "long-short-period",
+        env = "LONG_SHORT_PERIOD",
+        default_value = "5m",
+        value_parser = ["5m", "1H", "1D"]
+    )]
+    pub long_short_period: String,
+
+    /// Minimum gap between TUI redraws (e.g., 100ms, 250ms; between 16ms and 2s)
+    #[clap(
+        long = "redraw-gap",
+        env = "REDRAW_GAP",
+        value_name = "DURATION",
+        default_value = "100ms",
+        value_parser = parse_redraw_gap
+    )]
+    pub redraw_gap: DurationSpec,
+
+    /// Mark a price as stale when no update arrived for this long (e.g., 30s, 2m; between 1s and 1h)
+    #[clap(
+        long = "stale-price-after",
+        env = "STALE_PRICE_AFTER",
+        value_name = "DURATION",
+        default_value = "30s",
+        value_parser = parse_stale_price_after
+    )]
+    pub stale_price_after: DurationSpec,
+
+    /// Minimum gap between repeated alerts for the same price bound (e.g., 30s, 5m; at most 24h)
+    #[clap(
+        long = "alert-cooldown",
+        env = "ALERT_COOLDOWN",
+        value_name = "DURATION",
+        default_value = "5m",
+        value_parser = parse_alert_cooldown
+    )]
+    pub alert_cooldown: DurationSpec,
+
+    /// Account profile from config.json to load OKX credentials from
+    #[clap(long = "profile", env = "OKX_PROFILE", value_name = "NAME")]
+    pub profile: Option<String>,
+
     /// OKX API key used for authenticated trading
     #[clap(long = "okx-api-key", env = "OKX_API_KEY")]
     pub okx_api_key: Option<String>,
@@ -43,6 +110,122 @@ pub struct CliParams {
     #[clap(long = "okx-api-passphrase", env = "OKX_API_PASSPHRASE")]
     pub okx_api_passphrase: Option<String>,
 
+    /// Log orders locally as simulated successes without sending them to OKX
+    #[clap(long = "dry-run", env = "DRY_RUN")]
+    pub dry_run: bool,
+
+    /// Interval between full account snapshot reconciliations (e.g., 60s, 5m; minimum 10s)
+    #[clap(
+        long = "account-resync-interval",
+        env = "ACCOUNT_RESYNC_INTERVAL",
+        value_name = "DURATION",
+        default_value = "60s",
+        value_parser = parse_account_resync_interval
+    )]
+    pub account_resync_interval: DurationSpec,
+
+    /// Append a health summary to health_logs.jsonl at this interval (e.g., 5m; minimum 10s)
+    #[clap(
+        long = "health-log-interval",
+        env = "HEALTH_LOG_INTERVAL",
+        value_name = "DURATION",
+        value_parser = parse_health_log_interval
+    )]
+    pub health_log_interval: Option<DurationSpec>,
+
+    /// Maximum attempts for signed OKX GET requests on timeouts, 429 or 5xx (1 disables retries)
+    #[clap(
+        long = "rest-max-attempts",
+        env = "REST_MAX_ATTEMPTS",
+        value_name = "COUNT",
+        default_value_t = 3,
+        value_parser = clap::value_parser!(u32).range(1..=10)
+    )]
+    pub rest_max_attempts: u32,
+
+    /// Base delay before retrying a signed OKX GET, doubled on each retry (e.g., 500ms, 2s)
+    #[clap(
+        long = "rest-retry-delay",
+        env = "REST_RETRY_DELAY",
+        value_name = "DURATION",
+        default_value = "500ms",
+        value_parser = parse_retry_delay
+    )]
+    pub rest_retry_delay: DurationSpec,
+
+    /// Random jitter applied to retry delays as a fraction of the delay (0 to 1)
+    #[clap(
+        long = "rest-retry-jitter",
+        env = "REST_RETRY_JITTER",
+        value_name = "RATIO",
+        default_value_t = 0.2,
+        value_parser = parse_retry_jitter
+    )]
+    pub rest_retry_jitter: f64,
+
+    /// Cancel all resting orders of the tracked instruments before exiting (opt-in)
+    #[clap(long = "cancel-orders-on-exit", env = "CANCEL_ORDERS_ON_EXIT")]
+    pub cancel_orders_on_exit: bool,
+
+    /// Maximum time to wait for exit cancellations to be acknowledged (e.g., 5s; between 1s and 60s)
+    #[clap(
+        long = "exit-cancel-timeout",
+        env = "EXIT_CANCEL_TIMEOUT",
+        value_name = "DURATION",
+        default_value = "5s",
+        value_parser = parse_exit_cancel_timeout
+    )]
+    pub exit_cancel_timeout: DurationSpec,
+
+    /// Show a confirmation popup with a risk summary before submitting manual orders
+    #[clap(long = "confirm-orders", env = "CONFIRM_ORDERS")]
+    pub confirm_orders: bool,
+
+    /// Capture the mouse to focus panels and select rows by clicking (disables terminal text selection)
+    #[clap(long = "mouse", env = "MOUSE")]
+    pub mouse: bool,
+
+    /// Record incoming mark prices, snapshots and fills to a JSONL file for later replay
+    #[clap(
+        long = "record",
+        env = "RECORD_FILE",
+        value_name = "PATH",
+        conflicts_with = "replay"
+    )]
+    pub record: Option<PathBuf>,
+
+    /// Replay a recorded session instead of connecting to OKX
+    #[clap(long = "replay", env = "REPLAY_FILE", value_name = "PATH")]
+    pub replay: Option<PathBuf>,
+
+    /// Playback speed multiplier for --replay (between 0.1 and 100)
+    #[clap(
+        long = "replay-speed",
+        env = "REPLAY_SPEED",
+        value_name = "FACTOR",
+        default_value_t = 1.0,
+        value_parser = parse_replay_speed
+    )]
+    pub replay_speed: f64,
+
+    /// Reject orders whose notional value (price * size) exceeds this amount
+    #[clap(
+        long = "max-order-notional",
+        env = "MAX_ORDER_NOTIONAL",
+        value_name = "AMOUNT",
+        value_parser = parse_positive_limit
+    )]
+    pub max_order_notional: Option<f64>,
+
+    /// Reject orders with leverage above this value
+    #[clap(
+        long = "max-leverage",
+        env = "MAX_LEVERAGE",
+        value_name = "LEVERAGE",
+        value_parser = parse_positive_limit
+    )]
+    pub max_leverage: Option<f64>,
+
     /// Trading mode for OKX orders (cash, cross, or isolated)
     #[clap(
         long = "okx-td-mode",
@@ -51,16 +234,56 @@ pub struct CliParams {
     )]
     pub okx_td_mode: String,
 
+    /// Use OKX demo trading (simulated funds) instead of the live account
+    #[clap(long = "okx-demo", env = "OKX_DEMO")]
+    pub okx_demo: bool,
+
+    /// Webhook URL that receives alerts and errors as JSON POST requests
+    #[clap(long = "notify-webhook", env = "NOTIFY_WEBHOOK_URL")]
+    pub notify_webhook: Option<String>,
+
+    /// Telegram bot token used to push alerts and errors
+    #[clap(long = "telegram-bot-token", env = "TELEGRAM_BOT_TOKEN")]
+    pub telegram_bot_token: Option<String>,
+
+    /// Telegram chat ID that receives alerts and errors
+    #[clap(long = "telegram-chat-id", env = "TELEGRAM_CHAT_ID")]
+    pub telegram_chat_id: Option<String>,
+
+    /// Ring the terminal bell when an alert or error is broadcast
+    #[clap(long = "alert-bell", env = "ALERT_BELL")]
+    pub alert_bell: bool,
+
+    /// Sound file played for threshold alerts instead of the bell
+    #[clap(long = "alert-sound", env = "ALERT_SOUND", value_name = "PATH")]
+    pub alert_sound: Option<PathBuf>,
+
+    /// Sound file played for errors instead of the bell
+    #[clap(
+        long = "alert-error-sound",
+        env = "ALERT_ERROR_SOUND",
+        value_name = "PATH"
+    )]
+    pub alert_error_sound: Option<PathBuf>,
+
+    /// Command used to play alert sound files (default afplay on macOS, paplay elsewhere)
+    #[clap(
+        long = "alert-sound-player",
+        env = "ALERT_SOUND_PLAYER",
+        value_name = "COMMAND"
+    )]
+    pub alert_sound_player: Option<String>,
+
     /// Deepseek API key used for AI analysis of account states
     #[clap(long = "deepseek-api-key", env = "DEEPSEEK_API_KEY")]
     pub deepseek_api_key: Option<String>,
 
-    /// AI provider used for analysis (deepseek or openrouter)
+    /// AI provider used for analysis (deepseek, openai, ollama or openrouter)
     #[clap(
         long = "ai-provider",
         env = "AI_PROVIDER",
         default_value = "deepseek",
-        value_parser = ["deepseek", "openrouter"]
+        value_parser = ["deepseek", "openai", "ollama", "openrouter"]
     )]
     pub ai_provider: String,
 
@@ -100,11 +323,133 @@ pub struct CliParams {
     )]
     pub openrouter_endpoint: String,
 
-    /// Interval between AI decisions (e.g., 5m, 15m)
+    /// OpenAI (or Azure OpenAI / compatible gateway) API key
+    #[clap(long = "openai-api-key", env = "OPENAI_API_KEY")]
+    pub openai_api_key: Option<String>,
+
+    /// OpenAI model (or Azure deployment) name for chat completions
+    #[clap(
+        long = "openai-model",
+        env = "OPENAI_MODEL",
+        default_value = "gpt-4o-mini"
+    )]
+    pub openai_model: String,
+
+    /// OpenAI endpoint base URL (default https://api.openai.com/v1)
+    #[clap(
+        long = "openai-endpoint",
+        env = "OPENAI_API_BASE",
+        default_value = "https://api.openai.com/v1"
+    )]
+    pub openai_endpoint: String,
+
+    /// Optional API key for Ollama / LM Studio servers behind an auth proxy
+    #[clap(long = "ollama-api-key", env = "OLLAMA_API_KEY")]
+    pub ollama_api_key: Option<String>,
+
+    /// Ollama / LM Studio model name for chat completions
+    #[clap(
+        long = "ollama-model",
+        env = "OLLAMA_MODEL",
+        default_value = "llama3.1"
+    )]
+    pub ollama_model: String,
+
+    /// Ollama / LM Studio OpenAI-compatible base URL (default http://localhost:11434/v1)
+    #[clap(
+        long = "ollama-endpoint",
+        env = "OLLAMA_API_BASE",
+        default_value = "http://localhost:11434/v1"
+    )]
+    pub ollama_endpoint: String,
+
+    /// Price per 1k prompt tokens used to estimate AI cost
     #[clap(
-        long = "decision_interval",
+        long = "ai-prompt-price",
+        env = "AI_PROMPT_PRICE_PER_1K",
+        value_name = "PRICE_PER_1K",
+        default_value_t = 0.0
+    )]
+    pub ai_prompt_price: f64,
+
+    /// Price per 1k completion tokens used to estimate AI cost
+    #[clap(
+        long = "ai-completion-price",
+        env = "AI_COMPLETION_PRICE_PER_1K",
+        value_name = "PRICE_PER_1K",
+        default_value_t = 0.0
+    )]
+    pub ai_completion_price: f64,
+
+    /// Prompt template file with placeholders such as {positions}, {balance}, {indicators}
+    #[clap(
+        long = "ai-prompt-template",
+        env = "AI_PROMPT_TEMPLATE",
+        value_name = "PATH"
+    )]
+    pub ai_prompt_template: Option<PathBuf>,
+
+    /// Indicators included in the AI prompt; comma separated (ema, macd, rsi, atr, change)
+    #[clap(
+        long = "ai-indicators",
+        env = "AI_INDICATORS",
+        value_delimiter = ',',
+        num_args = 1..,
+        value_parser = ["ema", "macd", "rsi", "atr", "change"],
+        default_values_t = ["ema", "macd", "rsi", "atr", "change"].map(String::from)
+    )]
+    pub ai_indicators: Vec<String>,
+
+    /// Instruments the AI analyzes; comma separated subset of --inst-id (default: all charted)
+    #[clap(
+        long = "ai-inst-id",
+        env = "AI_INST_IDS",
+        value_name = "INST",
+        value_delimiter = ','
+    )]
+    pub ai_inst_ids: Vec<String>,
+
+    /// Stream AI responses via SSE so the panel shows text as it arrives
+    #[clap(long = "ai-stream", env = "AI_STREAM")]
+    pub ai_stream: bool,
+
+    /// Execute AI decisions automatically (each decision needs confirmation in the UI)
+    #[clap(long = "ai-autotrade", env = "AI_AUTOTRADE")]
+    pub ai_autotrade: bool,
+
+    /// Execute AI decisions without asking for confirmation (requires --ai-autotrade)
+    #[clap(
+        long = "ai-autotrade-unattended",
+        env = "AI_AUTOTRADE_UNATTENDED",
+        requires = "ai_autotrade"
+    )]
+    pub ai_autotrade_unattended: bool,
+
+    /// Reject AI entry orders larger than this size (contracts)
+    #[clap(
+        long = "ai-max-order-size",
+        env = "AI_MAX_ORDER_SIZE",
+        value_name = "SIZE"
+    )]
+    pub ai_max_order_size: Option<f64>,
+
+    /// Reject AI entry orders with leverage above this value
+    #[clap(
+        long = "ai-max-leverage",
+        env = "AI_MAX_LEVERAGE",
+        value_name = "LEVERAGE"
+    )]
+    pub ai_max_leverage: Option<f64>,
+
+    /// Interval between AI decisions (e.g., 300s, 5m, 15m; minimum 30s)
+    #[clap(
+        long = "ai-interval",
+        visible_alias = "deepseek-interval",
+        alias = "decision_interval",
+        env = "AI_INTERVAL",
         value_name = "DURATION",
-        default_value = "5m"
+        default_value = "5m",
+        value_parser = parse_ai_interval
     )]
     decision_interval: DurationSpec,
 }
@@ -158,10 +503,19 @@ impl FromStr for ThresholdSpec {
 }
 
 impl CliParams {
-    pub fn threshold_map(&self) -> HashMap<String, (f64, f64)> {
+    pub fn threshold_map(&self) -> HashMap<String, PriceThreshold> {
         let mut map = HashMap::new();
         for spec in &self.thresholds {
-            map.insert(spec.inst_id.clone(), (spec.lower, spec.upper));
+            map.insert(
+                spec.inst_id.clone(),
+                PriceThreshold {
+                    lower: Some(spec.lower),
+                    upper: Some(spec.upper),
+                    change_pct: None,
+                    atr: None,
+                    cooldown_secs: None,
+                },
+            );
         }
         map
     }
@@ -170,6 +524,55 @@ impl CliParams {
         self.window.as_duration()
     }
 
+    pub fn chart_retention(&self) -> Duration {
+        self.retention
+            .map_or_else(|| self.history_window(), |spec| spec.as_duration())
+    }
+
+    // 凭证优先级：命令行参数 > 环境变量 > config.json 中的 profile
+    pub fn resolve_credentials(&mut self, profile: Option<&AccountProfile>) -> AnyResult<()> {
+        let fill = |target: &mut Option<String>, value: Option<String>| {
+            if target.as_deref().is_none_or(|v| v.trim().is_empty()) {
+                *target = value.filter(|v| !v.trim().is_empty());
+            }
+        };
+        fill(
+            &mut self.okx_api_passphrase,
+            std::env::var(PASSPHRASE_ENV_FALLBACK).ok(),
+        );
+        if let Some(profile) = profile {
+            fill(&mut self.okx_api_key, profile.api_key.clone());
+            fill(&mut self.okx_api_secret, profile.api_secret.clone());
+            fill(&mut self.okx_api_passphrase, profile.passphrase.clone());
+            if profile.demo {
+                self.okx_demo = true;
+            }
+        }
+        let missing: Vec<&str> = [
+            ("API key (--okx-api-key / OKX_API_KEY)", &self.okx_api_key),
+            (
+                "API secret (--okx-api-secret / OKX_API_SECRET)",
+                &self.okx_api_secret,
+            ),
+            (
+                "passphrase (--okx-api-passphrase / OKX_API_PASSPHRASE / OKX_PASSPHRASE)",
+                &self.okx_api_passphrase,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.as_deref().is_none_or(|v| v.trim().is_empty()))
+        .map(|(label, _)| label)
+        .collect();
+        // 三项都缺失表示只看行情；部分缺失多半是漏配，直接报错而不是静默关闭交易
+        if missing.is_empty() || missing.len() == 3 {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "OKX API 凭证不完整，缺少：{}。凭证按 命令行参数 > 环境变量 > config.json profile 的优先级读取",
+            missing.join("、")
+        ))
+    }
+
     pub fn trading_config(&self) -> Option<TradingConfig> {
         let api_key = self.okx_api_key.as_ref()?.trim();
         let api_secret = self.okx_api_secret.as_ref()?.trim();
@@ -182,9 +585,109 @@ impl CliParams {
             api_secret: api_secret.to_string(),
             passphrase: passphrase.to_string(),
             td_mode: self.okx_td_mode.clone(),
+            position_mode: PositionMode::default(),
+            demo: self.okx_demo,
+            dry_run: self.dry_run,
+        })
+    }
+
+    pub fn notifier_config(&self) -> NotifierConfig {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let telegram = match (
+            non_empty(&self.telegram_bot_token),
+            non_empty(&self.telegram_chat_id),
+        ) {
+            (Some(bot_token), Some(chat_id)) => Some(TelegramConfig { bot_token, chat_id }),
+            _ => None,
+        };
+        NotifierConfig {
+            webhook_url: non_empty(&self.notify_webhook),
+            telegram,
+        }
+    }
+
+    pub fn alert_sound_config(&self) -> AlertSoundConfig {
+        let player = self
+            .alert_sound_player
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .unwrap_or(DEFAULT_SOUND_PLAYER)
+            .to_string();
+        AlertSoundConfig {
+            bell: self.alert_bell,
+            info_sound: self.alert_sound.clone(),
+            error_sound: self.alert_error_sound.clone(),
+            player,
+        }
+    }
+
+    // 代理优先级：命令行参数 / HTTPS_PROXY > config.json 中的 proxy
+    pub fn okx_proxy(&self, run_config: &AppRunConfig) -> AnyResult<Option<reqwest::Url>> {
+        let raw = self
+            .proxy
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .or(run_config.proxy.as_deref());
+        raw.map(okx::parse_proxy_url).transpose()
+    }
+
+    pub fn retry_policy(&self) -> okx::RetryPolicy {
+        okx::RetryPolicy {
+            max_attempts: self.rest_max_attempts,
+            base_delay: self.rest_retry_delay.as_duration(),
+            jitter: self.rest_retry_jitter,
+        }
+    }
+
+    pub fn order_limits(&self) -> OrderLimits {
+        OrderLimits {
+            max_notional: self.max_order_notional,
+            max_leverage: self.max_leverage,
+        }
+    }
+
+    fn ai_autotrade_config(&self) -> Option<AiAutoTradeConfig> {
+        self.ai_autotrade.then_some(AiAutoTradeConfig {
+            unattended: self.ai_autotrade_unattended,
+            max_order_size: self.ai_max_order_size,
+            max_leverage: self.ai_max_leverage,
+            order_limits: self.order_limits(),
         })
     }
 
+    // AI 分析范围，未设置时为全部图表合约；第二项为不在图表中而被忽略的合约
+    pub fn ai_inst_ids(&self) -> (Vec<String>, Vec<String>) {
+        let mut scope = Vec::new();
+        let mut ignored = Vec::new();
+        for raw in &self.ai_inst_ids {
+            let inst_id = raw.trim().to_ascii_uppercase();
+            if inst_id.is_empty() || scope.contains(&inst_id) || ignored.contains(&inst_id) {
+                continue;
+            }
+            if self
+                .inst_ids
+                .iter()
+                .any(|charted| charted.eq_ignore_ascii_case(&inst_id))
+            {
+                scope.push(inst_id);
+            } else {
+                ignored.push(inst_id);
+            }
+        }
+        if scope.is_empty() {
+            scope = self.inst_ids.clone();
+        }
+        (scope, ignored)
+    }
+
     pub fn ai_config(&self) -> Option<DeepseekConfig> {
         let provider = parse_ai_provider(&self.ai_provider);
         match provider {
@@ -205,6 +708,12 @@ impl CliParams {
                     model: model.to_string(),
                     interval: self.decision_interval.as_duration(),
                     provider,
+                    prompt_price_per_1k: self.ai_prompt_price,
+                    completion_price_per_1k: self.ai_completion_price,
+                    prompt_template: self.ai_prompt_template.clone(),
+                    autotrade: self.ai_autotrade_config(),
+                    prompt_indicators: self.ai_indicators.clone(),
+                    stream: self.ai_stream,
                 })
             }
             AiProvider::OpenRouter => {
@@ -227,6 +736,64 @@ impl CliParams {
                     model: model.to_string(),
                     interval: self.decision_interval.as_duration(),
                     provider,
+                    prompt_price_per_1k: self.ai_prompt_price,
+                    completion_price_per_1k: self.ai_completion_price,
+                    prompt_template: self.ai_prompt_template.clone(),
+                    autotrade: self.ai_autotrade_config(),
+                    prompt_indicators: self.ai_indicators.clone(),
+                    stream: self.ai_stream,
+                })
+            }
+            AiProvider::OpenAi => {
+                let api_key = self.openai_api_key.as_ref()?.trim();
+                if api_key.is_empty() {
+                    return None;
+                }
+                let endpoint =
+                    normalize_endpoint(self.openai_endpoint.trim(), DEFAULT_OPENAI_ENDPOINT);
+                let model = self.openai_model.trim();
+                if model.is_empty() {
+                    return None;
+                }
+                Some(DeepseekConfig {
+                    api_key: api_key.to_string(),
+                    endpoint,
+                    model: model.to_string(),
+                    interval: self.decision_interval.as_duration(),
+                    provider,
+                    prompt_price_per_1k: self.ai_prompt_price,
+                    completion_price_per_1k: self.ai_completion_price,
+                    prompt_template: self.ai_prompt_template.clone(),
+                    autotrade: self.ai_autotrade_config(),
+                    prompt_indicators: self.ai_indicators.clone(),
+                    stream: self.ai_stream,
+                })
+            }
+            AiProvider::Ollama => {
+                // 本地服务通常无需鉴权，key 可留空
+                let api_key = self
+                    .ollama_api_key
+                    .as_deref()
+                    .map(str::trim)
+                    .unwrap_or_default();
+                let endpoint =
+                    normalize_endpoint(self.ollama_endpoint.trim(), DEFAULT_OLLAMA_ENDPOINT);
+                let model = self.ollama_model.trim();
+                if model.is_empty() {
+                    return None;
+                }
+                Some(DeepseekConfig {
+                    api_key: api_key.to_string(),
+                    endpoint,
+                    model: model.to_string(),
+                    interval: self.decision_interval.as_duration(),
+                    provider,
+                    prompt_price_per_1k: self.ai_prompt_price,
+                    completion_price_per_1k: self.ai_completion_price,
+                    prompt_template: self.ai_prompt_template.clone(),
+                    autotrade: self.ai_autotrade_config(),
+                    prompt_indicators: self.ai_indicators.clone(),
+                    stream: self.ai_stream,
                 })
             }
         }
@@ -251,6 +818,148 @@ impl FromStr for DurationSpec {
     }
 }
 
+const PASSPHRASE_ENV_FALLBACK: &str = "OKX_PASSPHRASE";
+const REDACTED: &str = "<redacted>";
+
+pub const MIN_AI_INTERVAL: Duration = Duration::from_secs(30);
+const MIN_ACCOUNT_RESYNC_INTERVAL: Duration = Duration::from_secs(10);
+const MIN_HEALTH_LOG_INTERVAL: Duration = Duration::from_secs(10);
+const MIN_REDRAW_GAP: Duration = Duration::from_millis(16);
+const MAX_PRICE_PRECISION: usize = 12;
+const MIN_RETRY_DELAY: Duration = Duration::from_millis(10);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MIN_EXIT_CANCEL_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_EXIT_CANCEL_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_REDRAW_GAP: Duration = Duration::from_secs(2);
+const MIN_STALE_PRICE_AFTER: Duration = Duration::from_secs(1);
+const MAX_STALE_PRICE_AFTER: Duration = Duration::from_secs(3600);
+const MAX_ALERT_COOLDOWN: Duration = Duration::from_secs(24 * 3600);
+
+fn parse_ai_interval(input: &str) -> Result<DurationSpec, String> {
+    let duration = parse_duration_spec(input)?;
+    if duration < MIN_AI_INTERVAL {
+        return Err(format!(
+            "AI interval must be at least {}s to avoid hammering the API",
+            MIN_AI_INTERVAL.as_secs()
+        ));
+    }
+    Ok(DurationSpec(duration))
+}
+
+fn parse_account_resync_interval(input: &str) -> Result<DurationSpec, String> {
+    let duration = parse_duration_spec(input)?;
+    if duration < MIN_ACCOUNT_RESYNC_INTERVAL {
+        return Err(format!(
+            "account resync interval must be at least {}s",
+            MIN_ACCOUNT_RESYNC_INTERVAL.as_secs()
+        ));
+    }
+    Ok(DurationSpec(duration))
+}
+
+fn parse_health_log_interval(input: &str) -> Result<DurationSpec, String> {
+    let duration = parse_duration_spec(input)?;
+    if duration < MIN_HEALTH_LOG_INTERVAL {
+        return Err(format!(
+            "health log interval must be at least {}s",
+            MIN_HEALTH_LOG_INTERVAL.as_secs()
+        ));
+    }
+    Ok(DurationSpec(duration))
+}
+
+fn parse_history_bar(input: &str) -> Result<String, String> {
+    okx::history_bar(input)
+        .map(|(label, _)| label.to_string())
+        .ok_or_else(|| {
+            format!(
+                "unsupported history bar `{}` (expected one of: {})",
+                input.trim(),
+                okx::history_bar_labels().join(", ")
+            )
+        })
+}
+
+fn parse_positive_limit(input: &str) -> Result<f64, String> {
+    match input.trim().parse::<f64>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(value),
+        _ => Err(format!("`{input}` must be a positive number")),
+    }
+}
+
+fn parse_retry_delay(input: &str) -> Result<DurationSpec, String> {
+    let duration = parse_duration_spec(input)?;
+    if duration < MIN_RETRY_DELAY || duration > MAX_RETRY_DELAY {
+        return Err(format!(
+            "retry delay must be between {}ms and {}s",
+            MIN_RETRY_DELAY.as_millis(),
+            MAX_RETRY_DELAY.as_secs()
+        ));
+    }
+    Ok(DurationSpec(duration))
+}
+
+fn parse_exit_cancel_timeout(input: &str) -> Result<DurationSpec, String> {
+    let duration = parse_duration_spec(input)?;
+    if duration < MIN_EXIT_CANCEL_TIMEOUT || duration > MAX_EXIT_CANCEL_TIMEOUT {
+        return Err(format!(
+            "exit cancel timeout must be between {}s and {}s",
+            MIN_EXIT_CANCEL_TIMEOUT.as_secs(),
+            MAX_EXIT_CANCEL_TIMEOUT.as_secs()
+        ));
+    }
+    Ok(DurationSpec(duration))
+}
+
+fn parse_retry_jitter(input: &str) -> Result<f64, String> {
+    match input.trim().parse::<f64>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+        _ => Err(format!("`{input}` must be a number between 0 and 1")),
+    }
+}
+
+fn parse_replay_speed(input: &str) -> Result<f64, String> {
+    match input.trim().parse::<f64>() {
+        Ok(value) if (0.1..=100.0).contains(&value) => Ok(value),
+        _ => Err(format!("`{input}` must be a number between 0.1 and 100")),
+    }
+}
+
+fn parse_stale_price_after(input: &str) -> Result<DurationSpec, String> {
+    let duration = parse_duration_spec(input)?;
+    if duration < MIN_STALE_PRICE_AFTER || duration > MAX_STALE_PRICE_AFTER {
+        return Err(format!(
+            "stale price threshold must be between {}s and {}s",
+            MIN_STALE_PRICE_AFTER.as_secs(),
+            MAX_STALE_PRICE_AFTER.as_secs()
+        ));
+    }
+    Ok(DurationSpec(duration))
+}
+
+fn parse_alert_cooldown(input: &str) -> Result<DurationSpec, String> {
+    let duration = parse_duration_spec(input)?;
+    if duration > MAX_ALERT_COOLDOWN {
+        return Err(format!(
+            "alert cooldown must be at most {}s",
+            MAX_ALERT_COOLDOWN.as_secs()
+        ));
+    }
+    Ok(DurationSpec(duration))
+}
+
+fn parse_redraw_gap(input: &str) -> Result<DurationSpec, String> {
+    let duration = parse_duration_spec(input)?;
+    if duration < MIN_REDRAW_GAP || duration > MAX_REDRAW_GAP {
+        return Err(format!(
+            "redraw gap must be between {}ms and {}s",
+            MIN_REDRAW_GAP.as_millis(),
+            MAX_REDRAW_GAP.as_secs()
+        ));
+    }
+    Ok(DurationSpec(duration))
+}
+
 fn parse_duration_spec(input: &str) -> Result<Duration, String> {
     let trimmed = input.trim();
     if trimmed.is_empty() {
@@ -274,6 +983,7 @@ fn parse_duration_spec(input: &str) -> Result<Duration, String> {
         return Err("duration spec missing unit (use s, m, h, or d)".to_string());
     }
     let seconds_multiplier = match unit.as_str() {
+        "ms" | "msec" | "millis" => 0.001,
         "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
         "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
         "h" | "hr" | "hrs" | "hour" | "hours" => 60.0 * 60.0,
@@ -296,12 +1006,90 @@ fn parse_duration_spec(input: &str) -> Result<Duration, String> {
     Ok(Duration::from_secs_f64(seconds))
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct TradingConfig {
     pub api_key: String,
     pub api_secret: String,
     pub passphrase: String,
     pub td_mode: String,
+    pub position_mode: PositionMode,
+    pub demo: bool,
+    pub dry_run: bool,
+}
+
+// 凭证不参与 Debug 输出，避免意外打印到日志或错误信息中
+impl fmt::Debug for TradingConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TradingConfig")
+            .field("api_key", &REDACTED)
+            .field("api_secret", &REDACTED)
+            .field("passphrase", &REDACTED)
+            .field("td_mode", &self.td_mode)
+            .field("position_mode", &self.position_mode)
+            .field("demo", &self.demo)
+            .field("dry_run", &self.dry_run)
+            .finish()
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct AccountProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    #[serde(default)]
+    pub demo: bool,
+}
+
+impl fmt::Debug for AccountProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccountProfile")
+            .field("api_key", &self.api_key.as_ref().map(|_| REDACTED))
+            .field("api_secret", &self.api_secret.as_ref().map(|_| REDACTED))
+            .field("passphrase", &self.passphrase.as_ref().map(|_| REDACTED))
+            .field("demo", &self.demo)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NotifierConfig {
+    pub webhook_url: Option<String>,
+    pub telegram: Option<TelegramConfig>,
+}
+
+impl NotifierConfig {
+    pub fn is_empty(&self) -> bool {
+        self.webhook_url.is_none() && self.telegram.is_none()
+    }
+}
+
+#[cfg(target_os = "macos")]
+const DEFAULT_SOUND_PLAYER: &str = "afplay";
+#[cfg(not(target_os = "macos"))]
+const DEFAULT_SOUND_PLAYER: &str = "paplay";
+
+#[derive(Clone, Debug)]
+pub struct AlertSoundConfig {
+    pub bell: bool,
+    pub info_sound: Option<PathBuf>,
+    pub error_sound: Option<PathBuf>,
+    pub player: String,
+}
+
+impl AlertSoundConfig {
+    pub fn is_empty(&self) -> bool {
+        !self.bell && self.info_sound.is_none() && self.error_sound.is_none()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    pub chat_id: String,
 }
 
 #[derive(Clone, Debug)]
@@ -311,6 +1099,73 @@ pub struct DeepseekConfig {
     pub model: String,
     pub interval: Duration,
     pub provider: AiProvider,
+    pub prompt_price_per_1k: f64,
+    pub completion_price_per_1k: f64,
+    pub prompt_template: Option<PathBuf>,
+    pub autotrade: Option<AiAutoTradeConfig>,
+    pub prompt_indicators: Vec<String>,
+    pub stream: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AiAutoTradeConfig {
+    pub unattended: bool,
+    pub max_order_size: Option<f64>,
+    pub max_leverage: Option<f64>,
+    pub order_limits: OrderLimits,
+}
+
+// 手动下单与 AI 自动下单共用的硬性上限，防止误操作下出超大订单
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OrderLimits {
+    pub max_notional: Option<f64>,
+    pub max_leverage: Option<f64>,
+}
+
+impl OrderLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_notional.is_none() && self.max_leverage.is_none()
+    }
+
+    pub fn check(&self, notional: f64, leverage: Option<f64>) -> Result<(), String> {
+        if let Some(max) = self.max_notional.filter(|max| notional > *max) {
+            return Err(format!(
+                "名义价值 {} 超过上限 {}",
+                format_limit(notional),
+                format_limit(max)
+            ));
+        }
+        let over_leverage = leverage
+            .zip(self.max_leverage)
+            .filter(|(leverage, max)| leverage > max);
+        if let Some((leverage, max)) = over_leverage {
+            return Err(format!(
+                "杠杆 {}x 超过上限 {}x",
+                format_limit(leverage),
+                format_limit(max)
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(max) = self.max_notional {
+            parts.push(format!("名义 ≤ {}", format_limit(max)));
+        }
+        if let Some(max) = self.max_leverage {
+            parts.push(format!("杠杆 ≤ {}x", format_limit(max)));
+        }
+        parts.join(" · ")
+    }
+}
+
+fn format_limit(value: f64) -> String {
+    let formatted = format!("{value:.4}");
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
 }
 
 impl DeepseekConfig {
@@ -318,23 +1173,60 @@ impl DeepseekConfig {
         match self.provider {
             AiProvider::Deepseek => "Deepseek",
             AiProvider::OpenRouter => "OpenRouter",
+            AiProvider::OpenAi if self.is_azure() => "Azure OpenAI",
+            AiProvider::OpenAi => "OpenAI",
+            AiProvider::Ollama => "Ollama",
         }
         .to_string()
     }
+
+    pub fn auth_style(&self) -> AiAuthStyle {
+        if self.api_key.is_empty() {
+            return AiAuthStyle::None;
+        }
+        if self.is_azure() {
+            AiAuthStyle::ApiKeyHeader
+        } else {
+            AiAuthStyle::Bearer
+        }
+    }
+
+    pub fn estimate_cost(&self, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        (prompt_tokens as f64 * self.prompt_price_per_1k
+            + completion_tokens as f64 * self.completion_price_per_1k)
+            / 1000.0
+    }
+
+    fn is_azure(&self) -> bool {
+        self.provider == AiProvider::OpenAi && self.endpoint.contains(".openai.azure.com")
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AiProvider {
     Deepseek,
     OpenRouter,
+    OpenAi,
+    Ollama,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AiAuthStyle {
+    Bearer,
+    ApiKeyHeader,
+    None,
 }
 
 const DEFAULT_DEEPSEEK_ENDPOINT: &str = "https://api.deepseek.com";
 const DEFAULT_OPENROUTER_ENDPOINT: &str = "https://openrouter.ai/api/v1";
+const DEFAULT_OPENAI_ENDPOINT: &str = "https://api.openai.com/v1";
+const DEFAULT_OLLAMA_ENDPOINT: &str = "http://localhost:11434/v1";
 
 fn parse_ai_provider(value: &str) -> AiProvider {
     match value.trim().to_ascii_lowercase().as_str() {
         "openrouter" => AiProvider::OpenRouter,
+        "openai" => AiProvider::OpenAi,
+        "ollama" => AiProvider::Ollama,
         _ => AiProvider::Deepseek,
     }
 }
@@ -366,6 +1258,15 @@ impl ConfiguredTimeZone {
         Some(self.apply_timezone(base).format(fmt).to_string())
     }
 
+    // 导出用的完整 ISO8601 时间，带毫秒与所配置时区在该时刻的 UTC 偏移
+    pub fn format_iso8601(&self, timestamp_ms: i64) -> Option<String> {
+        let base = Utc.timestamp_millis_opt(timestamp_ms).single()?;
+        Some(
+            self.apply_timezone(base)
+                .to_rfc3339_opts(SecondsFormat::Millis, false),
+        )
+    }
+
     fn apply_timezone<TzSrc: TimeZone>(
         &self,
         datetime: chrono::DateTime<TzSrc>,
@@ -467,8 +1368,75 @@ fn try_parse_fixed_offset(input: &str) -> Option<FixedOffset> {
 
 #[derive(Debug, Clone)]
 pub struct AppRunConfig {
+    path: PathBuf,
     start_timestamp_ms: i64,
     timezone: ConfiguredTimeZone,
+    export_dir: Option<PathBuf>,
+    thresholds: HashMap<String, PriceThreshold>,
+    theme: Theme,
+    profiles: BTreeMap<String, AccountProfile>,
+    fee_rates: HashMap<String, FeeRate>,
+    chart: ChartPreferences,
+    endpoints: okx::OkxEndpoints,
+    proxy: Option<String>,
+    instrument_order: Vec<String>,
+    trade_logs: TradeLogRetention,
+    price_precision: HashMap<String, usize>,
+}
+
+// 图表视图偏好，退出时写回 config.json；缺省字段沿用默认值
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChartPreferences {
+    pub normalize: bool,
+    pub log_scale: bool,
+    pub multi_axis: bool,
+    pub y_zoom: f64,
+    pub candles: bool,
+    pub candle_interval_secs: u64,
+}
+
+impl Default for ChartPreferences {
+    fn default() -> Self {
+        ChartPreferences {
+            normalize: false,
+            log_scale: false,
+            multi_axis: false,
+            y_zoom: 1.0,
+            candles: false,
+            candle_interval_secs: 60,
+        }
+    }
+}
+
+impl ChartPreferences {
+    fn is_default(&self) -> bool {
+        *self == ChartPreferences::default()
+    }
+}
+
+// 费率为小数形式，如 0.0005 表示 0.05%
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeRate {
+    pub maker: f64,
+    pub taker: f64,
+}
+
+impl FeeRate {
+    // OKX 普通用户等级的默认费率
+    pub fn okx_default(spot: bool) -> Self {
+        if spot {
+            FeeRate {
+                maker: 0.0008,
+                taker: 0.001,
+            }
+        } else {
+            FeeRate {
+                maker: 0.0002,
+                taker: 0.0005,
+            }
+        }
+    }
 }
 
 impl AppRunConfig {
@@ -482,6 +1450,17 @@ impl AppRunConfig {
                 let stored = StoredAppRunConfig {
                     start_timestamp_ms: now_ms,
                     timezone: Some(DEFAULT_TIMEZONE_LABEL.to_string()),
+                    export_dir: None,
+                    thresholds: HashMap::new(),
+                    theme: None,
+                    profiles: BTreeMap::new(),
+                    fee_rates: HashMap::new(),
+                    chart: ChartPreferences::default(),
+                    endpoints: okx::OkxEndpoints::default(),
+                    proxy: None,
+                    instrument_order: Vec::new(),
+                    trade_logs: TradeLogRetention::default(),
+                    price_precision: HashMap::new(),
                 };
                 let payload = serde_json::to_string_pretty(&stored)?;
                 fs::write(path, payload).with_context(|| format!("无法写入 {}", path.display()))?;
@@ -492,9 +1471,67 @@ impl AppRunConfig {
             }
         };
         let timezone = parse_timezone_label(stored.timezone.clone())?;
+        let theme = match stored.theme.as_deref().map(str::trim) {
+            None | Some("") => Theme::default(),
+            Some(name) => Theme::by_name(name).ok_or_else(|| {
+                anyhow!(
+                    "无法识别主题 `{}`，可选值：{}",
+                    name,
+                    THEME_NAMES.join(" / ")
+                )
+            })?,
+        };
+        let export_dir = stored
+            .export_dir
+            .as_deref()
+            .map(str::trim)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from);
+        stored
+            .endpoints
+            .validate()
+            .with_context(|| format!("{} 中的 endpoints 配置无效", path.display()))?;
+        if let Some((inst_id, precision)) = stored
+            .price_precision
+            .iter()
+            .find(|(_, precision)| **precision > MAX_PRICE_PRECISION)
+        {
+            return Err(anyhow!(
+                "{} 中 price_precision.{} = {} 超出范围，小数位最多 {} 位",
+                path.display(),
+                inst_id,
+                precision,
+                MAX_PRICE_PRECISION
+            ));
+        }
+        if stored.trade_logs.memory_entries == 0 {
+            return Err(anyhow!(
+                "{} 中 trade_logs.memory_entries 必须大于 0",
+                path.display()
+            ));
+        }
         Ok(AppRunConfig {
+            path: path.to_path_buf(),
             start_timestamp_ms: stored.start_timestamp_ms,
             timezone,
+            export_dir,
+            thresholds: stored.thresholds,
+            theme,
+            profiles: stored.profiles,
+            fee_rates: stored.fee_rates,
+            chart: stored.chart,
+            endpoints: stored.endpoints,
+            proxy: stored
+                .proxy
+                .map(|proxy| proxy.trim().to_string())
+                .filter(|proxy| !proxy.is_empty()),
+            instrument_order: stored.instrument_order,
+            trade_logs: stored.trade_logs,
+            price_precision: stored
+                .price_precision
+                .into_iter()
+                .map(|(inst_id, precision)| (inst_id.trim().to_ascii_uppercase(), precision))
+                .collect(),
         })
     }
 
@@ -505,6 +1542,128 @@ impl AppRunConfig {
     pub fn timezone(&self) -> ConfiguredTimeZone {
         self.timezone
     }
+
+    pub fn export_dir(&self) -> PathBuf {
+        self.export_dir
+            .clone()
+            .unwrap_or_else(export::default_export_dir)
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.theme.clone()
+    }
+
+    // 未指定 profile 时，仅在只配置了一个 profile 的情况下自动选用
+    pub fn profile(&self, name: Option<&str>) -> AnyResult<Option<&AccountProfile>> {
+        let names = || {
+            self.profiles
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(" / ")
+        };
+        match name.map(str::trim).filter(|name| !name.is_empty()) {
+            Some(name) => self.profiles.get(name).map(Some).ok_or_else(|| {
+                if self.profiles.is_empty() {
+                    anyhow!(
+                        "{} 中没有配置账户 profile，无法使用 `{}`",
+                        self.path.display(),
+                        name
+                    )
+                } else {
+                    anyhow!("未找到账户 profile `{}`，可选值：{}", name, names())
+                }
+            }),
+            None if self.profiles.len() > 1 => Err(anyhow!(
+                "{} 中配置了多个账户 profile，请使用 --profile 指定：{}",
+                self.path.display(),
+                names()
+            )),
+            None => Ok(self.profiles.values().next()),
+        }
+    }
+
+    // 先按产品查找，再使用 "*" 通配配置，都没有时取 OKX 默认费率
+    pub fn fee_rate(&self, inst_id: &str) -> FeeRate {
+        self.fee_rates
+            .get(inst_id)
+            .or_else(|| self.fee_rates.get("*"))
+            .copied()
+            .unwrap_or_else(|| FeeRate::okx_default(okx::is_spot_inst(inst_id)))
+    }
+
+    pub fn thresholds(&self) -> &HashMap<String, PriceThreshold> {
+        &self.thresholds
+    }
+
+    pub fn okx_endpoints(&self) -> okx::OkxEndpoints {
+        self.endpoints.clone()
+    }
+
+    pub fn price_precision_overrides(&self) -> HashMap<String, usize> {
+        self.price_precision.clone()
+    }
+
+    pub fn trade_log_retention(&self) -> TradeLogRetention {
+        self.trade_logs
+    }
+
+    pub fn chart_preferences(&self) -> ChartPreferences {
+        self.chart
+    }
+
+    pub fn save_chart_preferences(&mut self, chart: ChartPreferences) -> AnyResult<()> {
+        if self.chart == chart {
+            return Ok(());
+        }
+        self.chart = chart;
+        self.rewrite_stored(|stored| stored.chart = chart)
+    }
+
+    // 按保存的顺序排列合约，未记录的合约保持原有相对顺序排在后面
+    pub fn order_instruments(&self, inst_ids: &mut [String]) {
+        if self.instrument_order.is_empty() {
+            return;
+        }
+        inst_ids.sort_by_key(|inst_id| {
+            self.instrument_order
+                .iter()
+                .position(|saved| saved.eq_ignore_ascii_case(inst_id))
+                .unwrap_or(usize::MAX)
+        });
+    }
+
+    pub fn save_instrument_order(&mut self, inst_ids: &[String]) -> AnyResult<()> {
+        if self.instrument_order == inst_ids {
+            return Ok(());
+        }
+        self.instrument_order = inst_ids.to_vec();
+        let order = self.instrument_order.clone();
+        self.rewrite_stored(|stored| stored.instrument_order = order)
+    }
+
+    pub fn save_threshold(&mut self, inst_id: &str, threshold: PriceThreshold) -> AnyResult<()> {
+        if threshold.is_empty() {
+            self.thresholds.remove(inst_id);
+        } else {
+            self.thresholds.insert(inst_id.to_string(), threshold);
+        }
+        let thresholds = self.thresholds.clone();
+        self.rewrite_stored(|stored| stored.thresholds = thresholds)
+    }
+
+    // 只改写指定字段，其余字段以磁盘上的内容为准
+    fn rewrite_stored(&self, update: impl FnOnce(&mut StoredAppRunConfig)) -> AnyResult<()> {
+        let contents = fs::read_to_string(&self.path)
+            .with_context(|| format!("读取 {} 失败", self.path.display()))?;
+        let mut stored = serde_json::from_str::<StoredAppRunConfig>(&contents)
+            .with_context(|| format!("解析 {} 失败", self.path.display()))?;
+        update(&mut stored);
+        let payload = serde_json::to_string_pretty(&stored)?;
+        fs::write(&self.path, payload)
+            .with_context(|| format!("无法写入 {}", self.path.display()))?;
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -512,4 +1671,90 @@ struct StoredAppRunConfig {
     start_timestamp_ms: i64,
     #[serde(default)]
     timezone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    export_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    thresholds: HashMap<String, PriceThreshold>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    theme: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, AccountProfile>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    fee_rates: HashMap<String, FeeRate>,
+    #[serde(default, skip_serializing_if = "ChartPreferences::is_default")]
+    chart: ChartPreferences,
+    #[serde(default, skip_serializing_if = "okx::OkxEndpoints::is_default")]
+    endpoints: okx::OkxEndpoints,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    proxy: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    instrument_order: Vec<String>,
+    #[serde(default, skip_serializing_if = "TradeLogRetention::is_default")]
+    trade_logs: TradeLogRetention,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    price_precision: HashMap<String, usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ai_inst_ids_limited_to_charted() {
+        let params = CliParams::parse_from([
+            "trade-ai",
+            "-i",
+            "BTC-USDT-SWAP,ETH-USDT-SWAP",
+            "--ai-inst-id",
+            "eth-usdt-swap,DOGE-USDT-SWAP",
+        ]);
+        let (scope, ignored) = params.ai_inst_ids();
+        assert_eq!(scope, vec!["ETH-USDT-SWAP".to_string()]);
+        assert_eq!(ignored, vec!["DOGE-USDT-SWAP".to_string()]);
+
+        let params = CliParams::parse_from(["trade-ai", "-i", "BTC-USDT-SWAP"]);
+        assert_eq!(
+            params.ai_inst_ids(),
+            (vec!["BTC-USDT-SWAP".to_string()], Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_chart_retention_independent_of_window() {
+        let params = CliParams::parse_from(["trade-ai", "--window", "1h"]);
+        assert_eq!(params.chart_retention(), Duration::from_secs(3600));
+
+        let params = CliParams::parse_from(["trade-ai", "--window", "1h", "--retention", "15m"]);
+        assert_eq!(params.history_window(), Duration::from_secs(3600));
+        assert_eq!(params.chart_retention(), Duration::from_secs(900));
+    }
+
+    #[test]
+    fn test_format_iso8601_embeds_zone_offset() {
+        let shanghai = parse_timezone_label(Some("Asia/Shanghai".to_string())).unwrap();
+        assert_eq!(
+            shanghai.format_iso8601(1_700_000_000_123).as_deref(),
+            Some("2023-11-15T06:13:20.123+08:00")
+        );
+        let fixed = parse_timezone_label(Some("UTC-05:30".to_string())).unwrap();
+        assert_eq!(
+            fixed.format_iso8601(0).as_deref(),
+            Some("1969-12-31T18:30:00.000-05:30")
+        );
+    }
+
+    #[test]
+    fn test_format_iso8601_across_dst_boundary() {
+        let new_york = parse_timezone_label(Some("America/New_York".to_string())).unwrap();
+        // 2024-03-10 07:00:00 UTC 美东由 EST 切换为 EDT
+        let switch_ms = 1_710_054_000_000;
+        assert_eq!(
+            new_york.format_iso8601(switch_ms - 1).as_deref(),
+            Some("2024-03-10T01:59:59.999-05:00")
+        );
+        assert_eq!(
+            new_york.format_iso8601(switch_ms).as_deref(),
+            Some("2024-03-10T03:00:00.000-04:00")
+        );
+    }
 }
@@ -4,24 +4,29 @@ use std::time::Duration;
 use anyhow::{Context, Result, anyhow};
 use chrono::{Duration as ChronoDuration, Local, TimeZone};
 use rand::Rng;
-use reqwest::{Client, ClientBuilder};
+use reqwest::{Client, ClientBuilder, Response};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{RwLock, broadcast, mpsc};
-use tokio::time;
+use tokio::time::{self, Instant};
 
-use crate::ai_decision::{DecisionExecutor, LeverageKey, initial_leverage_cache};
+use crate::ai_decision::{DecisionExecutor, DecisionScope, LeverageKey, initial_leverage_cache};
 use crate::ai_prompt::{
-    InstrumentLeverage, PerformanceStats, PerformanceSummary, build_snapshot_prompt,
-    load_system_prompt,
+    IndicatorSet, InstrumentLeverage, PerformanceStats, PerformanceSummary, PromptSections,
+    PromptTemplate, load_system_prompt,
 };
-use crate::command::{AccountSnapshot, AiInsightRecord, Command, TradeEvent, TradingCommand};
-use crate::config::{ConfiguredTimeZone, DeepseekConfig, TradingConfig};
+use crate::command::{
+    AccountSnapshot, AiInsightRecord, AiTokenUsage, Command, TradeEvent, TradingCommand,
+};
+use crate::config::{AiAuthStyle, ConfiguredTimeZone, DeepseekConfig, TradingConfig};
 use crate::error_log::ErrorLogStore;
 use crate::okx::{MarketInfo, SharedAccountState};
 use crate::okx_analytics::{InstrumentAnalytics, MarketDataFetcher};
 use crate::trade_log::{TradeLogEntry, TradeLogStore};
 
 const MAX_ANALYTICS_INSTRUMENTS: usize = 3;
+const STREAM_MAX_ATTEMPTS: usize = 2;
+const STREAM_PARTIAL_INTERVAL: Duration = Duration::from_millis(200);
+const MANUAL_ANALYSIS_MIN_GAP: Duration = Duration::from_secs(30);
 
 pub struct DeepseekReporter {
     client: DeepseekClient,
@@ -36,8 +41,11 @@ pub struct DeepseekReporter {
     order_tx: Option<mpsc::Sender<TradingCommand>>,
     error_log: ErrorLogStore,
     system_prompt: String,
+    prompt_template: PromptTemplate,
+    indicators: IndicatorSet,
     timezone: ConfiguredTimeZone,
     operator_label: String,
+    config: DeepseekConfig,
 }
 
 impl DeepseekReporter {
@@ -53,6 +61,8 @@ impl DeepseekReporter {
         timezone: ConfiguredTimeZone,
     ) -> Result<Self> {
         let system_prompt = load_system_prompt()?;
+        let prompt_template = PromptTemplate::load(config.prompt_template.as_deref())?;
+        let indicators = IndicatorSet::from_names(&config.prompt_indicators);
         let operator_label = config.provider_label();
         let client = DeepseekClient::new(&config, system_prompt.clone(), operator_label.clone())?;
         let market = MarketDataFetcher::new(trading_config)?;
@@ -73,20 +83,63 @@ impl DeepseekReporter {
             order_tx,
             error_log,
             system_prompt,
+            prompt_template,
+            indicators,
             timezone,
             operator_label,
+            config,
         })
     }
 
     pub async fn run(self, mut exit_rx: broadcast::Receiver<()>) -> Result<()> {
+        let mut next_run = Instant::now() + self.random_dispatch_delay();
+        let mut command_rx = self.tx.subscribe();
+        let mut last_run: Option<Instant> = None;
         loop {
-            let delay = self.random_dispatch_delay();
             tokio::select! {
-                _ = time::sleep(delay) => {
+                _ = time::sleep_until(next_run) => {
+                    last_run = Some(Instant::now());
                     if let Err(err) = self.report_once().await {
                         let _ = self.tx.send(Command::Error(format!("{} 分析失败: {err}", self.operator_label)));
                     }
+                    next_run = Instant::now() + self.random_dispatch_delay();
                 }
+                message = command_rx.recv() => match message {
+                    Ok(Command::AiAnalyzeNow) => {
+                        // 手动触发也要与上一次分析保持最小间隔，避免频繁请求触发限流
+                        if let Some(last) = last_run
+                            && last.elapsed() < MANUAL_ANALYSIS_MIN_GAP
+                        {
+                            let wait = MANUAL_ANALYSIS_MIN_GAP - last.elapsed();
+                            let _ = self.tx.send(Command::Error(format!(
+                                "{} 距上次分析不足 {}s，请 {}s 后再试",
+                                self.operator_label,
+                                MANUAL_ANALYSIS_MIN_GAP.as_secs(),
+                                wait.as_secs() + 1
+                            )));
+                            continue;
+                        }
+                        last_run = Some(Instant::now());
+                        let _ = self.tx.send(Command::AiAnalysisRunning(true));
+                        match self.report_once().await {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                let _ = self.tx.send(Command::Error(format!(
+                                    "{} 本次未生成决策（暂无账户数据或模型返回为空）",
+                                    self.operator_label
+                                )));
+                            }
+                            Err(err) => {
+                                let _ = self.tx.send(Command::Error(format!("{} 分析失败: {err}", self.operator_label)));
+                            }
+                        }
+                        let _ = self.tx.send(Command::AiAnalysisRunning(false));
+                        // 手动分析后重新计时，避免紧接着再跑一轮定时分析
+                        next_run = Instant::now() + self.random_dispatch_delay();
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
                 message = exit_rx.recv() => match message {
                     Ok(_) | Err(broadcast::error::RecvError::Closed) => break,
                     Err(broadcast::error::RecvError::Lagged(_)) => continue,
@@ -96,19 +149,27 @@ impl DeepseekReporter {
         Ok(())
     }
 
+    // 多个实例共用同一间隔时错开请求时间，每轮在 [min(60s, 间隔), 间隔] 内随机等待
     fn random_dispatch_delay(&self) -> Duration {
-        let min_secs = 60;
-        let max_secs = self.interval.as_secs().max(min_secs);
-        let mut rng = rand::rng();
-        let seconds = rng.random_range(min_secs..=max_secs);
+        let max_secs = self.interval.as_secs().max(1);
+        let min_secs = max_secs.min(60);
+        let seconds = rand::rng().random_range(min_secs..=max_secs);
         Duration::from_secs(seconds)
     }
 
-    async fn report_once(&self) -> Result<()> {
-        let snapshot = self.state.snapshot().await;
+    // 返回 false 表示本轮被静默跳过（无账户数据或模型返回为空）
+    async fn report_once(&self) -> Result<bool> {
+        let mut snapshot = self.state.snapshot().await;
         if !has_material_data(&snapshot) {
-            return Ok(());
+            return Ok(false);
         }
+        // 只把分析范围内合约的持仓与挂单交给 AI
+        snapshot
+            .positions
+            .retain(|position| self.inst_ids.contains(&position.inst_id));
+        snapshot
+            .open_orders
+            .retain(|order| self.inst_ids.contains(&order.inst_id));
         let decision_engine = self.decision_executor();
         decision_engine
             .capture_leverage_from_snapshot(&snapshot)
@@ -120,7 +181,7 @@ impl DeepseekReporter {
                 let _ = self.tx.send(Command::Error(format!(
                     "市场指标不完整，跳过本轮 AI 决策: {err}"
                 )));
-                return Ok(());
+                return Ok(true);
             }
         };
         let performance = match self.performance.summary(self.interval) {
@@ -138,45 +199,92 @@ impl DeepseekReporter {
                 None
             }
         };
-        let prompt = build_snapshot_prompt(
+        let sections = PromptSections::collect(
             &snapshot,
-            &analytics,
             performance.as_ref(),
             &self.inst_ids,
             &self.markets,
             &leverage_overview,
             self.timezone,
-        );
-        let insight = self.client.chat_completion(&prompt).await?;
+        )
+        .with_market_analytics(&analytics, self.indicators, self.timezone);
+        let prompt = self.prompt_template.render(&sections);
+        let (insight, usage) = if self.config.stream {
+            self.stream_completion(&prompt).await?
+        } else {
+            self.client.chat_completion(&prompt).await?
+        };
+        let usage = usage.map(|usage| AiTokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            cost: self
+                .config
+                .estimate_cost(usage.prompt_tokens, usage.completion_tokens),
+        });
         let trimmed = insight.trim();
         if trimmed.is_empty() {
-            return Ok(());
-        }
-        if let Err(err) = decision_engine.execute(trimmed).await {
-            let _ = self
-                .tx
-                .send(Command::Error(format!("执行 AI 决策失败: {err}")));
+            return Ok(false);
         }
+        // 先展示决策内容，便于在确认弹窗出现时查看分析依据
         let record = AiInsightRecord {
             timestamp_ms: Local::now().timestamp_millis(),
             system_prompt: self.system_prompt.clone(),
             user_prompt: prompt,
             response: trimmed.to_string(),
+            usage,
         };
         let _ = self.tx.send(Command::AiInsight(record));
-        Ok(())
+        if let Err(err) = decision_engine.execute(trimmed).await {
+            let _ = self
+                .tx
+                .send(Command::Error(format!("执行 AI 决策失败: {err}")));
+        }
+        Ok(true)
+    }
+
+    // 流式响应中途断开时记录错误并整体重试一次，仍失败则按普通失败处理
+    async fn stream_completion(&self, prompt: &str) -> Result<(String, Option<ChatUsage>)> {
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .client
+                .chat_completion_stream(prompt, |text| {
+                    let _ = self
+                        .tx
+                        .send(Command::AiInsightPartial(Some(text.to_string())));
+                })
+                .await;
+            match result {
+                Ok(completion) => return Ok(completion),
+                Err(err) if attempt < STREAM_MAX_ATTEMPTS => {
+                    let _ = self.tx.send(Command::Error(format!(
+                        "{} 流式响应中断，正在重试 ({attempt}/{STREAM_MAX_ATTEMPTS}): {err:#}",
+                        self.operator_label
+                    )));
+                    attempt += 1;
+                }
+                Err(err) => {
+                    let _ = self.tx.send(Command::AiInsightPartial(None));
+                    return Err(err);
+                }
+            }
+        }
     }
 
     fn decision_executor(&self) -> DecisionExecutor<'_> {
         DecisionExecutor::new(
             self.state.clone(),
             self.tx.clone(),
-            &self.inst_ids,
             &self.market,
             self.order_tx.clone(),
             &self.leverage_cache,
             self.error_log.clone(),
-            self.operator_label.clone(),
+            DecisionScope {
+                inst_ids: &self.inst_ids,
+                markets: &self.markets,
+                operator_name: self.operator_label.clone(),
+                autotrade: self.config.autotrade,
+            },
         )
     }
 
@@ -215,6 +323,9 @@ impl DeepseekReporter {
         {
             let cache = self.leverage_cache.read().await;
             for (key, value) in cache.iter() {
+                if !self.inst_ids.iter().any(|inst| inst == key.inst_id()) {
+                    continue;
+                }
                 let entry = overview
                     .entry(key.inst_id().to_string())
                     .or_insert_with(|| InstrumentLeverage::new(key.inst_id().to_string()));
@@ -309,7 +420,7 @@ impl PerformanceTracker {
     }
 }
 
-fn format_duration_brief(duration: Duration) -> String {
+pub fn format_duration_brief(duration: Duration) -> String {
     let secs = duration.as_secs();
     if secs == 0 {
         return "0s".to_string();
@@ -373,6 +484,7 @@ struct DeepseekClient {
     http: Client,
     base_url: String,
     api_key: String,
+    auth_style: AiAuthStyle,
     model: String,
     system_prompt: String,
     display_name: String,
@@ -388,15 +500,76 @@ impl DeepseekClient {
                 .build()?,
             base_url: config.endpoint.clone(),
             api_key: config.api_key.clone(),
+            auth_style: config.auth_style(),
             model: config.model.clone(),
             system_prompt,
             display_name,
         })
     }
 
-    async fn chat_completion(&self, prompt: &str) -> Result<String> {
-        let url = format!("{}/chat/completions", self.base_url);
-        let request = ChatCompletionRequest {
+    async fn chat_completion(&self, prompt: &str) -> Result<(String, Option<ChatUsage>)> {
+        let response = self
+            .send_request(&self.build_request(prompt, false))
+            .await?;
+        let response_text = response.text().await.unwrap_or_default();
+        let completion: ChatCompletionResponse =
+            serde_json::from_str(&response_text).map_err(|err| {
+                anyhow!(
+                    "解析 {} 响应失败: {}\n响应原文:\n{}",
+                    self.display_name,
+                    err,
+                    response_text
+                )
+            })?;
+        let usage = completion.usage;
+        let choice = completion
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("{} 响应中缺少内容", self.display_name))?;
+        self.finish_completion(&choice.message.content, usage)
+    }
+
+    // 中途断开返回错误交由上层重试
+    async fn chat_completion_stream(
+        &self,
+        prompt: &str,
+        mut on_partial: impl FnMut(&str),
+    ) -> Result<(String, Option<ChatUsage>)> {
+        let mut response = self.send_request(&self.build_request(prompt, true)).await?;
+        let mut parser = SseStreamParser::new(&self.display_name);
+        let mut last_partial = Instant::now();
+        while !parser.is_done()
+            && let Some(chunk) = response
+                .chunk()
+                .await
+                .with_context(|| format!("{} 流式响应中断", self.display_name))?
+        {
+            parser.push(&chunk)?;
+            if last_partial.elapsed() >= STREAM_PARTIAL_INTERVAL {
+                on_partial(parser.content());
+                last_partial = Instant::now();
+            }
+        }
+        let (content, usage) = parser.finish()?;
+        self.finish_completion(&content, usage)
+    }
+
+    fn finish_completion(
+        &self,
+        content: &str,
+        usage: Option<ChatUsage>,
+    ) -> Result<(String, Option<ChatUsage>)> {
+        let content = content.trim().to_string();
+        if content.is_empty() {
+            Err(anyhow!("{} 响应为空", self.display_name))
+        } else {
+            Ok((content, usage))
+        }
+    }
+
+    fn build_request(&self, prompt: &str, stream: bool) -> ChatCompletionRequest {
+        ChatCompletionRequest {
             model: self.model.clone(),
             messages: vec![
                 ChatMessage {
@@ -412,12 +585,21 @@ impl DeepseekClient {
             response_format: Some(ResponseFormat {
                 r#type: "json_object".to_string(),
             }),
+            stream,
+            stream_options: stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
+        }
+    }
+
+    async fn send_request(&self, request: &ChatCompletionRequest) -> Result<Response> {
+        let url = format!("{}/chat/completions", self.base_url);
+        let builder = self.http.post(url).json(request);
+        let builder = match self.auth_style {
+            AiAuthStyle::Bearer => builder.bearer_auth(&self.api_key),
+            AiAuthStyle::ApiKeyHeader => builder.header("api-key", &self.api_key),
+            AiAuthStyle::None => builder,
         };
-        let builder = self
-            .http
-            .post(url)
-            .bearer_auth(&self.api_key)
-            .json(&request);
         let response = builder
             .send()
             .await
@@ -432,27 +614,7 @@ impl DeepseekClient {
                 body
             ));
         }
-        let response_text = response.text().await.unwrap_or_default();
-        let completion: ChatCompletionResponse =
-            serde_json::from_str(&response_text).map_err(|err| {
-                anyhow!(
-                    "解析 {} 响应失败: {}\n响应原文:\n{}",
-                    self.display_name,
-                    err,
-                    response_text
-                )
-            })?;
-        let choice = completion
-            .choices
-            .into_iter()
-            .next()
-            .ok_or_else(|| anyhow!("{} 响应中缺少内容", self.display_name))?;
-        let content = choice.message.content.trim().to_string();
-        if content.is_empty() {
-            Err(anyhow!("{} 响应为空", self.display_name))
-        } else {
-            Ok(content)
-        }
+        Ok(response)
     }
 }
 
@@ -463,6 +625,15 @@ struct ChatCompletionRequest {
     #[serde(default)]
     temperature: f32,
     response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+#[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
 }
 #[derive(Serialize)]
 struct ResponseFormat {
@@ -478,6 +649,105 @@ struct ChatMessage {
 #[derive(Deserialize)]
 struct ChatCompletionResponse {
     choices: Vec<ChatCompletionChoice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+struct ChatUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChatCompletionChunkChoice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionChunkChoice {
+    #[serde(default)]
+    delta: ChatCompletionDelta,
+}
+
+#[derive(Deserialize, Default)]
+struct ChatCompletionDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+// SSE 按行解析 data: 事件，一行可能跨多个网络块；收到 [DONE] 才算完整
+struct SseStreamParser<'a> {
+    display_name: &'a str,
+    pending: Vec<u8>,
+    content: String,
+    usage: Option<ChatUsage>,
+    done: bool,
+}
+
+impl<'a> SseStreamParser<'a> {
+    fn new(display_name: &'a str) -> Self {
+        SseStreamParser {
+            display_name,
+            pending: Vec::new(),
+            content: String::new(),
+            usage: None,
+            done: false,
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) -> Result<()> {
+        self.pending.extend_from_slice(chunk);
+        while !self.done
+            && let Some(pos) = self.pending.iter().position(|byte| *byte == b'\n')
+        {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                self.done = true;
+                break;
+            }
+            let event: ChatCompletionChunk = serde_json::from_str(data).map_err(|err| {
+                anyhow!(
+                    "解析 {} 流式响应失败: {err}\n原文: {data}",
+                    self.display_name
+                )
+            })?;
+            // 开启 include_usage 后最后一个事件只有 usage，choices 为空
+            if event.usage.is_some() {
+                self.usage = event.usage;
+            }
+            for choice in event.choices {
+                if let Some(text) = choice.delta.content {
+                    self.content.push_str(&text);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn content(&self) -> &str {
+        &self.content
+    }
+
+    fn finish(self) -> Result<(String, Option<ChatUsage>)> {
+        if !self.done {
+            return Err(anyhow!("{} 流式响应在完成前断开", self.display_name));
+        }
+        Ok((self.content, self.usage))
+    }
 }
 
 #[derive(Deserialize)]
@@ -489,3 +759,53 @@ struct ChatCompletionChoice {
 struct ChatCompletionMessage {
     content: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STREAM: &str = concat!(
+        "data: {\"choices\":[{\"delta\":{\"content\":\"{\\\"sig\"}}]}\n\n",
+        ": keep-alive\n\n",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"nal\\\":1}\"}}]}\n\n",
+        "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":5}}\n\n",
+        "data: [DONE]\n\n",
+    );
+
+    #[test]
+    fn test_sse_parser_handles_lines_split_across_chunks() {
+        let expected = (
+            "{\"signal\":1}".to_string(),
+            Some(ChatUsage {
+                prompt_tokens: 12,
+                completion_tokens: 5,
+            }),
+        );
+        for chunk_size in [1, 7, 64, STREAM.len()] {
+            let mut parser = SseStreamParser::new("DeepSeek");
+            for chunk in STREAM.as_bytes().chunks(chunk_size) {
+                parser.push(chunk).unwrap();
+            }
+            assert!(parser.is_done());
+            assert_eq!(
+                parser.finish().unwrap(),
+                expected,
+                "chunk size {chunk_size}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_sse_parser_rejects_truncated_stream() {
+        let cut = STREAM.find("data: [DONE]").unwrap();
+        let mut parser = SseStreamParser::new("DeepSeek");
+        parser.push(&STREAM.as_bytes()[..cut]).unwrap();
+        assert_eq!(parser.content(), "{\"signal\":1}");
+        assert!(!parser.is_done());
+        let err = parser.finish().unwrap_err();
+        assert!(err.to_string().contains("完成前断开"));
+
+        let mut parser = SseStreamParser::new("DeepSeek");
+        assert!(parser.push(b"data: {\"choices\":[{\"del\n").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::ai_log::AiDecisionRecord;
+use crate::config::ConfiguredTimeZone;
+
+const FILE_TIME_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+pub fn default_export_dir() -> PathBuf {
+    PathBuf::from("exports")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AiExportFormat {
+    Csv,
+    Json,
+}
+
+impl AiExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            AiExportFormat::Csv => "csv",
+            AiExportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AiDecisionRow {
+    timestamp_ms: i64,
+    iso_time: String,
+    operations: String,
+    justification: String,
+    response: String,
+}
+
+impl AiDecisionRow {
+    fn new(record: &AiDecisionRecord, timezone: ConfiguredTimeZone) -> Self {
+        let timestamp_ms = record.timestamp_ms();
+        let justification = record
+            .operations
+            .iter()
+            .filter_map(|op| op.justification.as_deref())
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" | ");
+        AiDecisionRow {
+            timestamp_ms,
+            iso_time: timezone.format_iso8601(timestamp_ms).unwrap_or_default(),
+            operations: record.summary(),
+            justification,
+            response: record.response.clone(),
+        }
+    }
+}
+
+pub fn write_ai_decisions(
+    dir: &Path,
+    records: &[AiDecisionRecord],
+    format: AiExportFormat,
+    timezone: ConfiguredTimeZone,
+) -> Result<PathBuf> {
+    if records.is_empty() {
+        return Err(anyhow!("暂无 AI 决策记录"));
+    }
+    fs::create_dir_all(dir).with_context(|| format!("无法创建目录 {}", dir.display()))?;
+    let path = dir.join(format!(
+        "ai_decisions_{}.{}",
+        file_stamp(timezone),
+        format.extension()
+    ));
+    let file = File::create(&path).with_context(|| format!("无法写入 {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    let rows = records
+        .iter()
+        .map(|record| AiDecisionRow::new(record, timezone));
+    match format {
+        AiExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &rows.collect::<Vec<_>>())?;
+            writeln!(writer)?;
+        }
+        AiExportFormat::Csv => {
+            writeln!(
+                writer,
+                "timestamp_ms,iso_time,operations,justification,response"
+            )?;
+            for row in rows {
+                writeln!(
+                    writer,
+                    "{},{},{},{},{}",
+                    row.timestamp_ms,
+                    row.iso_time,
+                    csv_field(&row.operations),
+                    csv_field(&row.justification),
+                    csv_field(&row.response)
+                )?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(path)
+}
+
+pub fn write_price_csv(
+    dir: &Path,
+    inst_id: &str,
+    series: &[(f64, f64)],
+    timezone: ConfiguredTimeZone,
+) -> Result<PathBuf> {
+    if series.is_empty() {
+        return Err(anyhow!("{inst_id} 暂无价格数据"));
+    }
+    fs::create_dir_all(dir).with_context(|| format!("无法创建目录 {}", dir.display()))?;
+    let path = dir.join(format!(
+        "{}_{}.csv",
+        sanitize_file_name(inst_id),
+        file_stamp(timezone)
+    ));
+    let file = File::create(&path).with_context(|| format!("无法写入 {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    writeln!(writer, "inst_id,timestamp_ms,iso_time,mark_px")?;
+    for (ts, mark_px) in series {
+        let timestamp_ms = *ts as i64;
+        let iso_time = timezone.format_iso8601(timestamp_ms).unwrap_or_default();
+        writeln!(writer, "{inst_id},{timestamp_ms},{iso_time},{mark_px}")?;
+    }
+    writer.flush()?;
+    Ok(path)
+}
+
+fn file_stamp(timezone: ConfiguredTimeZone) -> String {
+    timezone
+        .format_timestamp(Utc::now().timestamp_millis(), FILE_TIME_FORMAT)
+        .unwrap_or_else(|| Utc::now().format(FILE_TIME_FORMAT).to_string())
+}
+
+// AI 回复常带逗号、引号与换行，统一加引号并转义内部引号
+fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+fn sanitize_file_name(value: &str) -> String {
+    value
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
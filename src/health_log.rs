@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::Local;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tokio::time::{MissedTickBehavior, interval_at};
+
+use crate::command::{Command, TradeEvent};
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct SessionCounters {
+    orders: u64,
+    order_failures: u64,
+    cancels: u64,
+    fills: u64,
+    errors: u64,
+}
+
+#[derive(Serialize)]
+struct HealthLogEntry<'a> {
+    timestamp_ms: i64,
+    uptime_secs: u64,
+    connections: BTreeMap<&'a str, bool>,
+    // 各合约最近一次收到标记价格的本地时间
+    last_price_ms: &'a BTreeMap<String, i64>,
+    #[serde(flatten)]
+    counters: &'a SessionCounters,
+}
+
+// 只统计广播通道上的消息，不参与界面与交易流程，定时追加一行摘要
+pub struct HealthLog {
+    path: PathBuf,
+    every: Duration,
+    rx: broadcast::Receiver<Command>,
+    exit_rx: broadcast::Receiver<()>,
+    started: Instant,
+    // 频道 -> 各连接编号的状态，同一频道全部连接成功才记为已连接
+    connections: BTreeMap<String, BTreeMap<u32, bool>>,
+    last_price_ms: BTreeMap<String, i64>,
+    counters: SessionCounters,
+}
+
+impl HealthLog {
+    pub fn new(
+        path: PathBuf,
+        every: Duration,
+        rx: broadcast::Receiver<Command>,
+        exit_rx: broadcast::Receiver<()>,
+    ) -> Self {
+        HealthLog {
+            path,
+            every,
+            rx,
+            exit_rx,
+            started: Instant::now(),
+            connections: BTreeMap::new(),
+            last_price_ms: BTreeMap::new(),
+            counters: SessionCounters::default(),
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        PathBuf::from("health_logs.jsonl")
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        let mut ticker = interval_at(tokio::time::Instant::now() + self.every, self.every);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => self.append()?,
+                message = self.rx.recv() => match message {
+                    Ok(command) => self.record(&command),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                signal = self.exit_rx.recv() => match signal {
+                    Ok(_) | Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, command: &Command) {
+        match command {
+            Command::MarkPriceUpdate(inst_id, ..) => {
+                let now_ms = Local::now().timestamp_millis();
+                match self.last_price_ms.get_mut(inst_id) {
+                    Some(last) => *last = now_ms,
+                    None => {
+                        self.last_price_ms.insert(inst_id.clone(), now_ms);
+                    }
+                }
+            }
+            Command::ConnState(channel, conn_id, connected) => {
+                self.connections
+                    .entry(channel.clone())
+                    .or_default()
+                    .insert(*conn_id, *connected);
+            }
+            Command::TradeResult(TradeEvent::Order(response)) => {
+                if response.success {
+                    self.counters.orders += 1;
+                } else {
+                    self.counters.order_failures += 1;
+                }
+            }
+            Command::TradeResult(TradeEvent::Cancel(cancel)) if cancel.success => {
+                self.counters.cancels += 1;
+            }
+            Command::TradeResult(TradeEvent::Fill(_)) => self.counters.fills += 1,
+            Command::Error(_) => self.counters.errors += 1,
+            _ => {}
+        }
+    }
+
+    fn channel_states(&self) -> BTreeMap<&str, bool> {
+        self.connections
+            .iter()
+            .map(|(channel, conns)| (channel.as_str(), conns.values().all(|ok| *ok)))
+            .collect()
+    }
+
+    fn append(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let entry = HealthLogEntry {
+            timestamp_ms: Local::now().timestamp_millis(),
+            uptime_secs: self.started.elapsed().as_secs(),
+            connections: self.channel_states(),
+            last_price_ms: &self.last_price_ms,
+            counters: &self.counters,
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        serde_json::to_writer(&mut file, &entry)?;
+        file.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::{CancelResponse, TradeOperator};
+
+    #[test]
+    fn test_health_counters_track_session_activity() {
+        let (_tx, rx) = broadcast::channel(4);
+        let (_exit_tx, exit_rx) = broadcast::channel(1);
+        let mut health = HealthLog::new(
+            HealthLog::default_path(),
+            Duration::from_secs(60),
+            rx,
+            exit_rx,
+        );
+        health.record(&Command::MarkPriceUpdate(
+            "BTC-USDT-SWAP".to_string(),
+            100.0,
+            0,
+            1,
+        ));
+        health.record(&Command::ConnState("mark-price".to_string(), 1, true));
+        health.record(&Command::ConnState("mark-price".to_string(), 2, true));
+        health.record(&Command::ConnState("mark-price".to_string(), 2, false));
+        health.record(&Command::Error("boom".to_string()));
+        let cancel = |success: bool| {
+            Command::TradeResult(TradeEvent::Cancel(CancelResponse {
+                inst_id: "BTC-USDT-SWAP".to_string(),
+                ord_id: "1".to_string(),
+                message: String::new(),
+                success,
+                operator: TradeOperator::Manual,
+                pos_side: None,
+                error_code: None,
+                dry_run: false,
+            }))
+        };
+        health.record(&cancel(true));
+        health.record(&cancel(false));
+        assert!(health.last_price_ms.contains_key("BTC-USDT-SWAP"));
+        assert_eq!(health.channel_states().get("mark-price"), Some(&false));
+        assert_eq!(
+            health.counters,
+            SessionCounters {
+                cancels: 1,
+                errors: 1,
+                ..SessionCounters::default()
+            }
+        );
+    }
+}
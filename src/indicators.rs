@@ -0,0 +1,140 @@
+use crate::okx_analytics::{Candle, compute_atr, compute_ema, compute_rsi};
+
+pub const DEFAULT_RSI_PERIOD: usize = 14;
+pub const DEFAULT_ATR_PERIOD: usize = 14;
+pub const DEFAULT_MACD_FAST: usize = 12;
+pub const DEFAULT_MACD_SLOW: usize = 26;
+pub const DEFAULT_MACD_SIGNAL: usize = 9;
+pub const DEFAULT_BOLLINGER_PERIOD: usize = 20;
+pub const DEFAULT_BOLLINGER_K: f64 = 2.0;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MacdSeries {
+    pub macd: Vec<(f64, f64)>,
+    pub signal: Vec<(f64, f64)>,
+    pub histogram: Vec<(f64, f64)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BollingerBands {
+    pub upper: Vec<(f64, f64)>,
+    pub middle: Vec<(f64, f64)>,
+    pub lower: Vec<(f64, f64)>,
+}
+
+pub fn rsi(series: &[(f64, f64)], period: usize) -> Vec<(f64, f64)> {
+    if period == 0 || series.len() <= period {
+        return Vec::new();
+    }
+    let closes: Vec<f64> = series.iter().map(|(_, price)| *price).collect();
+    let values = compute_rsi(&closes, period);
+    series
+        .iter()
+        .zip(values)
+        .skip(period + 1)
+        .filter(|(_, value)| value.is_finite())
+        .map(|((ts, _), value)| (*ts, value.clamp(0.0, 100.0)))
+        .collect()
+}
+
+pub fn latest_rsi(series: &[(f64, f64)], period: usize) -> Option<f64> {
+    rsi(series, period).last().map(|(_, value)| *value)
+}
+
+// 只有标记价格，没有 K 线高低点，真实波幅退化为相邻两个价格之差的绝对值
+pub fn atr(series: &[(f64, f64)], period: usize) -> Vec<(f64, f64)> {
+    if period == 0 || series.len() <= period {
+        return Vec::new();
+    }
+    let candles: Vec<Candle> = series
+        .iter()
+        .map(|(ts, price)| Candle {
+            ts: *ts as i64,
+            open: *price,
+            high: *price,
+            low: *price,
+            close: *price,
+            volume: 0.0,
+        })
+        .collect();
+    series
+        .iter()
+        .zip(compute_atr(&candles, period))
+        .skip(period)
+        .filter(|(_, value)| value.is_finite())
+        .map(|((ts, _), value)| (*ts, value))
+        .collect()
+}
+
+pub fn latest_atr(series: &[(f64, f64)], period: usize) -> Option<f64> {
+    atr(series, period).last().map(|(_, value)| *value)
+}
+
+// EMA 以首个价格为种子，前 slow 个点的慢线尚未收敛；信号线从慢线收敛后才开始计算，
+// 再等 signal 个点收敛后才输出，避免开头出现虚假的尖峰
+pub fn macd(series: &[(f64, f64)], fast: usize, slow: usize, signal: usize) -> MacdSeries {
+    if fast == 0 || slow <= fast || signal == 0 || series.len() < slow + signal - 1 {
+        return MacdSeries::default();
+    }
+    let closes: Vec<f64> = series.iter().map(|(_, price)| *price).collect();
+    let fast_ema = compute_ema(&closes, fast);
+    let slow_ema = compute_ema(&closes, slow);
+    let macd_line: Vec<f64> = fast_ema
+        .iter()
+        .zip(&slow_ema)
+        .skip(slow - 1)
+        .map(|(f, s)| f - s)
+        .collect();
+    let signal_line = compute_ema(&macd_line, signal);
+    let mut result = MacdSeries::default();
+    for ((ts, _), (macd, signal)) in series
+        .iter()
+        .skip(slow - 1)
+        .zip(macd_line.iter().zip(&signal_line))
+        .skip(signal - 1)
+    {
+        result.macd.push((*ts, *macd));
+        result.signal.push((*ts, *signal));
+        result.histogram.push((*ts, macd - signal));
+    }
+    result
+}
+
+pub fn sma(series: &[(f64, f64)], period: usize) -> Vec<(f64, f64)> {
+    if period == 0 || series.len() < period {
+        return Vec::new();
+    }
+    let mut averages = Vec::with_capacity(series.len() - period + 1);
+    let mut sum: f64 = series[..period].iter().map(|(_, y)| *y).sum();
+    averages.push((series[period - 1].0, sum / period as f64));
+    for idx in period..series.len() {
+        sum += series[idx].1 - series[idx - period].1;
+        averages.push((series[idx].0, sum / period as f64));
+    }
+    averages
+}
+
+// 使用总体标准差；价格不变时方差可能因浮点误差略小于 0，截断后上下轨与中轨重合
+pub fn bollinger(series: &[(f64, f64)], period: usize, k: f64) -> BollingerBands {
+    if period == 0 || series.len() < period || !k.is_finite() {
+        return BollingerBands::default();
+    }
+    let mut bands = BollingerBands::default();
+    for window in series.windows(period) {
+        let ts = window[period - 1].0;
+        let mean = window.iter().map(|(_, price)| *price).sum::<f64>() / period as f64;
+        let variance = window
+            .iter()
+            .map(|(_, price)| (price - mean).powi(2))
+            .sum::<f64>()
+            / period as f64;
+        let std_dev = variance.max(0.0).sqrt();
+        if !mean.is_finite() || !std_dev.is_finite() {
+            continue;
+        }
+        bands.upper.push((ts, mean + k * std_dev));
+        bands.middle.push((ts, mean));
+        bands.lower.push((ts, mean - k * std_dev));
+    }
+    bands
+}
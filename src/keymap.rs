@@ -0,0 +1,459 @@
+use crossterm::event::KeyCode;
+
+// 快捷键帮助的唯一数据源；测试会检查 tui 中各 handle_*_key 匹配的按键都已列在此表
+pub struct KeyBinding {
+    pub keys: &'static [KeyCode],
+    pub scope: Option<&'static str>,
+    pub action: &'static str,
+}
+
+pub struct KeyBindingGroup {
+    pub title: &'static str,
+    pub bindings: &'static [KeyBinding],
+}
+
+const fn bind(keys: &'static [KeyCode], action: &'static str) -> KeyBinding {
+    KeyBinding {
+        keys,
+        scope: None,
+        action,
+    }
+}
+
+const fn bind_in(
+    keys: &'static [KeyCode],
+    scope: &'static str,
+    action: &'static str,
+) -> KeyBinding {
+    KeyBinding {
+        keys,
+        scope: Some(scope),
+        action,
+    }
+}
+
+pub const KEY_BINDINGS: &[KeyBindingGroup] = &[
+    KeyBindingGroup {
+        title: "全局",
+        bindings: &[
+            bind(
+                &[KeyCode::Char('q'), KeyCode::Char('Q'), KeyCode::Esc],
+                "退出（需确认，Ctrl+C 同）",
+            ),
+            bind(
+                &[KeyCode::Char('t'), KeyCode::Char('T')],
+                "切换图表/交易页面",
+            ),
+            bind(
+                &[KeyCode::Char('d'), KeyCode::Char('D')],
+                "打开/关闭盈亏统计",
+            ),
+            bind(&[KeyCode::Char('?')], "打开/关闭快捷键帮助"),
+            bind(
+                &[KeyCode::Char('u'), KeyCode::Char('U')],
+                "撤销 3 秒内刚提交的手动下单",
+            ),
+            bind(
+                &[KeyCode::Char('z'), KeyCode::Char('Z')],
+                "数量单位切换（币/张）",
+            ),
+        ],
+    },
+    KeyBindingGroup {
+        title: "图表",
+        bindings: &[
+            bind(&[KeyCode::Char('n'), KeyCode::Char('N')], "相对涨跌模式"),
+            bind(&[KeyCode::Char('m'), KeyCode::Char('M')], "多 Y 轴模式"),
+            bind(&[KeyCode::Char('g'), KeyCode::Char('G')], "对数坐标"),
+            bind(&[KeyCode::Char('+'), KeyCode::Char('=')], "放大 Y 轴"),
+            bind(&[KeyCode::Char('-')], "缩小 Y 轴"),
+            bind(&[KeyCode::Char('0')], "重置 Y 轴缩放"),
+            bind(&[KeyCode::Char('c'), KeyCode::Char('C')], "切换 K 线/折线"),
+            bind(&[KeyCode::Char('i'), KeyCode::Char('I')], "切换 K 线周期"),
+            bind(&[KeyCode::Char('a'), KeyCode::Char('A')], "SMA 均线"),
+            bind(&[KeyCode::Char('['), KeyCode::Char(']')], "调整 SMA 周期"),
+            bind(&[KeyCode::Char('b'), KeyCode::Char('B')], "布林带"),
+            bind(&[KeyCode::Char('o'), KeyCode::Char('O')], "MACD 面板"),
+            bind(&[KeyCode::Char('f'), KeyCode::Char('F')], "跟随最新行情"),
+            bind(
+                &[KeyCode::Left, KeyCode::Char('h'), KeyCode::Char('H')],
+                "向左平移时间窗口",
+            ),
+            bind(
+                &[KeyCode::Right, KeyCode::Char('l'), KeyCode::Char('L')],
+                "向右平移时间窗口",
+            ),
+            bind(
+                &[KeyCode::Char('e'), KeyCode::Char('E')],
+                "导出当前合约 CSV",
+            ),
+            bind(&[KeyCode::Char('w'), KeyCode::Char('W')], "设置价格提醒"),
+            bind(
+                &[KeyCode::Char('/')],
+                "按前缀跳转（Tab）或添加（Enter）合约",
+            ),
+            bind(&[KeyCode::Char('x'), KeyCode::Char('X')], "移除当前合约"),
+            bind(
+                &[KeyCode::Char('K'), KeyCode::Char('J')],
+                "上移/下移当前合约",
+            ),
+        ],
+    },
+    KeyBindingGroup {
+        title: "交易",
+        bindings: &[
+            bind(&[KeyCode::Tab, KeyCode::BackTab], "切换焦点面板"),
+            bind(
+                &[
+                    KeyCode::Up,
+                    KeyCode::Down,
+                    KeyCode::Char('k'),
+                    KeyCode::Char('j'),
+                ],
+                "浏览/滚动",
+            ),
+            bind(&[KeyCode::PageUp, KeyCode::PageDown], "翻页"),
+            bind(&[KeyCode::Home, KeyCode::End], "跳到首行/末行"),
+            bind(&[KeyCode::Char('b'), KeyCode::Char('B')], "买入下单"),
+            bind(&[KeyCode::Char('s'), KeyCode::Char('S')], "卖出下单"),
+            bind(&[KeyCode::Char('C')], "一键平掉全部持仓"),
+            bind(
+                &[KeyCode::Char('w'), KeyCode::Char('W')],
+                "持仓/挂单范围：图表合约 ⇄ 全部合约",
+            ),
+            bind(
+                &[KeyCode::Char('/')],
+                "按前缀跳转（Tab）或添加（Enter）合约",
+            ),
+            bind_in(&[KeyCode::Char('p'), KeyCode::Char('P')], "持仓", "止盈"),
+            bind_in(&[KeyCode::Char('l'), KeyCode::Char('L')], "持仓", "止损"),
+            bind_in(
+                &[KeyCode::Char('g'), KeyCode::Char('G')],
+                "持仓",
+                "移动止损",
+            ),
+            bind_in(
+                &[
+                    KeyCode::Char('1'),
+                    KeyCode::Char('2'),
+                    KeyCode::Char('3'),
+                    KeyCode::Char('4'),
+                ],
+                "持仓",
+                "快速平仓 25%/50%/75%/100%",
+            ),
+            bind_in(&[KeyCode::Char('v'), KeyCode::Char('V')], "持仓", "反手"),
+            bind_in(
+                &[KeyCode::Char('n'), KeyCode::Char('N')],
+                "持仓",
+                "净持仓汇总视图",
+            ),
+            bind_in(
+                &[KeyCode::Char('+'), KeyCode::Char('=')],
+                "持仓",
+                "同向加仓（按持仓预填委托）",
+            ),
+            bind_in(
+                &[KeyCode::Char('a'), KeyCode::Char('A')],
+                "持仓/合约",
+                "调整杠杆",
+            ),
+            bind_in(&[KeyCode::Char('c')], "挂单", "撤销选中挂单"),
+            bind_in(
+                &[KeyCode::Char('x'), KeyCode::Char('X')],
+                "挂单",
+                "撤销全部挂单",
+            ),
+            bind_in(&[KeyCode::Char('r'), KeyCode::Char('R')], "挂单", "改单"),
+            bind_in(
+                &[KeyCode::Char('p'), KeyCode::Char('P')],
+                "历史",
+                "切换时间范围",
+            ),
+            bind_in(
+                &[KeyCode::Char('v'), KeyCode::Char('V')],
+                "历史",
+                "切换委托/成交",
+            ),
+            bind_in(&[KeyCode::Char('r'), KeyCode::Char('R')], "历史", "刷新"),
+            bind_in(
+                &[KeyCode::Char('o'), KeyCode::Char('O')],
+                "委托日志",
+                "查看详情",
+            ),
+            bind_in(
+                &[KeyCode::Char('i'), KeyCode::Char('I')],
+                "委托日志",
+                "按合约筛选（全部/各合约循环）",
+            ),
+            bind_in(
+                &[KeyCode::Char('f'), KeyCode::Char('F')],
+                "委托日志",
+                "按类型筛选（全部/委托/撤单/成交）",
+            ),
+            bind_in(
+                &[KeyCode::Char('o'), KeyCode::Char('O')],
+                "AI",
+                "查看提示详情",
+            ),
+            bind_in(&[KeyCode::Char('r'), KeyCode::Char('R')], "AI", "立即分析"),
+            bind_in(&[KeyCode::Char('e')], "AI", "导出决策 CSV"),
+            bind_in(&[KeyCode::Char('E')], "AI", "导出决策 JSON"),
+        ],
+    },
+    KeyBindingGroup {
+        title: "下单对话框",
+        bindings: &[
+            bind(&[KeyCode::Enter], "提交（仓位计算器开启时填入数量）"),
+            bind(&[KeyCode::Esc], "取消"),
+            bind(
+                &[
+                    KeyCode::Tab,
+                    KeyCode::BackTab,
+                    KeyCode::Left,
+                    KeyCode::Right,
+                ],
+                "切换字段",
+            ),
+            bind_in(&[KeyCode::Up, KeyCode::Down], "价格", "±1 跳"),
+            bind_in(&[KeyCode::PageUp, KeyCode::PageDown], "价格", "±10 跳"),
+            bind(&[KeyCode::Char('m'), KeyCode::Char('M')], "切换委托类型"),
+            bind(&[KeyCode::Char('i'), KeyCode::Char('I')], "切换全仓/逐仓"),
+            bind(&[KeyCode::Char('o'), KeyCode::Char('O')], "切换只减仓"),
+            bind(&[KeyCode::Char('r'), KeyCode::Char('R')], "仓位计算器"),
+        ],
+    },
+    KeyBindingGroup {
+        title: "AI 提示详情",
+        bindings: &[
+            bind(
+                &[KeyCode::Char('o'), KeyCode::Char('O'), KeyCode::Esc],
+                "关闭",
+            ),
+            bind(&[KeyCode::Char('j'), KeyCode::Down], "向下滚动"),
+            bind(&[KeyCode::Char('k'), KeyCode::Up], "向上滚动"),
+            bind(&[KeyCode::PageUp, KeyCode::PageDown], "翻页"),
+        ],
+    },
+    KeyBindingGroup {
+        title: "盈亏统计",
+        bindings: &[bind(
+            &[KeyCode::Char('p'), KeyCode::Char('P'), KeyCode::Tab],
+            "切换统计区间",
+        )],
+    },
+    KeyBindingGroup {
+        title: "输入对话框",
+        bindings: &[
+            bind(&[KeyCode::Enter], "提交"),
+            bind(&[KeyCode::Esc], "取消"),
+            bind_in(
+                &[KeyCode::Tab, KeyCode::BackTab, KeyCode::Up, KeyCode::Down],
+                "价格提醒",
+                "切换字段",
+            ),
+            bind_in(&[KeyCode::Tab], "合约", "跳转到前缀匹配的合约"),
+        ],
+    },
+    KeyBindingGroup {
+        title: "确认对话框",
+        bindings: &[
+            bind(
+                &[KeyCode::Char('y'), KeyCode::Char('Y'), KeyCode::Enter],
+                "确认",
+            ),
+            bind(
+                &[KeyCode::Char('n'), KeyCode::Char('N'), KeyCode::Esc],
+                "取消",
+            ),
+            bind_in(
+                &[KeyCode::Char('q'), KeyCode::Char('Q')],
+                "退出",
+                "确认退出（Ctrl+C 同）",
+            ),
+        ],
+    },
+    KeyBindingGroup {
+        title: "快捷键帮助",
+        bindings: &[
+            bind(&[KeyCode::Char('?'), KeyCode::Esc], "关闭"),
+            bind(&[KeyCode::Char('j'), KeyCode::Down], "向下滚动"),
+            bind(&[KeyCode::Char('k'), KeyCode::Up], "向上滚动"),
+            bind(&[KeyCode::PageUp, KeyCode::PageDown], "翻页"),
+            bind(&[KeyCode::Home, KeyCode::End], "跳到顶部/底部"),
+        ],
+    },
+];
+
+fn key_label(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        KeyCode::BackTab => "Shift+Tab".to_string(),
+        other => other.to_string(),
+    }
+}
+
+// 同一字母的大小写只显示一次
+pub fn keys_label(keys: &[KeyCode]) -> String {
+    let mut labels: Vec<String> = Vec::new();
+    for code in keys {
+        let duplicate = matches!(code, KeyCode::Char(c)
+            if c.is_ascii_uppercase() && keys.contains(&KeyCode::Char(c.to_ascii_lowercase())));
+        if duplicate {
+            continue;
+        }
+        labels.push(key_label(*code));
+    }
+    labels.join(" / ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_bindings_have_no_conflicts() {
+        for group in KEY_BINDINGS {
+            let mut seen = Vec::new();
+            for binding in group.bindings {
+                assert!(!binding.keys.is_empty(), "{} 存在空按键", group.title);
+                for code in binding.keys {
+                    let entry = (*code, binding.scope);
+                    assert!(
+                        !seen.contains(&entry),
+                        "{} 中 {:?} 重复绑定",
+                        group.title,
+                        entry
+                    );
+                    seen.push(entry);
+                }
+            }
+        }
+        assert_eq!(
+            keys_label(&[KeyCode::Char('q'), KeyCode::Char('Q'), KeyCode::Esc]),
+            "q / Esc"
+        );
+        assert_eq!(
+            keys_label(&[KeyCode::Char('K'), KeyCode::Char('J')]),
+            "K / J"
+        );
+    }
+
+    // 各按键处理函数对应的帮助分组；额外按键为 Ctrl 组合键，在说明文字中列出
+    const HANDLER_GROUPS: &[(&str, &[&str], &[KeyCode])] = &[
+        ("handle_key_event", &["全局"], &[KeyCode::Char('c')]),
+        ("handle_help_overlay_key", &["快捷键帮助"], &[]),
+        (
+            "handle_exit_confirmation_key",
+            &["确认对话框"],
+            &[KeyCode::Char('c')],
+        ),
+        ("handle_close_all_confirmation_key", &["确认对话框"], &[]),
+        ("handle_ai_proposal_key", &["确认对话框"], &[]),
+        ("handle_order_confirmation_key", &["确认对话框"], &[]),
+        ("handle_reverse_confirmation_key", &["确认对话框"], &[]),
+        ("handle_chart_key", &["图表"], &[]),
+        ("handle_threshold_input_key", &["输入对话框"], &[]),
+        ("handle_instrument_input_key", &["输入对话框"], &[]),
+        ("handle_stats_key", &["盈亏统计"], &[]),
+        ("handle_trade_key", &["交易", "AI 提示详情"], &[]),
+        ("handle_leverage_input_key", &["输入对话框"], &[]),
+        ("handle_order_input_key", &["下单对话框"], &[]),
+    ];
+
+    fn handler_body<'a>(source: &'a str, name: &str) -> &'a str {
+        let start = source
+            .find(&format!("fn {name}("))
+            .unwrap_or_else(|| panic!("tui 中找不到 {name}"));
+        let body = &source[start..];
+        let end = body[1..]
+            .find("\n    fn ")
+            .map_or(body.len(), |idx| idx + 1);
+        &body[..end]
+    }
+
+    // 只识别具体按键；Char(c)、Backspace 等文本编辑输入不计入
+    fn matched_key_codes(body: &str) -> Vec<KeyCode> {
+        let mut codes = Vec::new();
+        for (idx, _) in body.match_indices("KeyCode::") {
+            let rest = &body[idx + "KeyCode::".len()..];
+            let name: String = rest
+                .chars()
+                .take_while(|ch| ch.is_ascii_alphanumeric())
+                .collect();
+            let code = match name.as_str() {
+                "Char" => {
+                    let quoted: Vec<char> = rest
+                        .split(')')
+                        .next()
+                        .unwrap_or_default()
+                        .split('\'')
+                        .skip(1)
+                        .step_by(2)
+                        .filter_map(|part| part.chars().next())
+                        .collect();
+                    match quoted.as_slice() {
+                        [ch] => codes.push(KeyCode::Char(*ch)),
+                        [from, to] => codes.extend((*from..=*to).map(KeyCode::Char)),
+                        _ => {}
+                    }
+                    continue;
+                }
+                "Up" => KeyCode::Up,
+                "Down" => KeyCode::Down,
+                "Left" => KeyCode::Left,
+                "Right" => KeyCode::Right,
+                "PageUp" => KeyCode::PageUp,
+                "PageDown" => KeyCode::PageDown,
+                "Home" => KeyCode::Home,
+                "End" => KeyCode::End,
+                "Tab" => KeyCode::Tab,
+                "BackTab" => KeyCode::BackTab,
+                "Enter" => KeyCode::Enter,
+                "Esc" => KeyCode::Esc,
+                _ => continue,
+            };
+            codes.push(code);
+        }
+        codes
+    }
+
+    #[test]
+    fn test_key_bindings_cover_tui_handlers() {
+        let source = include_str!("tui.rs");
+        let handlers: Vec<&str> = source
+            .match_indices("fn handle_")
+            .filter_map(|(idx, _)| {
+                let name = source[idx + 3..].split('(').next()?;
+                (name.ends_with("_key") || name == "handle_key_event").then_some(name)
+            })
+            .collect();
+        for name in &handlers {
+            assert!(
+                HANDLER_GROUPS.iter().any(|(handler, ..)| handler == name),
+                "{name} 未对应任何快捷键分组"
+            );
+        }
+        for (handler, titles, extra) in HANDLER_GROUPS {
+            let documented: Vec<KeyCode> = KEY_BINDINGS
+                .iter()
+                .filter(|group| titles.contains(&group.title))
+                .flat_map(|group| group.bindings.iter())
+                .flat_map(|binding| binding.keys.iter().copied())
+                .chain(extra.iter().copied())
+                .collect();
+            for code in matched_key_codes(handler_body(source, handler)) {
+                assert!(
+                    documented.contains(&code),
+                    "{handler} 处理的 {code:?} 未列在 {titles:?} 快捷键表中"
+                );
+            }
+        }
+    }
+}
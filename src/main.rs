@@ -5,17 +5,29 @@ mod command;
 mod config;
 mod deepseek;
 mod error_log;
+mod export;
+mod health_log;
+mod indicators;
+mod keymap;
 mod monitor;
 mod notify;
 mod okx;
 mod okx_analytics;
+mod pnl_stats;
+mod price_series;
+mod replay;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
+mod theme;
 mod trade_log;
 mod tui;
 
 #[cfg(test)]
 mod test_indicators;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, IsTerminal};
+use std::time::Duration;
 
 use anyhow::anyhow;
 use clap::Parser;
@@ -23,22 +35,35 @@ use color_eyre::Result;
 use tokio::sync::{broadcast, mpsc};
 use tokio::task;
 
-use crate::command::{Command, TradingCommand};
+use crate::command::{Command, MarginMode, TradingCommand};
 use crate::deepseek::DeepseekReporter;
 use crate::error_log::ErrorLogStore;
-use crate::notify::OsNotification;
+use crate::health_log::HealthLog;
+use crate::notify::{AlertSound, OsNotification, RemoteNotification};
 use crate::okx::{
     OkxBusinessWsClient, OkxPrivateWsClient, OkxTradingClient, OkxWsClient, SharedAccountState,
 };
+use crate::replay::SessionRecorder;
 use crate::tui::TuiApp;
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
-    let param = config::CliParams::parse();
+    let mut param = config::CliParams::parse();
     let run_config = config::AppRunConfig::load_or_init("config.json")?;
+    okx::set_endpoints(run_config.okx_endpoints());
+    okx::set_retry_policy(param.retry_policy());
+    if let Some(proxy) = param.okx_proxy(&run_config)? {
+        okx::set_proxy(proxy)?;
+    }
+    param.resolve_credentials(run_config.profile(param.profile.as_deref())?)?;
+    run_config.order_instruments(&mut param.inst_ids);
     let run_start_timestamp_ms = run_config.start_timestamp_ms();
     let timezone = run_config.timezone();
-    let ai_cfg = param.ai_config();
+    let replaying = param.replay.is_some();
+    let ai_cfg = if replaying { None } else { param.ai_config() };
+    if let Some(cfg) = &ai_cfg {
+        ai_prompt::PromptTemplate::load(cfg.prompt_template.as_deref())?;
+    }
     let (tx, mut rx) = broadcast::channel::<Command>(16);
     let (exit_tx, _exit_rx) = broadcast::channel::<()>(1);
     {
@@ -56,7 +81,12 @@ async fn main() -> Result<(), anyhow::Error> {
                         }
                         Ok(_) => continue,
                         Err(broadcast::error::RecvError::Closed) => break,
-                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            let message = format!("error log lagged, {skipped} messages dropped");
+                            if let Err(err) = error_log_store.append_message(message) {
+                                eprintln!("failed to persist error log: {err}");
+                            }
+                        }
                     },
                     signal = exit_rx.recv() => match signal {
                         Ok(_) | Err(broadcast::error::RecvError::Closed) => break,
@@ -66,7 +96,49 @@ async fn main() -> Result<(), anyhow::Error> {
             }
         });
     }
-    let trading_cfg = param.trading_config();
+    if let Some(every) = param.health_log_interval {
+        let health_rx = tx.subscribe();
+        let health_exit_rx = exit_tx.subscribe();
+        let health_tx = tx.clone();
+        task::spawn(async move {
+            let mut health = HealthLog::new(
+                HealthLog::default_path(),
+                every.as_duration(),
+                health_rx,
+                health_exit_rx,
+            );
+            if let Err(err) = health.run().await {
+                let _ = health_tx.send(Command::Error(format!("health log error: {err}")));
+            }
+        });
+    }
+    let record_task = param.record.clone().map(|path| {
+        let record_rx = tx.subscribe();
+        let record_exit_rx = exit_tx.subscribe();
+        let record_tx = tx.clone();
+        task::spawn(async move {
+            let mut recorder = SessionRecorder::new(path, record_rx, record_exit_rx);
+            if let Err(err) = recorder.run().await {
+                let _ = record_tx.send(Command::Error(format!("session record error: {err:#}")));
+            }
+        })
+    });
+    let mut trading_cfg = if replaying {
+        None
+    } else {
+        param.trading_config()
+    };
+    if let Some(cfg) = trading_cfg.as_mut() {
+        match okx::fetch_position_mode(cfg).await {
+            Ok(mode) => cfg.position_mode = mode,
+            Err(err) => {
+                let _ = tx.send(Command::Error(format!(
+                    "获取账户持仓模式失败，按{}处理: {err}",
+                    cfg.position_mode.label()
+                )));
+            }
+        }
+    }
     if let (Some(cfg), None) = (ai_cfg.as_ref(), trading_cfg.as_ref()) {
         let _ = tx.send(Command::Error(format!(
             "已启用 {} 集成，但缺少 OKX API 配置，无法获取账户信息",
@@ -99,7 +171,13 @@ async fn main() -> Result<(), anyhow::Error> {
         let td_mode = param.okx_td_mode.clone();
         let market_tx = tx.clone();
         let ai_cfg_for_market = ai_cfg.clone();
-        let ai_inst_ids = param.inst_ids.clone();
+        let (ai_inst_ids, ignored_ai_inst_ids) = param.ai_inst_ids();
+        if ai_cfg.is_some() && !ignored_ai_inst_ids.is_empty() {
+            let _ = tx.send(Command::Error(format!(
+                "AI 分析范围中的 {} 不在图表合约中，已忽略",
+                ignored_ai_inst_ids.join(", ")
+            )));
+        }
         let ai_start_ms = run_start_timestamp_ms;
         let ai_tx = tx.clone();
         let ai_order_tx = order_tx.clone();
@@ -153,18 +231,116 @@ async fn main() -> Result<(), anyhow::Error> {
     }
     if let Some(snapshot_cfg) = trading_cfg.clone() {
         let inst_ids = param.inst_ids.clone();
+        let td_mode = param.okx_td_mode.clone();
+        let resync_interval = param.account_resync_interval.as_duration();
         let snapshot_tx = tx.clone();
+        let mut resync_rx = tx.subscribe();
+        let mut exit_rx = exit_tx.subscribe();
         task::spawn(async move {
-            match okx::fetch_account_snapshot(&snapshot_cfg, &inst_ids).await {
-                Ok(snapshot) => {
-                    let filter = okx::inst_filter(&inst_ids);
-                    let state = SharedAccountState::global();
-                    state.update_filter(filter).await;
-                    state.seed(&snapshot).await;
-                    let _ = snapshot_tx.send(Command::AccountSnapshot(snapshot));
+            // 启动时拉取一次，之后定时或收到 ResyncAccount（界面积压、私有 WS 重连）时
+            // 重新拉取并覆盖共享账户状态
+            let state = SharedAccountState::global();
+            state.update_filter(okx::inst_filter(&inst_ids)).await;
+            let mut show_all = false;
+            let position_filter = |all: bool| {
+                if all {
+                    None
+                } else {
+                    okx::inst_filter(&inst_ids)
                 }
-                Err(err) => {
-                    let _ = snapshot_tx.send(Command::Error(format!("okx snapshot error: {err}")));
+            };
+            let mut known_markets: HashSet<String> = inst_ids.iter().cloned().collect();
+            loop {
+                let fetch = okx::fetch_account_snapshot(&snapshot_cfg, &inst_ids, show_all);
+                match state.resync(fetch).await {
+                    Ok(snapshot) => {
+                        // 全部持仓模式下图表外的合约缺少面值与精度，补拉后才能正常显示与平仓
+                        let missing: Vec<String> = snapshot
+                            .positions
+                            .iter()
+                            .map(|position| &position.inst_id)
+                            .chain(snapshot.open_orders.iter().map(|order| &order.inst_id))
+                            .filter(|inst_id| !known_markets.contains(*inst_id))
+                            .cloned()
+                            .collect::<HashSet<_>>()
+                            .into_iter()
+                            .collect();
+                        let _ = snapshot_tx.send(Command::AccountSnapshot(snapshot));
+                        if !missing.is_empty() {
+                            match okx::fetch_market_info(&td_mode, &snapshot_cfg, &missing).await {
+                                Ok(markets) => {
+                                    known_markets.extend(missing);
+                                    let _ = snapshot_tx.send(Command::MarketsAdded(markets));
+                                }
+                                Err(err) => {
+                                    let _ = snapshot_tx
+                                        .send(Command::Error(format!("获取币种信息失败: {err}")));
+                                }
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        let _ =
+                            snapshot_tx.send(Command::Error(format!("okx snapshot error: {err}")));
+                    }
+                }
+                // 拉取期间积压的重同步请求合并为一次，但持仓范围的切换需要立即重新拉取
+                let mut scope_changed = false;
+                while let Ok(message) = resync_rx.try_recv() {
+                    if let Command::ShowAllPositions(all) = message {
+                        show_all = all;
+                        scope_changed = true;
+                    }
+                }
+                if scope_changed {
+                    state.set_filter(position_filter(show_all)).await;
+                    continue;
+                }
+                let deadline = tokio::time::Instant::now() + resync_interval;
+                loop {
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(deadline) => break,
+                        message = resync_rx.recv() => match message {
+                            Ok(Command::ResyncAccount) => break,
+                            Ok(Command::ShowAllPositions(all)) => {
+                                show_all = all;
+                                state.set_filter(position_filter(show_all)).await;
+                                break;
+                            }
+                            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(broadcast::error::RecvError::Closed) => return,
+                        },
+                        signal = exit_rx.recv() => match signal {
+                            Ok(_) | Err(broadcast::error::RecvError::Closed) => return,
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        }
+                    }
+                }
+            }
+        });
+    }
+    if let Some(history_cfg) = trading_cfg.clone() {
+        let mut history_rx = tx.subscribe();
+        let mut exit_rx = exit_tx.subscribe();
+        let history_tx = tx.clone();
+        task::spawn(async move {
+            loop {
+                tokio::select! {
+                    message = history_rx.recv() => match message {
+                        Ok(Command::FetchOrderHistory(request)) => {
+                            let result = okx::fetch_order_history(&history_cfg, &request)
+                                .await
+                                .map_err(|err| format!("{err:#}"));
+                            let _ = history_tx.send(Command::OrderHistory(result));
+                        }
+                        Ok(_) => continue,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    },
+                    signal = exit_rx.recv() => match signal {
+                        Ok(_) | Err(broadcast::error::RecvError::Closed) => break,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    }
                 }
             }
         });
@@ -196,71 +372,285 @@ async fn main() -> Result<(), anyhow::Error> {
         });
     }
     let history_window = param.history_window();
-    let history_points =
-        match okx::bootstrap_history(&param.inst_ids, history_window, tx.clone()).await {
+    let history_points = if replaying {
+        Vec::new()
+    } else {
+        match okx::bootstrap_history(
+            &param.inst_ids,
+            history_window,
+            param.history_bar.as_deref(),
+            tx.clone(),
+        )
+        .await
+        {
             Ok(points) => points,
             Err(err) => {
                 let _ = tx.send(Command::Error(format!("history bootstrap error: {err}")));
                 Vec::new()
             }
-        };
-    let pok = param.clone();
-    let ttx = tx.clone();
-    task::spawn(async move {
-        let result = async {
-            let client = OkxWsClient::new(ttx.clone()).await?;
-            client.subscribe_mark_price(&pok.inst_ids).await
         }
-        .await;
+    };
+    // 回放模式不连接 OKX，行情与账户数据全部来自录制文件
+    if !replaying {
+        let pok = param.clone();
+        let ttx = tx.clone();
+        task::spawn(async move {
+            let result = async {
+                let client = OkxWsClient::new(ttx.clone(), pok.okx_demo).await?;
+                client.subscribe_mark_price(&pok.inst_ids).await
+            }
+            .await;
 
-        if let Err(err) = result {
-            let _ = ttx.send(Command::Error(format!("okx websocket error: {err}")));
+            if let Err(err) = result {
+                let _ = ttx.send(Command::Error(format!("okx websocket error: {err}")));
+            }
+        });
+        let book_inst_ids = param.inst_ids.clone();
+        let book_demo = param.okx_demo;
+        let book_tx = tx.clone();
+        task::spawn(async move {
+            let result = async {
+                let client = OkxWsClient::new(book_tx.clone(), book_demo).await?;
+                client.subscribe_books(&book_inst_ids).await
+            }
+            .await;
+            if let Err(err) = result {
+                let _ = book_tx.send(Command::Error(format!("okx order book error: {err}")));
+            }
+        });
+        let trades_inst_ids = param.inst_ids.clone();
+        let trades_demo = param.okx_demo;
+        let trades_tx = tx.clone();
+        task::spawn(async move {
+            let result = async {
+                let client = OkxWsClient::new(trades_tx.clone(), trades_demo).await?;
+                client.subscribe_trades(&trades_inst_ids).await
+            }
+            .await;
+            if let Err(err) = result {
+                let _ = trades_tx.send(Command::Error(format!("okx trades error: {err}")));
+            }
+        });
+        let ticker_inst_ids = param.inst_ids.clone();
+        let ticker_demo = param.okx_demo;
+        let ticker_tx = tx.clone();
+        task::spawn(async move {
+            let result = async {
+                let client = OkxWsClient::new(ticker_tx.clone(), ticker_demo).await?;
+                client.subscribe_tickers(&ticker_inst_ids).await
+            }
+            .await;
+            if let Err(err) = result {
+                let _ = ticker_tx.send(Command::Error(format!("okx ticker error: {err}")));
+            }
+        });
+        let funding_inst_ids = param.inst_ids.clone();
+        let funding_tx = tx.clone();
+        task::spawn(async move {
+            if let Err(err) = okx::stream_funding_rates(&funding_inst_ids, funding_tx.clone()).await
+            {
+                let _ = funding_tx.send(Command::Error(format!("funding rate error: {err}")));
+            }
+        });
+        let ratio_inst_ids = param.inst_ids.clone();
+        let ratio_period = param.long_short_period.clone();
+        let ratio_tx = tx.clone();
+        task::spawn(async move {
+            if let Err(err) = okx_analytics::stream_long_short_ratios(
+                &ratio_inst_ids,
+                &ratio_period,
+                ratio_tx.clone(),
+            )
+            .await
+            {
+                let _ = ratio_tx.send(Command::Error(format!("long/short ratio error: {err}")));
+            }
+        });
+        {
+            let mut add_rx = tx.subscribe();
+            let mut exit_rx = exit_tx.subscribe();
+            let add_tx = tx.clone();
+            let add_demo = param.okx_demo;
+            task::spawn(async move {
+                loop {
+                    tokio::select! {
+                        message = add_rx.recv() => match message {
+                            Ok(Command::AddInstrument(inst_id)) => {
+                                let track_tx = add_tx.clone();
+                                task::spawn(async move {
+                                    let label = inst_id.clone();
+                                    if let Err(err) =
+                                        okx::track_instrument(inst_id, add_demo, track_tx.clone()).await
+                                    {
+                                        let _ = track_tx.send(Command::Error(format!(
+                                            "okx websocket error for {label}: {err}"
+                                        )));
+                                    }
+                                });
+                            }
+                            Ok(_) => continue,
+                            Err(broadcast::error::RecvError::Closed) => break,
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        },
+                        signal = exit_rx.recv() => match signal {
+                            Ok(_) | Err(broadcast::error::RecvError::Closed) => break,
+                            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        }
+                    }
+                }
+            });
         }
-    });
-    let nrx = tx.subscribe();
-    let notify_tx = tx.clone();
-    let notify_exit_rx = exit_tx.subscribe();
-    task::spawn(async move {
-        let mut notifier = OsNotification::new(nrx, notify_exit_rx);
-        if let Err(err) = notifier.run().await {
-            let _ = notify_tx.send(Command::Error(format!("notification error: {err}")));
+    }
+    // 回放只驱动界面，提醒不发桌面通知、不响铃、不推送到远端
+    if !replaying {
+        let nrx = tx.subscribe();
+        let notify_tx = tx.clone();
+        let notify_exit_rx = exit_tx.subscribe();
+        task::spawn(async move {
+            let mut notifier = OsNotification::new(nrx, notify_exit_rx);
+            if let Err(err) = notifier.run().await {
+                let _ = notify_tx.send(Command::Error(format!("notification error: {err}")));
+            }
+        });
+        // 没有终端时（如重定向到文件）不响铃也不播放声音
+        let alert_sound_config = param.alert_sound_config();
+        if !alert_sound_config.is_empty() && io::stdout().is_terminal() {
+            let sound_tx = tx.clone();
+            let sound_rx = tx.subscribe();
+            let sound_exit_rx = exit_tx.subscribe();
+            task::spawn(async move {
+                let mut alert_sound = AlertSound::new(
+                    alert_sound_config,
+                    sound_tx.clone(),
+                    sound_rx,
+                    sound_exit_rx,
+                );
+                if let Err(err) = alert_sound.run().await {
+                    let _ = sound_tx.send(Command::Error(format!("alert sound error: {err}")));
+                }
+            });
         }
-    });
+        match notify::build_notifiers(&param.notifier_config()) {
+            Ok(notifiers) if !notifiers.is_empty() => {
+                let remote_tx = tx.clone();
+                let remote_rx = tx.subscribe();
+                let remote_exit_rx = exit_tx.subscribe();
+                task::spawn(async move {
+                    let mut remote = RemoteNotification::new(
+                        notifiers,
+                        remote_tx.clone(),
+                        remote_rx,
+                        remote_exit_rx,
+                    );
+                    if let Err(err) = remote.run().await {
+                        let _ = remote_tx
+                            .send(Command::Error(format!("remote notification error: {err}")));
+                    }
+                });
+            }
+            Ok(_) => {}
+            Err(err) => {
+                let _ = tx.send(Command::Error(format!("notifier init error: {err}")));
+            }
+        }
+    }
     let monitor_error_tx = tx.clone();
     let mtx = tx.clone();
     let mrx = tx.subscribe();
     let monitor_exit_rx = exit_tx.subscribe();
-    let thresholds = param.threshold_map();
+    // 命令行传入的阈值优先于 config.json 中保存的阈值
+    let mut thresholds = run_config.thresholds().clone();
+    thresholds.extend(param.threshold_map());
+    let monitor_thresholds = thresholds.clone();
+    let alert_cooldown = param.alert_cooldown.as_duration();
     task::spawn(async move {
-        let mut monitor = monitor::Monitor::new(thresholds, mtx, mrx, monitor_exit_rx);
+        let mut monitor = monitor::Monitor::new(
+            monitor_thresholds,
+            alert_cooldown,
+            mtx,
+            mrx,
+            monitor_exit_rx,
+        );
         if let Err(err) = monitor.run().await {
             let _ = monitor_error_tx.send(Command::Error(format!("monitor error: {err}")));
         }
     });
 
     let ai_label = ai_cfg.as_ref().map(|cfg| cfg.provider_label());
+    let ai_interval = ai_cfg.as_ref().map(|cfg| cfg.interval);
     let mut app = TuiApp::new(
         &param.inst_ids,
-        history_window,
+        param.chart_retention(),
         HashMap::new(),
         order_tx.clone(),
         ai_cfg.is_some(),
         ai_label,
+        ai_interval,
         trading_cfg.is_some(),
         timezone,
     );
+    app.set_redraw_gap(param.redraw_gap.as_duration());
+    app.set_stale_price_after(param.stale_price_after.as_duration());
+    app.set_export_dir(run_config.export_dir());
+    app.set_theme(run_config.theme());
+    app.set_chart_preferences(run_config.chart_preferences());
+    app.set_trade_log_retention(run_config.trade_log_retention());
+    app.set_precision_overrides(run_config.price_precision_overrides());
+    app.set_thresholds(thresholds, tx.clone(), run_config);
+    app.set_demo_mode(param.okx_demo);
+    app.set_dry_run(param.dry_run && trading_cfg.is_some());
+    app.set_confirm_orders(param.confirm_orders);
+    app.set_mouse_enabled(param.mouse);
+    app.set_replay_mode(replaying);
+    app.set_default_margin_mode(MarginMode::from_okx(&param.okx_td_mode));
+    if let Some(cfg) = trading_cfg.as_ref() {
+        app.set_position_mode(cfg.position_mode);
+    }
+    app.set_order_limits(param.order_limits());
     app.preload_trade_logs();
     app.preload_ai_insights();
     if !history_points.is_empty() {
         app.preload_history(&history_points);
     }
+    if let Some(path) = param.replay.clone() {
+        let replay_tx = tx.clone();
+        let replay_exit_rx = exit_tx.subscribe();
+        let speed = param.replay_speed;
+        task::spawn(async move {
+            if let Err(err) =
+                replay::replay_session(path, speed, replay_tx.clone(), replay_exit_rx).await
+            {
+                let _ = replay_tx.send(Command::Error(format!("session replay error: {err:#}")));
+            }
+        });
+    }
     let mut app_exit_rx = exit_tx.subscribe();
     let app_result = tokio::select! {
         result = app.run(&mut rx, &mut app_exit_rx) => result,
         _ = tokio::signal::ctrl_c() => Ok(()),
     };
+    // 默认保留挂单，仅在显式开启时于退出前撤单
+    let exit_cancel_summary = if param.cancel_orders_on_exit {
+        app.cancel_orders_on_exit(tx.subscribe(), param.exit_cancel_timeout.as_duration())
+            .await
+    } else {
+        Vec::new()
+    };
     let _ = exit_tx.send(());
     app.dispose();
+    // 录制按秒落盘，退出时等它写完缓冲区
+    if let Some(record_task) = record_task {
+        let _ = tokio::time::timeout(Duration::from_secs(2), record_task).await;
+    }
+    if !exit_cancel_summary.is_empty() {
+        let error_log_store = ErrorLogStore::new(ErrorLogStore::default_path());
+        for line in exit_cancel_summary {
+            eprintln!("{line}");
+            if let Err(err) = error_log_store.append_message(line) {
+                eprintln!("failed to persist error log: {err}");
+            }
+        }
+    }
     app_result.map_err(|err| anyhow!(err.to_string()))?;
     Ok(())
 }
@@ -1,18 +1,39 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 
 use tokio::sync::broadcast;
 
+use crate::command::PriceThreshold;
+use crate::indicators::{self, DEFAULT_ATR_PERIOD};
+
+// 只保留计算 ATR 所需的最近价格，Wilder 平滑需要一定的预热长度
+const ATR_HISTORY_LEN: usize = DEFAULT_ATR_PERIOD * 8;
+// 价格回到阈值内侧超过该比例才视为明确离开，重新允许立即提醒
+const ALERT_REARM_FRACTION: f64 = 0.002;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum BoundKind {
+    Lower,
+    Upper,
+}
+
 pub struct Monitor {
-    pub thresholds: HashMap<String, (f64, f64)>,
+    pub thresholds: HashMap<String, PriceThreshold>,
     pub tx: broadcast::Sender<crate::command::Command>,
     pub rx: broadcast::Receiver<crate::command::Command>,
     exit_rx: broadcast::Receiver<()>,
     price_precision: HashMap<String, usize>,
+    change_base: HashMap<String, f64>,
+    atr_history: HashMap<String, VecDeque<(f64, f64)>>,
+    atr_alerted: HashSet<String>,
+    alert_cooldown: Duration,
+    bound_alerted_at: HashMap<(String, BoundKind), Instant>,
 }
 
 impl Monitor {
     pub fn new(
-        thresholds: HashMap<String, (f64, f64)>,
+        thresholds: HashMap<String, PriceThreshold>,
+        alert_cooldown: Duration,
         tx: broadcast::Sender<crate::command::Command>,
         rx: broadcast::Receiver<crate::command::Command>,
         exit_rx: broadcast::Receiver<()>,
@@ -23,6 +44,11 @@ impl Monitor {
             rx,
             exit_rx,
             price_precision: HashMap::new(),
+            change_base: HashMap::new(),
+            atr_history: HashMap::new(),
+            atr_alerted: HashSet::new(),
+            alert_cooldown,
+            bound_alerted_at: HashMap::new(),
         }
     }
 
@@ -40,31 +66,23 @@ impl Monitor {
                 Event::Command(Ok(crate::command::Command::MarkPriceUpdate(
                     inst_id,
                     mark_price,
-                    _ts,
+                    ts,
                     precision,
                 ))) => {
                     self.update_precision(&inst_id, precision);
-                    let (lower, upper) = self.threshold_for(&inst_id);
-                    if mark_price < lower {
-                        let notify_msg = format!(
-                            "{} mark price {} is below lower bound {}",
-                            inst_id,
-                            self.format_price(&inst_id, mark_price),
-                            self.format_price(&inst_id, lower)
-                        );
-                        let _ = self
-                            .tx
-                            .send(crate::command::Command::Notify(inst_id.clone(), notify_msg));
-                    } else if mark_price > upper {
-                        let notify_msg = format!(
-                            "{} mark price {} is above upper bound {}",
-                            inst_id,
-                            self.format_price(&inst_id, mark_price),
-                            self.format_price(&inst_id, upper)
-                        );
-                        let _ = self
-                            .tx
-                            .send(crate::command::Command::Notify(inst_id.clone(), notify_msg));
+                    self.check_price(&inst_id, ts, mark_price);
+                }
+                Event::Command(Ok(crate::command::Command::SetThreshold(inst_id, threshold))) => {
+                    self.change_base.remove(&inst_id);
+                    self.atr_alerted.remove(&inst_id);
+                    self.bound_alerted_at.retain(|(id, _), _| *id != inst_id);
+                    if threshold.atr.is_none() {
+                        self.atr_history.remove(&inst_id);
+                    }
+                    if threshold.is_empty() {
+                        self.thresholds.remove(&inst_id);
+                    } else {
+                        self.thresholds.insert(inst_id, threshold);
                     }
                 }
                 Event::Command(Ok(_)) => {}
@@ -77,11 +95,133 @@ impl Monitor {
         Ok(())
     }
 
-    fn threshold_for(&self, inst_id: &str) -> (f64, f64) {
-        self.thresholds
-            .get(inst_id)
-            .copied()
-            .unwrap_or((0.0, f64::MAX))
+    fn check_price(&mut self, inst_id: &str, ts: i64, mark_price: f64) {
+        let threshold = self.thresholds.get(inst_id).copied().unwrap_or_default();
+        let cooldown = threshold
+            .cooldown_secs
+            .map_or(self.alert_cooldown, Duration::from_secs);
+        if let Some(lower) = threshold.lower {
+            self.check_bound(inst_id, BoundKind::Lower, mark_price, lower, cooldown);
+        }
+        if let Some(upper) = threshold.upper {
+            self.check_bound(inst_id, BoundKind::Upper, mark_price, upper, cooldown);
+        }
+        if let Some(change_pct) = threshold.change_pct {
+            self.check_change(inst_id, mark_price, change_pct);
+        }
+        if let Some(atr_limit) = threshold.atr {
+            self.check_atr(inst_id, ts, mark_price, atr_limit);
+        }
+    }
+
+    // 同一上下限在冷却期内只提醒一次，价格明确回到阈值内侧后重置
+    fn check_bound(
+        &mut self,
+        inst_id: &str,
+        kind: BoundKind,
+        mark_price: f64,
+        bound: f64,
+        cooldown: Duration,
+    ) {
+        let key = (inst_id.to_string(), kind);
+        let (breached, rearmed) = match kind {
+            BoundKind::Lower => (
+                mark_price < bound,
+                mark_price >= bound * (1.0 + ALERT_REARM_FRACTION),
+            ),
+            BoundKind::Upper => (
+                mark_price > bound,
+                mark_price <= bound * (1.0 - ALERT_REARM_FRACTION),
+            ),
+        };
+        if rearmed {
+            self.bound_alerted_at.remove(&key);
+            return;
+        }
+        if !breached {
+            return;
+        }
+        let now = Instant::now();
+        if self
+            .bound_alerted_at
+            .get(&key)
+            .is_some_and(|last| now.duration_since(*last) < cooldown)
+        {
+            return;
+        }
+        self.bound_alerted_at.insert(key, now);
+        let (direction, label) = match kind {
+            BoundKind::Lower => ("below", "lower"),
+            BoundKind::Upper => ("above", "upper"),
+        };
+        let notify_msg = format!(
+            "{} mark price {} is {direction} {label} bound {}",
+            inst_id,
+            self.format_price(inst_id, mark_price),
+            self.format_price(inst_id, bound)
+        );
+        let _ = self.tx.send(crate::command::Command::Notify(
+            inst_id.to_string(),
+            notify_msg,
+        ));
+    }
+
+    fn check_change(&mut self, inst_id: &str, mark_price: f64, change_pct: f64) {
+        let base = *self
+            .change_base
+            .entry(inst_id.to_string())
+            .or_insert(mark_price);
+        if base <= 0.0 {
+            return;
+        }
+        let change = (mark_price - base) / base * 100.0;
+        if change.abs() < change_pct {
+            return;
+        }
+        let notify_msg = format!(
+            "{} mark price {} moved {:+.2}% from {} (trigger {:.2}%)",
+            inst_id,
+            self.format_price(inst_id, mark_price),
+            change,
+            self.format_price(inst_id, base),
+            change_pct
+        );
+        self.change_base.insert(inst_id.to_string(), mark_price);
+        let _ = self.tx.send(crate::command::Command::Notify(
+            inst_id.to_string(),
+            notify_msg,
+        ));
+    }
+
+    // 只在 ATR 由下向上穿越阈值时提醒一次，回落到阈值以下后重新计算
+    fn check_atr(&mut self, inst_id: &str, ts: i64, mark_price: f64, atr_limit: f64) {
+        let history = self.atr_history.entry(inst_id.to_string()).or_default();
+        history.push_back((ts as f64, mark_price));
+        while history.len() > ATR_HISTORY_LEN {
+            history.pop_front();
+        }
+        let Some(atr) = indicators::latest_atr(history.make_contiguous(), DEFAULT_ATR_PERIOD)
+        else {
+            return;
+        };
+        if atr <= atr_limit {
+            self.atr_alerted.remove(inst_id);
+            return;
+        }
+        if !self.atr_alerted.insert(inst_id.to_string()) {
+            return;
+        }
+        let notify_msg = format!(
+            "{} ATR{} {} exceeds {} (volatility spike)",
+            inst_id,
+            DEFAULT_ATR_PERIOD,
+            self.format_price(inst_id, atr),
+            self.format_price(inst_id, atr_limit)
+        );
+        let _ = self.tx.send(crate::command::Command::Notify(
+            inst_id.to_string(),
+            notify_msg,
+        ));
     }
 
     fn update_precision(&mut self, inst_id: &str, precision: usize) {
@@ -103,3 +243,101 @@ impl Monitor {
         format!("{value:.prec$}", value = value, prec = precision)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Command;
+
+    const INST: &str = "BTC-USDT-SWAP";
+
+    fn monitor(
+        threshold: PriceThreshold,
+        cooldown: Duration,
+    ) -> (Monitor, broadcast::Receiver<Command>) {
+        let (tx, rx) = broadcast::channel(16);
+        let (_exit_tx, exit_rx) = broadcast::channel(1);
+        let thresholds = HashMap::from([(INST.to_string(), threshold)]);
+        let monitor = Monitor::new(thresholds, cooldown, tx.clone(), tx.subscribe(), exit_rx);
+        (monitor, rx)
+    }
+
+    fn upper(bound: f64) -> PriceThreshold {
+        PriceThreshold {
+            upper: Some(bound),
+            ..PriceThreshold::default()
+        }
+    }
+
+    fn alert_count(rx: &mut broadcast::Receiver<Command>) -> usize {
+        let mut count = 0;
+        while let Ok(command) = rx.try_recv() {
+            if matches!(command, Command::Notify(..)) {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    // 把上次提醒时间往前推，模拟冷却期已过去一段时间
+    fn age_alert(monitor: &mut Monitor, by: Duration) {
+        let last = monitor
+            .bound_alerted_at
+            .get_mut(&(INST.to_string(), BoundKind::Upper))
+            .unwrap();
+        *last = last.checked_sub(by).unwrap();
+    }
+
+    #[test]
+    fn test_bound_alert_suppressed_during_cooldown() {
+        let (mut monitor, mut rx) = monitor(upper(100.0), Duration::from_secs(60));
+        monitor.check_price(INST, 0, 101.0);
+        assert_eq!(alert_count(&mut rx), 1);
+        monitor.check_price(INST, 1, 102.0);
+        age_alert(&mut monitor, Duration::from_secs(30));
+        monitor.check_price(INST, 2, 103.0);
+        assert_eq!(alert_count(&mut rx), 0);
+    }
+
+    #[test]
+    fn test_bound_realerts_after_cooldown() {
+        let (mut monitor, mut rx) = monitor(upper(100.0), Duration::from_secs(60));
+        monitor.check_price(INST, 0, 101.0);
+        assert_eq!(alert_count(&mut rx), 1);
+        age_alert(&mut monitor, Duration::from_secs(61));
+        monitor.check_price(INST, 1, 101.0);
+        assert_eq!(alert_count(&mut rx), 1);
+    }
+
+    #[test]
+    fn test_bound_rearm_resets_cooldown() {
+        let (mut monitor, mut rx) = monitor(upper(100.0), Duration::from_secs(60));
+        monitor.check_price(INST, 0, 101.0);
+        // 回落不足 0.2% 不算离开，仍在冷却期内
+        monitor.check_price(INST, 1, 99.9);
+        monitor.check_price(INST, 2, 101.0);
+        assert_eq!(alert_count(&mut rx), 1);
+        monitor.check_price(INST, 3, 99.7);
+        monitor.check_price(INST, 4, 101.0);
+        assert_eq!(alert_count(&mut rx), 1);
+    }
+
+    #[test]
+    fn test_instrument_cooldown_overrides_global() {
+        let threshold = PriceThreshold {
+            cooldown_secs: Some(5),
+            ..upper(100.0)
+        };
+        let (mut monitor, mut rx) = monitor(threshold, Duration::from_secs(600));
+        monitor.check_price(INST, 0, 101.0);
+        assert_eq!(alert_count(&mut rx), 1);
+        age_alert(&mut monitor, Duration::from_secs(6));
+        monitor.check_price(INST, 1, 101.0);
+        assert_eq!(alert_count(&mut rx), 1);
+
+        monitor.thresholds.insert(INST.to_string(), upper(100.0));
+        age_alert(&mut monitor, Duration::from_secs(6));
+        monitor.check_price(INST, 2, 101.0);
+        assert_eq!(alert_count(&mut rx), 0);
+    }
+}
@@ -1,9 +1,22 @@
+use std::collections::HashMap;
 use std::io::{self, Write};
+use std::path::Path;
+use std::process::Stdio;
 use std::time::Duration;
 
+use anyhow::{Context, anyhow};
+use futures_util::future::BoxFuture;
+use reqwest::{Client, ClientBuilder};
+use serde::Serialize;
 use tokio::sync::broadcast;
 
 use crate::command::Command;
+use crate::config::{AlertSoundConfig, NotifierConfig, TelegramConfig};
+
+const TELEGRAM_API_BASE: &str = "https://api.telegram.org";
+// 推送失败时回报的错误前缀，用于避免失败信息再次被推送形成循环
+const REMOTE_ERROR_PREFIX: &str = "remote notifier";
+const ALERT_SOUND_ERROR_PREFIX: &str = "alert sound";
 
 pub struct OsNotification {
     pub rx: broadcast::Receiver<Command>,
@@ -50,6 +63,288 @@ impl OsNotification {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AlertSeverity {
+    Info,
+    Error,
+}
+
+// 提醒与错误分别限频，避免阈值来回穿越时连续响铃
+pub struct AlertSound {
+    rx: broadcast::Receiver<Command>,
+    exit_rx: broadcast::Receiver<()>,
+    tx: broadcast::Sender<Command>,
+    config: AlertSoundConfig,
+    interval: Duration,
+}
+
+impl AlertSound {
+    pub fn new(
+        config: AlertSoundConfig,
+        tx: broadcast::Sender<Command>,
+        rx: broadcast::Receiver<Command>,
+        exit_rx: broadcast::Receiver<()>,
+    ) -> AlertSound {
+        AlertSound {
+            rx,
+            exit_rx,
+            tx,
+            config,
+            interval: Duration::from_secs(10),
+        }
+    }
+
+    pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        let mut last_info: Option<tokio::time::Instant> = None;
+        let mut last_error: Option<tokio::time::Instant> = None;
+        loop {
+            let severity = tokio::select! {
+                result = self.rx.recv() => match result {
+                    Ok(Command::Notify(_, _)) => AlertSeverity::Info,
+                    Ok(Command::Error(msg)) if !msg.starts_with(ALERT_SOUND_ERROR_PREFIX) => {
+                        AlertSeverity::Error
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                signal = self.exit_rx.recv() => match signal {
+                    Ok(_) | Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                }
+            };
+            let (last, sound) = match severity {
+                AlertSeverity::Info => (&mut last_info, self.config.info_sound.as_deref()),
+                AlertSeverity::Error => (&mut last_error, self.config.error_sound.as_deref()),
+            };
+            if sound.is_none() && !self.config.bell {
+                continue;
+            }
+            if last.is_some_and(|fired| fired.elapsed() <= self.interval) {
+                continue;
+            }
+            *last = Some(tokio::time::Instant::now());
+            let result = match sound {
+                Some(path) => play_sound(&self.config.player, path),
+                None => ring_bell(),
+            };
+            if let Err(err) = result {
+                let _ = self.tx.send(Command::Error(format!(
+                    "{ALERT_SOUND_ERROR_PREFIX} {err:#}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn ring_bell() -> Result<(), anyhow::Error> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\u{7}")?;
+    stdout.flush()?;
+    Ok(())
+}
+
+// 播放器输出会破坏 TUI 画面，全部丢弃；子进程在后台回收
+fn play_sound(player: &str, path: &Path) -> Result<(), anyhow::Error> {
+    let mut child = tokio::process::Command::new(player)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("failed to run {player} {}", path.display()))?;
+    tokio::spawn(async move {
+        let _ = child.wait().await;
+    });
+    Ok(())
+}
+
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn send<'a>(&'a self, title: &'a str, body: &'a str) -> BoxFuture<'a, anyhow::Result<()>>;
+}
+
+pub fn build_notifiers(config: &NotifierConfig) -> anyhow::Result<Vec<Box<dyn Notifier>>> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+    if config.is_empty() {
+        return Ok(notifiers);
+    }
+    let http = ClientBuilder::new()
+        .connect_timeout(Duration::from_secs(5))
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    if let Some(url) = &config.webhook_url {
+        notifiers.push(Box::new(WebhookNotifier {
+            http: http.clone(),
+            url: url.clone(),
+        }));
+    }
+    if let Some(telegram) = &config.telegram {
+        notifiers.push(Box::new(TelegramNotifier::new(http, telegram)));
+    }
+    Ok(notifiers)
+}
+
+pub struct WebhookNotifier {
+    http: Client,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    title: &'a str,
+    message: &'a str,
+    timestamp_ms: i64,
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn send<'a>(&'a self, title: &'a str, body: &'a str) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let payload = WebhookPayload {
+                title,
+                message: body,
+                timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            };
+            let response = self
+                .http
+                .post(&self.url)
+                .json(&payload)
+                .send()
+                .await
+                .map_err(|err| anyhow!("request failed: {}", err.without_url()))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(anyhow!("HTTP {status}"));
+            }
+            Ok(())
+        })
+    }
+}
+
+pub struct TelegramNotifier {
+    http: Client,
+    endpoint: String,
+    chat_id: String,
+}
+
+#[derive(Serialize)]
+struct TelegramMessage<'a> {
+    chat_id: &'a str,
+    text: String,
+    disable_web_page_preview: bool,
+}
+
+impl TelegramNotifier {
+    fn new(http: Client, config: &TelegramConfig) -> Self {
+        TelegramNotifier {
+            http,
+            endpoint: format!("{TELEGRAM_API_BASE}/bot{}/sendMessage", config.bot_token),
+            chat_id: config.chat_id.clone(),
+        }
+    }
+}
+
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn send<'a>(&'a self, title: &'a str, body: &'a str) -> BoxFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let message = TelegramMessage {
+                chat_id: &self.chat_id,
+                text: format!("{title}\n{body}"),
+                disable_web_page_preview: true,
+            };
+            // 错误信息去掉 URL，避免 bot token 写进错误日志
+            let response = self
+                .http
+                .post(&self.endpoint)
+                .json(&message)
+                .send()
+                .await
+                .map_err(|err| anyhow!("request failed: {}", err.without_url()))?;
+            let status = response.status();
+            if !status.is_success() {
+                let detail = response.text().await.unwrap_or_default();
+                return Err(anyhow!("HTTP {status}: {}", detail.trim()));
+            }
+            Ok(())
+        })
+    }
+}
+
+pub struct RemoteNotification {
+    rx: broadcast::Receiver<Command>,
+    exit_rx: broadcast::Receiver<()>,
+    tx: broadcast::Sender<Command>,
+    notifiers: Vec<Box<dyn Notifier>>,
+    interval: Duration,
+}
+
+impl RemoteNotification {
+    pub fn new(
+        notifiers: Vec<Box<dyn Notifier>>,
+        tx: broadcast::Sender<Command>,
+        rx: broadcast::Receiver<Command>,
+        exit_rx: broadcast::Receiver<()>,
+    ) -> RemoteNotification {
+        RemoteNotification {
+            rx,
+            exit_rx,
+            tx,
+            notifiers,
+            interval: Duration::from_secs(10),
+        }
+    }
+
+    pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        // 按标题分别节流：各合约的价格提醒与错误互不挤占
+        let mut last_sent: HashMap<String, tokio::time::Instant> = HashMap::new();
+        loop {
+            let (title, body) = tokio::select! {
+                result = self.rx.recv() => match result {
+                    Ok(Command::Notify(inst_id, msg)) => (format!("Price Monitor - {inst_id}"), msg),
+                    Ok(Command::Error(msg)) if !msg.starts_with(REMOTE_ERROR_PREFIX) => {
+                        ("Trade AI Error".to_string(), msg)
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                signal = self.exit_rx.recv() => match signal {
+                    Ok(_) | Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                }
+            };
+            if last_sent
+                .get(&title)
+                .is_some_and(|sent| sent.elapsed() <= self.interval)
+            {
+                continue;
+            }
+            last_sent.insert(title.clone(), tokio::time::Instant::now());
+            for notifier in &self.notifiers {
+                let result = notifier
+                    .send(&title, &body)
+                    .await
+                    .with_context(|| format!("{} push failed", notifier.name()));
+                if let Err(err) = result {
+                    let _ = self
+                        .tx
+                        .send(Command::Error(format!("{REMOTE_ERROR_PREFIX} {err:#}")));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 fn terminal_notify(inst_id: &str, msg: &str) -> Result<(), anyhow::Error> {
     let title = format!("Price Monitor - {inst_id}");
     let sanitized_title = sanitize_osc_field(&title);
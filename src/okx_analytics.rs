@@ -3,21 +3,25 @@ use std::time::Duration;
 use anyhow::{Context, Result, anyhow};
 use reqwest::Client;
 use serde::Deserialize;
+use tokio::sync::broadcast;
+use tokio::time::interval;
 
 use crate::{
+    command::Command,
     config::TradingConfig,
     okx::{self, LongShortRatio, OkxResponse},
 };
 
-const MARKET_CANDLES_ENDPOINT: &str = "https://www.okx.com/api/v5/market/candles";
-const FUNDING_RATE_ENDPOINT: &str = "https://www.okx.com/api/v5/public/funding-rate";
-const OPEN_INTEREST_ENDPOINT: &str = "https://www.okx.com/api/v5/public/open-interest";
-const OPEN_INTEREST_HISTORY_ENDPOINT: &str =
-    "https://www.okx.com/api/v5/rubik/stat/contracts/open-interest-history";
-const TAKER_VOLUME_ENDPOINT: &str = "https://www.okx.com/api/v5/rubik/stat/taker-volume-contract";
+const MARKET_CANDLES_ENDPOINT: &str = "/api/v5/market/candles";
+const FUNDING_RATE_ENDPOINT: &str = "/api/v5/public/funding-rate";
+const OPEN_INTEREST_ENDPOINT: &str = "/api/v5/public/open-interest";
+const OPEN_INTEREST_HISTORY_ENDPOINT: &str = "/api/v5/rubik/stat/contracts/open-interest-history";
+const TAKER_VOLUME_ENDPOINT: &str = "/api/v5/rubik/stat/taker-volume-contract";
 
 const LONG_SHORT_ACCOUNT_RATIO_ENDPOINT: &str =
-    "https://www.okx.com/api/v5/rubik/stat/contracts/long-short-account-ratio-contract";
+    "/api/v5/rubik/stat/contracts/long-short-account-ratio-contract";
+
+const LONG_SHORT_RATIO_REFRESH: Duration = Duration::from_secs(60);
 
 const ANALYTICS_INTRADAY_LIMIT: usize = 160;
 const ANALYTICS_SWING_LIMIT: usize = 120;
@@ -53,6 +57,8 @@ pub struct InstrumentAnalytics {
     pub intraday_3m_rsi14: Vec<f64>,
 
     pub intraday_5m_prices: Vec<f64>,
+    // 完整的 5 分钟收盘价，仅用于在提示词中计算最新指标
+    pub intraday_5m_history: Vec<f64>,
     pub intraday_5m_ema20: Vec<f64>,
     pub intraday_5m_macd: Vec<f64>,
     pub intraday_5m_rsi7: Vec<f64>,
@@ -112,13 +118,54 @@ pub struct MarketDataFetcher {
     http: Client,
 }
 
+fn build_analytics_client() -> Result<Client> {
+    Ok(okx::with_proxy(Client::builder())
+        .connect_timeout(Duration::from_secs(5))
+        .read_timeout(Duration::from_secs(10))
+        .timeout(Duration::from_secs(20))
+        .build()?)
+}
+
+// 多空比只对合约有效；单次请求失败只上报错误，不中断轮询
+pub async fn stream_long_short_ratios(
+    inst_ids: &[String],
+    period: &str,
+    tx: broadcast::Sender<Command>,
+) -> Result<()> {
+    let contracts: Vec<&String> = inst_ids
+        .iter()
+        .filter(|inst_id| !okx::is_spot_inst(inst_id))
+        .collect();
+    if contracts.is_empty() {
+        return Ok(());
+    }
+    let http = build_analytics_client()?;
+    let mut ticker = interval(LONG_SHORT_RATIO_REFRESH);
+    loop {
+        ticker.tick().await;
+        for inst_id in &contracts {
+            match fetch_long_short_account_ratio(&http, inst_id, period).await {
+                Ok(ratios) => {
+                    if tx
+                        .send(Command::LongShortRatio(inst_id.to_string(), ratios))
+                        .is_err()
+                    {
+                        return Ok(());
+                    }
+                }
+                Err(err) => {
+                    let _ = tx.send(Command::Error(format!(
+                        "long/short ratio error for {inst_id}: {err:#}"
+                    )));
+                }
+            }
+        }
+    }
+}
+
 impl MarketDataFetcher {
     pub fn new(_trading_config: TradingConfig) -> Result<Self> {
-        let http = Client::builder()
-            .connect_timeout(Duration::from_secs(5))
-            .read_timeout(Duration::from_secs(10))
-            .timeout(Duration::from_secs(20))
-            .build()?;
+        let http = build_analytics_client()?;
         Ok(MarketDataFetcher { http })
     }
 
@@ -178,14 +225,21 @@ impl MarketDataFetcher {
         let rsi14_swing = compute_rsi(&closes_swing, RSI_LONG_PERIOD);
         let atr3_swing = compute_atr(&swing, ATR_FAST_PERIOD);
         let atr14_swing = compute_atr(&swing, ATR_SLOW_PERIOD);
-        let oi_stats = self.fetch_open_interest(inst_id).await?;
-        let funding_rate = self.fetch_funding_rate(inst_id).await?;
         let current_price = self.price_for_inst(inst_id).await?;
         let swing_volume_current = swing_volumes.last().copied();
         let swing_volume_avg = average_tail(&swing_volumes, VOLUME_AVG_PERIOD);
-        let taker_volume_5m = self.fetch_taker_volume(inst_id, "5m").await?;
-        let long_short_account_ratio_5m =
-            self.fetch_long_short_account_ratio(inst_id, "5m").await?;
+        // 持仓量、资金费率、多空比等只对合约有效，现货跳过
+        let (oi_stats, funding_rate, taker_volume_5m, long_short_account_ratio_5m) =
+            if okx::is_spot_inst(inst_id) {
+                (OpenInterestStats::default(), None, Vec::new(), Vec::new())
+            } else {
+                (
+                    self.fetch_open_interest(inst_id).await?,
+                    self.fetch_funding_rate(inst_id).await?,
+                    self.fetch_taker_volume(inst_id, "5m").await?,
+                    fetch_long_short_account_ratio(&self.http, inst_id, "5m").await?,
+                )
+            };
         Ok(InstrumentAnalytics {
             inst_id: inst_id.to_string(),
             symbol: inst_symbol(inst_id),
@@ -202,6 +256,7 @@ impl MarketDataFetcher {
             intraday_3m_rsi7: take_tail(&rsi7_3m, ANALYTICS_SERIES_TAIL),
             intraday_3m_rsi14: take_tail(&rsi14_3m, ANALYTICS_SERIES_TAIL),
             intraday_5m_prices: take_tail(&closes_intraday, ANALYTICS_SERIES_TAIL),
+            intraday_5m_history: closes_intraday.clone(),
             intraday_5m_ema20: take_tail(&ema20_intraday, ANALYTICS_SERIES_TAIL),
             intraday_5m_macd: take_tail(&macd_intraday, ANALYTICS_SERIES_TAIL),
             intraday_5m_rsi7: take_tail(&rsi7_intraday, ANALYTICS_SERIES_TAIL),
@@ -233,7 +288,7 @@ impl MarketDataFetcher {
         let limit_str = limit.to_string();
         let response: MarketCandleResponse = self
             .http
-            .get(MARKET_CANDLES_ENDPOINT)
+            .get(okx::api_url(MARKET_CANDLES_ENDPOINT))
             .query(&[
                 ("instId", inst_id),
                 ("bar", bar),
@@ -267,7 +322,7 @@ impl MarketDataFetcher {
     pub async fn fetch_funding_rate(&self, inst_id: &str) -> Result<Option<f64>> {
         let response: FundingRateResponse = self
             .http
-            .get(FUNDING_RATE_ENDPOINT)
+            .get(okx::api_url(FUNDING_RATE_ENDPOINT))
             .query(&[("instId", inst_id)])
             .send()
             .await
@@ -306,7 +361,7 @@ impl MarketDataFetcher {
     async fn fetch_open_interest_latest(&self, inst_id: &str) -> Result<Option<f64>> {
         let response: OpenInterestResponse = self
             .http
-            .get(OPEN_INTEREST_ENDPOINT)
+            .get(okx::api_url(OPEN_INTEREST_ENDPOINT))
             .query(&[("instType", "SWAP"), ("instId", inst_id)])
             .send()
             .await
@@ -328,7 +383,7 @@ impl MarketDataFetcher {
     async fn fetch_open_interest_history(&self, inst_id: &str) -> Result<Vec<f64>> {
         let response: OpenInterestHistoryResponse = self
             .http
-            .get(OPEN_INTEREST_HISTORY_ENDPOINT)
+            .get(okx::api_url(OPEN_INTEREST_HISTORY_ENDPOINT))
             .query(&[("instType", "SWAP"), ("instId", inst_id), ("period", "6H")])
             .send()
             .await
@@ -359,7 +414,7 @@ impl MarketDataFetcher {
     async fn fetch_taker_volume(&self, inst_id: &str, period: &str) -> Result<Vec<TakerVolume>> {
         let response: OkxResponse<Vec<TakerVolumeEntry>> = self
             .http
-            .get(TAKER_VOLUME_ENDPOINT)
+            .get(okx::api_url(TAKER_VOLUME_ENDPOINT))
             .query(&[("instId", inst_id), ("period", period), ("limit", "10")])
             .send()
             .await
@@ -389,39 +444,38 @@ impl MarketDataFetcher {
         volumes.sort_by_key(|v| v.timestamp_ms);
         Ok(volumes)
     }
+}
 
-    async fn fetch_long_short_account_ratio(
-        &self,
-        inst_id: &str,
-        period: &str,
-    ) -> Result<Vec<LongShortRatio>> {
-        let response: OkxResponse<Vec<LongShortRatioEntry>> = self
-            .http
-            .get(LONG_SHORT_ACCOUNT_RATIO_ENDPOINT)
-            .query(&[("instId", inst_id), ("period", period), ("limit", "10")])
-            .send()
-            .await
-            .with_context(|| format!("请求 {} 买卖成交量失败", inst_id))?
-            .json()
-            .await
-            .with_context(|| format!("解析 {} 买卖成交量失败", inst_id))?;
-        if response.code != "0" {
-            return Err(anyhow!(
-                "{} long-short-account-ratio-contract failed (code {}): {}",
-                inst_id,
-                response.code,
-                response.msg
-            ));
-        }
-        let mut ratios = Vec::new();
-        for entry in response.data {
-            let ts = entry.ts.parse().unwrap_or(0);
-            let ratio = parse_f64(&entry.long_short_acct_ratio).unwrap_or(0.0);
-            ratios.push(LongShortRatio { ts, ratio });
-        }
-        ratios.sort_by_key(|e| e.ts);
-        Ok(ratios)
+async fn fetch_long_short_account_ratio(
+    http: &Client,
+    inst_id: &str,
+    period: &str,
+) -> Result<Vec<LongShortRatio>> {
+    let response: OkxResponse<Vec<LongShortRatioEntry>> = http
+        .get(okx::api_url(LONG_SHORT_ACCOUNT_RATIO_ENDPOINT))
+        .query(&[("instId", inst_id), ("period", period), ("limit", "10")])
+        .send()
+        .await
+        .with_context(|| format!("请求 {} 买卖成交量失败", inst_id))?
+        .json()
+        .await
+        .with_context(|| format!("解析 {} 买卖成交量失败", inst_id))?;
+    if response.code != "0" {
+        return Err(anyhow!(
+            "{} long-short-account-ratio-contract failed (code {}): {}",
+            inst_id,
+            response.code,
+            response.msg
+        ));
+    }
+    let mut ratios = Vec::new();
+    for entry in response.data {
+        let ts = entry.ts.parse().unwrap_or(0);
+        let ratio = parse_f64(&entry.long_short_acct_ratio).unwrap_or(0.0);
+        ratios.push(LongShortRatio { ts, ratio });
     }
+    ratios.sort_by_key(|e| e.ts);
+    Ok(ratios)
 }
 
 #[derive(Debug, Clone)]
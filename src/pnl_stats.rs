@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::command::{TradeEvent, TradeFill};
+use crate::config::ConfiguredTimeZone;
+use crate::okx;
+use crate::trade_log::TradeLogEntry;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum StatsPeriod {
+    #[default]
+    Today,
+    Week,
+    All,
+}
+
+impl StatsPeriod {
+    pub fn next(&self) -> Self {
+        match self {
+            StatsPeriod::Today => StatsPeriod::Week,
+            StatsPeriod::Week => StatsPeriod::All,
+            StatsPeriod::All => StatsPeriod::Today,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsPeriod::Today => "今日",
+            StatsPeriod::Week => "近 7 日",
+            StatsPeriod::All => "全部",
+        }
+    }
+
+    // 按配置时区的自然日划分，返回起始日（含）
+    fn start_day(&self, timezone: ConfiguredTimeZone, now_ms: i64) -> Option<String> {
+        let days_back = match self {
+            StatsPeriod::Today => 0,
+            StatsPeriod::Week => 6,
+            StatsPeriod::All => return None,
+        };
+        timezone.format_timestamp(now_ms - days_back * DAY_MS, DAY_FORMAT)
+    }
+}
+
+// 按数值大小决定小数位，大额盈亏不再拖出一长串小数
+pub fn format_pnl(value: f64) -> String {
+    let abs = value.abs();
+    let precision = if abs >= 1000.0 {
+        2
+    } else if abs >= 1.0 {
+        4
+    } else {
+        6
+    };
+    let formatted = format!("{value:+.precision$}");
+    if formatted
+        .trim_start_matches(['+', '-'])
+        .trim_matches(['0', '.'])
+        .is_empty()
+    {
+        return "0".to_string();
+    }
+    formatted
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PnlSummary {
+    pub realized_pnl: f64,
+    pub fees: f64,
+    pub fills: usize,
+    // 按订单计：一笔平仓单分多次成交只算一次
+    pub closing_trades: usize,
+    pub wins: usize,
+}
+
+impl PnlSummary {
+    // OKX 的手续费为负数表示扣费，净收益直接相加
+    pub fn net(&self) -> f64 {
+        self.realized_pnl + self.fees
+    }
+
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.closing_trades > 0).then(|| self.wins as f64 / self.closing_trades as f64)
+    }
+}
+
+#[derive(Default)]
+struct SummaryBuilder {
+    summary: PnlSummary,
+    order_pnl: HashMap<String, f64>,
+}
+
+impl SummaryBuilder {
+    fn add(&mut self, fill: &TradeFill, pnl: f64) {
+        self.summary.fills += 1;
+        self.summary.fees += fill_fee(fill);
+        if pnl != 0.0 {
+            self.summary.realized_pnl += pnl;
+            *self.order_pnl.entry(fill.order_id.clone()).or_default() += pnl;
+        }
+    }
+
+    fn finish(self) -> PnlSummary {
+        let mut summary = self.summary;
+        summary.closing_trades = self.order_pnl.len();
+        summary.wins = self.order_pnl.values().filter(|pnl| **pnl > 0.0).count();
+        summary
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PnlStats {
+    pub by_inst: BTreeMap<String, PnlSummary>,
+    pub by_day: BTreeMap<String, PnlSummary>,
+    pub total: PnlSummary,
+}
+
+pub fn summarize(
+    entries: &[TradeLogEntry],
+    period: StatsPeriod,
+    timezone: ConfiguredTimeZone,
+    now_ms: i64,
+) -> PnlStats {
+    let start_day = period.start_day(timezone, now_ms);
+    let mut by_inst: BTreeMap<String, SummaryBuilder> = BTreeMap::new();
+    let mut by_day: BTreeMap<String, SummaryBuilder> = BTreeMap::new();
+    let mut total = SummaryBuilder::default();
+    // 旧日志没有 fill_pnl，用同一订单累计 pnl 的增量代替；需从头遍历以免跨区间的订单算错
+    let mut order_cum_pnl: HashMap<&str, f64> = HashMap::new();
+    for entry in entries {
+        let TradeEvent::Fill(fill) = &entry.event else {
+            continue;
+        };
+        let pnl = match (fill.fill_pnl, fill.pnl) {
+            (Some(pnl), _) => pnl,
+            (None, Some(cum)) => {
+                let prev = order_cum_pnl.insert(fill.order_id.as_str(), cum);
+                cum - prev.unwrap_or_default()
+            }
+            (None, None) => 0.0,
+        };
+        let Some(day) = timezone.format_timestamp(entry.timestamp_ms(), DAY_FORMAT) else {
+            continue;
+        };
+        if start_day.as_ref().is_some_and(|start| day < *start) {
+            continue;
+        }
+        by_inst
+            .entry(fill.inst_id.clone())
+            .or_default()
+            .add(fill, pnl);
+        by_day.entry(day).or_default().add(fill, pnl);
+        total.add(fill, pnl);
+    }
+    PnlStats {
+        by_inst: finish_all(by_inst),
+        by_day: finish_all(by_day),
+        total: total.finish(),
+    }
+}
+
+fn finish_all(builders: BTreeMap<String, SummaryBuilder>) -> BTreeMap<String, PnlSummary> {
+    builders
+        .into_iter()
+        .map(|(key, builder)| (key, builder.finish()))
+        .collect()
+}
+
+// 现货买入的手续费以基础币扣除，按成交价折算成计价币
+fn fill_fee(fill: &TradeFill) -> f64 {
+    let fee = fill.fee.unwrap_or_default();
+    let base_ccy = fill.inst_id.split('-').next().unwrap_or_default();
+    match fill.fee_currency.as_deref() {
+        Some(ccy) if okx::is_spot_inst(&fill.inst_id) && ccy == base_ccy => fee * fill.price,
+        _ => fee,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::TradeSide;
+    use chrono::{FixedOffset, TimeZone, Utc};
+
+    fn ts(y: i32, m: u32, d: u32, h: u32) -> i64 {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0)
+            .unwrap()
+            .timestamp_millis()
+    }
+
+    fn fill_entry(
+        order_id: &str,
+        fill_time: i64,
+        pnl: Option<f64>,
+        fill_pnl: Option<f64>,
+    ) -> TradeLogEntry {
+        let fill = TradeFill {
+            inst_id: "BTC-USDT-SWAP".to_string(),
+            side: TradeSide::Sell,
+            price: 50000.0,
+            size: 1.0,
+            order_id: order_id.to_string(),
+            pos_side: None,
+            trade_id: None,
+            exec_type: None,
+            fill_time: Some(fill_time),
+            fee: Some(-0.5),
+            fee_currency: Some("USDT".to_string()),
+            pnl,
+            fill_pnl,
+            acc_fill_size: None,
+            avg_price: None,
+            leverage: None,
+            tag: None,
+            cl_ord_id: None,
+        };
+        TradeLogEntry::from_event(TradeEvent::Fill(Box::new(fill)), None)
+    }
+
+    fn utc() -> ConfiguredTimeZone {
+        ConfiguredTimeZone::Fixed(FixedOffset::east_opt(0).unwrap())
+    }
+
+    #[test]
+    fn test_summarize_period_cutoffs() {
+        let now = ts(2024, 3, 10, 12);
+        let entries: Vec<_> = [
+            ts(2024, 2, 1, 12),
+            ts(2024, 3, 3, 23),
+            ts(2024, 3, 4, 0),
+            ts(2024, 3, 7, 12),
+            ts(2024, 3, 10, 1),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(idx, time)| fill_entry(&idx.to_string(), *time, None, None))
+        .collect();
+        let fills = |period| summarize(&entries, period, utc(), now).total.fills;
+        assert_eq!(fills(StatsPeriod::Today), 1);
+        // 近 7 日含今天，起始日 03-04 零点
+        assert_eq!(fills(StatsPeriod::Week), 3);
+        assert_eq!(fills(StatsPeriod::All), 5);
+    }
+
+    #[test]
+    fn test_summarize_day_buckets_in_configured_zone() {
+        let east8 = ConfiguredTimeZone::Fixed(FixedOffset::east_opt(8 * 3600).unwrap());
+        let entries = vec![
+            fill_entry("1", ts(2024, 3, 9, 15), None, Some(1.0)),
+            fill_entry("2", ts(2024, 3, 9, 17), None, Some(2.0)),
+        ];
+        let stats = summarize(&entries, StatsPeriod::All, east8, ts(2024, 3, 10, 12));
+        let days: Vec<_> = stats.by_day.keys().cloned().collect();
+        assert_eq!(days, vec!["2024-03-09", "2024-03-10"]);
+        assert_eq!(stats.by_day["2024-03-10"].realized_pnl, 2.0);
+        let utc_stats = summarize(&entries, StatsPeriod::All, utc(), ts(2024, 3, 10, 12));
+        assert_eq!(utc_stats.by_day.len(), 1);
+    }
+
+    #[test]
+    fn test_summarize_counts_partial_close_once() {
+        let now = ts(2024, 3, 10, 12);
+        let expected = PnlSummary {
+            realized_pnl: 4.0,
+            fees: -1.5,
+            fills: 3,
+            closing_trades: 2,
+            wins: 1,
+        };
+        let entries = vec![
+            fill_entry("A", ts(2024, 3, 10, 1), Some(3.0), Some(3.0)),
+            fill_entry("A", ts(2024, 3, 10, 2), Some(5.0), Some(2.0)),
+            fill_entry("B", ts(2024, 3, 10, 3), Some(-1.0), Some(-1.0)),
+        ];
+        assert_eq!(
+            summarize(&entries, StatsPeriod::Today, utc(), now).total,
+            expected
+        );
+        // 旧日志只有订单累计 pnl
+        let legacy = vec![
+            fill_entry("A", ts(2024, 3, 10, 1), Some(3.0), None),
+            fill_entry("A", ts(2024, 3, 10, 2), Some(5.0), None),
+            fill_entry("B", ts(2024, 3, 10, 3), Some(-1.0), None),
+        ];
+        assert_eq!(
+            summarize(&legacy, StatsPeriod::Today, utc(), now).total,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_format_pnl_precision_by_magnitude() {
+        assert_eq!(format_pnl(123456.789123), "+123456.79");
+        assert_eq!(format_pnl(-1500.5), "-1500.50");
+        assert_eq!(format_pnl(12.3456789), "+12.3457");
+        assert_eq!(format_pnl(-1.0), "-1.0000");
+        assert_eq!(format_pnl(0.000123456), "+0.000123");
+        assert_eq!(format_pnl(-0.5), "-0.500000");
+        assert_eq!(format_pnl(0.0), "0");
+        assert_eq!(format_pnl(-0.0000001), "0");
+    }
+}
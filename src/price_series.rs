@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+
+pub type PriceSeries = VecDeque<(f64, f64)>;
+
+// 按时间顺序追加价格点，并从队首丢弃早于 cutoff 的旧数据
+pub fn push_with_retention(series: &mut PriceSeries, point: (f64, f64), cutoff: f64) {
+    series.push_back(point);
+    while series
+        .front()
+        .is_some_and(|(timestamp, _)| *timestamp < cutoff)
+    {
+        series.pop_front();
+    }
+    // 保留至少一半长度的余量，环形缓冲回绕时 make_contiguous 的搬移成本可均摊到每次追加
+    let len = series.len();
+    if series.capacity() - len < len / 2 {
+        series.reserve(len);
+    }
+    series.make_contiguous();
+}
+
+// push_with_retention 保证序列连续存放，第一段即完整数据
+pub fn as_slice(series: &PriceSeries) -> &[(f64, f64)] {
+    let (head, tail) = series.as_slices();
+    debug_assert!(tail.is_empty());
+    head
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_push_with_retention_trims_front() {
+        let mut series = PriceSeries::new();
+        for ts in 0..10 {
+            push_with_retention(&mut series, (ts as f64, ts as f64 * 2.0), ts as f64 - 3.0);
+        }
+        assert_eq!(
+            as_slice(&series),
+            &[(6.0, 12.0), (7.0, 14.0), (8.0, 16.0), (9.0, 18.0)]
+        );
+    }
+
+    #[test]
+    fn test_push_with_retention_cost_stays_bounded() {
+        const WINDOW: usize = 5_000;
+        const TICKS: usize = 500_000;
+        let mut series = PriceSeries::new();
+        let started = Instant::now();
+        let mut max_capacity = 0;
+        for tick in 0..TICKS {
+            let cutoff = tick as f64 - WINDOW as f64 + 1.0;
+            push_with_retention(&mut series, (tick as f64, 1.0), cutoff);
+            max_capacity = max_capacity.max(series.capacity());
+        }
+        let elapsed = started.elapsed();
+        assert_eq!(series.len(), WINDOW);
+        assert_eq!(
+            as_slice(&series).first(),
+            Some(&((TICKS - WINDOW) as f64, 1.0))
+        );
+        // 容量不随 tick 数增长，逐点 retain 的 O(n) 实现在此规模下会慢上数个数量级
+        assert!(
+            max_capacity <= WINDOW * 4,
+            "capacity grew to {max_capacity}"
+        );
+        assert!(
+            elapsed < Duration::from_secs(5),
+            "trimming took {elapsed:?} for {TICKS} ticks"
+        );
+    }
+}
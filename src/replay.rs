@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tokio::time::{Instant, MissedTickBehavior, interval};
+
+use crate::command::{AccountSnapshot, Command, PublicTrade, TradeEvent};
+use crate::okx::MarketInfo;
+
+const RECORD_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+// 只记录驱动界面的行情与账户数据，其余命令（提醒、错误、用户操作）回放时会由程序自行产生
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+enum RecordedCommand {
+    MarkPrice {
+        inst_id: String,
+        price: f64,
+        ts: i64,
+        precision: usize,
+    },
+    AccountSnapshot(AccountSnapshot),
+    TradeResult(Box<TradeEvent>),
+    PublicTrades {
+        inst_id: String,
+        trades: Vec<PublicTrade>,
+    },
+    MarketsLoaded(HashMap<String, MarketInfo>),
+    // 录制端接收积压时丢弃的消息数，回放时提示此处数据不完整
+    Dropped {
+        count: u64,
+    },
+}
+
+impl RecordedCommand {
+    fn from_command(command: &Command) -> Option<Self> {
+        let recorded = match command {
+            Command::MarkPriceUpdate(inst_id, price, ts, precision) => RecordedCommand::MarkPrice {
+                inst_id: inst_id.clone(),
+                price: *price,
+                ts: *ts,
+                precision: *precision,
+            },
+            Command::AccountSnapshot(snapshot) => {
+                RecordedCommand::AccountSnapshot(snapshot.clone())
+            }
+            Command::TradeResult(event) => RecordedCommand::TradeResult(Box::new(event.clone())),
+            Command::PublicTrades(inst_id, trades) => RecordedCommand::PublicTrades {
+                inst_id: inst_id.clone(),
+                trades: trades.clone(),
+            },
+            Command::MarketsLoaded(markets) => RecordedCommand::MarketsLoaded(markets.clone()),
+            _ => return None,
+        };
+        Some(recorded)
+    }
+
+    fn into_command(self) -> Command {
+        match self {
+            RecordedCommand::MarkPrice {
+                inst_id,
+                price,
+                ts,
+                precision,
+            } => Command::MarkPriceUpdate(inst_id, price, ts, precision),
+            RecordedCommand::AccountSnapshot(snapshot) => Command::AccountSnapshot(snapshot),
+            RecordedCommand::TradeResult(event) => Command::TradeResult(*event),
+            RecordedCommand::PublicTrades { inst_id, trades } => {
+                Command::PublicTrades(inst_id, trades)
+            }
+            RecordedCommand::MarketsLoaded(markets) => Command::MarketsLoaded(markets),
+            RecordedCommand::Dropped { count } => {
+                Command::Error(format!("录制时消息积压，此处丢失 {count} 条消息"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedLine {
+    // 距录制开始的毫秒数，回放时按倍速还原间隔
+    elapsed_ms: u64,
+    command: RecordedCommand,
+}
+
+pub struct SessionRecorder {
+    path: PathBuf,
+    rx: broadcast::Receiver<Command>,
+    exit_rx: broadcast::Receiver<()>,
+}
+
+impl SessionRecorder {
+    pub fn new(
+        path: PathBuf,
+        rx: broadcast::Receiver<Command>,
+        exit_rx: broadcast::Receiver<()>,
+    ) -> Self {
+        SessionRecorder { path, rx, exit_rx }
+    }
+
+    pub async fn run(&mut self) -> Result<()> {
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let file = File::create(&self.path)
+            .with_context(|| format!("creating record file {}", self.path.display()))?;
+        let mut writer = BufWriter::new(file);
+        let started = Instant::now();
+        // 逐行 flush 会在异步任务里频繁阻塞写盘，改为定时落盘，退出时再 flush 一次
+        let mut flush_tick = interval(RECORD_FLUSH_INTERVAL);
+        flush_tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                message = self.rx.recv() => {
+                    let command = match message {
+                        Ok(command) => match RecordedCommand::from_command(&command) {
+                            Some(command) => command,
+                            None => continue,
+                        },
+                        Err(broadcast::error::RecvError::Lagged(count)) => {
+                            RecordedCommand::Dropped { count }
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    let line = RecordedLine {
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                        command,
+                    };
+                    serde_json::to_writer(&mut writer, &line)?;
+                    writer.write_all(b"\n")?;
+                }
+                _ = flush_tick.tick() => writer.flush()?,
+                signal = self.exit_rx.recv() => match signal {
+                    Ok(_) | Err(broadcast::error::RecvError::Closed) => break,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+pub async fn replay_session(
+    path: PathBuf,
+    speed: f64,
+    tx: broadcast::Sender<Command>,
+    mut exit_rx: broadcast::Receiver<()>,
+) -> Result<()> {
+    let file =
+        File::open(&path).with_context(|| format!("opening replay file {}", path.display()))?;
+    let _ = tx.send(Command::ConnState("replay".to_string(), 0, true));
+    let started = Instant::now();
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("reading replay file {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let recorded: RecordedLine = serde_json::from_str(&line)
+            .with_context(|| format!("parsing {} line {}", path.display(), idx + 1))?;
+        let offset = Duration::from_secs_f64(recorded.elapsed_ms as f64 / 1000.0 / speed);
+        tokio::select! {
+            _ = tokio::time::sleep_until(started + offset) => {}
+            _ = exit_rx.recv() => return Ok(()),
+        }
+        let _ = tx.send(recorded.command.into_command());
+    }
+    let _ = tx.send(Command::ConnState("replay".to_string(), 0, false));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::TradeSide;
+
+    #[test]
+    fn test_recorded_line_round_trip() {
+        let trade = PublicTrade {
+            price: 50000.5,
+            size: 0.25,
+            side: TradeSide::Sell,
+            ts: 1700000000000,
+        };
+        let command = Command::PublicTrades("BTC-USDT-SWAP".to_string(), vec![trade]);
+        let line = RecordedLine {
+            elapsed_ms: 1500,
+            command: RecordedCommand::from_command(&command).unwrap(),
+        };
+        let text = serde_json::to_string(&line).unwrap();
+        let parsed: RecordedLine = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed.elapsed_ms, 1500);
+        match parsed.command.into_command() {
+            Command::PublicTrades(inst_id, trades) => {
+                assert_eq!(inst_id, "BTC-USDT-SWAP");
+                assert_eq!(trades, vec![trade]);
+            }
+            other => panic!("unexpected command {other:?}"),
+        }
+        assert!(RecordedCommand::from_command(&Command::ResyncAccount).is_none());
+    }
+
+    #[test]
+    fn test_dropped_marker_survives_replay() {
+        let text = serde_json::to_string(&RecordedLine {
+            elapsed_ms: 20,
+            command: RecordedCommand::Dropped { count: 7 },
+        })
+        .unwrap();
+        assert_eq!(
+            text,
+            r#"{"elapsed_ms":20,"command":{"type":"dropped","data":{"count":7}}}"#
+        );
+        let parsed: RecordedLine = serde_json::from_str(&text).unwrap();
+        match parsed.command.into_command() {
+            Command::Error(message) => assert!(message.contains("7 条")),
+            other => panic!("unexpected command {other:?}"),
+        }
+    }
+}
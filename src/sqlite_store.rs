@@ -0,0 +1,150 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use anyhow::Result;
+use rusqlite::{Connection, TransactionBehavior, params};
+
+const SCHEMA_VERSION: i64 = 1;
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 每条记录以 JSON 文本保存，格式与 jsonl 文件的单行一致
+// 连接在首次使用时打开并完成建表与迁移，克隆后共用同一连接
+#[derive(Clone, Debug)]
+pub struct SqliteLogTable {
+    db_path: PathBuf,
+    legacy_path: PathBuf,
+    conn: Arc<Mutex<Option<Connection>>>,
+}
+
+impl SqliteLogTable {
+    pub fn new(legacy_path: &Path) -> Self {
+        SqliteLogTable {
+            db_path: legacy_path.with_extension("sqlite3"),
+            legacy_path: legacy_path.to_path_buf(),
+            conn: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn append(&self, timestamp_ms: i64, payload: &str) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO entries (timestamp_ms, payload) VALUES (?1, ?2)",
+                params![timestamp_ms, payload],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn load_recent(&self, limit: usize) -> Result<Vec<String>> {
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT payload FROM (
+                    SELECT id, payload FROM entries ORDER BY id DESC LIMIT ?1
+                ) ORDER BY id ASC",
+            )?;
+            let rows = stmt.query_map(params![limit as i64], |row| row.get::<_, String>(0))?;
+            let mut payloads = Vec::new();
+            for row in rows {
+                payloads.push(row?);
+            }
+            Ok(payloads)
+        })
+    }
+
+    // 删除早于 cutoff 的记录，并按时间从新到旧累计 payload 长度，超出预算的旧记录一并删除
+    pub fn prune(&self, cutoff_ms: Option<i64>, max_payload_bytes: Option<u64>) -> Result<usize> {
+        self.with_conn(|conn| {
+            let mut removed = 0;
+            if let Some(cutoff_ms) = cutoff_ms {
+                removed += conn.execute(
+                    "DELETE FROM entries WHERE timestamp_ms < ?1",
+                    params![cutoff_ms],
+                )?;
+            }
+            if let Some(max_bytes) = max_payload_bytes {
+                removed += conn.execute(
+                    "DELETE FROM entries WHERE id IN (
+                        SELECT id FROM (
+                            SELECT id, SUM(length(payload)) OVER (ORDER BY id DESC) AS total
+                            FROM entries
+                        ) WHERE total > ?1
+                    )",
+                    params![max_bytes.min(i64::MAX as u64) as i64],
+                )?;
+            }
+            Ok(removed)
+        })
+    }
+
+    // 打开失败不缓存，下次调用时重试
+    fn with_conn<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let mut guard = self.conn.lock().unwrap_or_else(PoisonError::into_inner);
+        let conn = match guard.take() {
+            Some(conn) => conn,
+            None => self.connect()?,
+        };
+        f(guard.insert(conn))
+    }
+
+    fn connect(&self) -> Result<Connection> {
+        if let Some(parent) = self
+            .db_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let mut conn = Connection::open(&self.db_path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp_ms INTEGER NOT NULL,
+                payload TEXT NOT NULL
+            );",
+        )?;
+        self.migrate(&mut conn)?;
+        Ok(conn)
+    }
+
+    // 首次使用时导入旧的 jsonl 记录，保留原文件不动
+    fn migrate(&self, conn: &mut Connection) -> Result<()> {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let version: i64 = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version >= SCHEMA_VERSION {
+            return Ok(());
+        }
+        match File::open(&self.legacy_path) {
+            Ok(file) => {
+                let mut stmt =
+                    tx.prepare("INSERT INTO entries (timestamp_ms, payload) VALUES (?1, ?2)")?;
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    let line = line.trim();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+                        continue;
+                    };
+                    let timestamp_ms = value
+                        .get("timestamp_ms")
+                        .and_then(|v| v.as_i64())
+                        .unwrap_or_default();
+                    stmt.execute(params![timestamp_ms, line])?;
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        tx.execute_batch(&format!("PRAGMA user_version = {SCHEMA_VERSION}"))?;
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 // 技术指标计算验证程序
 // 用于验证 OKX 市场指标计算的正确性
 
+use crate::indicators::{atr, bollinger, macd, rsi, sma};
 use crate::okx_analytics::{compute_atr, compute_ema, compute_macd, compute_rsi};
 
 #[cfg(test)]
@@ -246,6 +247,122 @@ mod tests {
         }
     }
 
+    // 验证价格序列 RSI 的预热期与取值范围
+    #[test]
+    fn test_series_rsi_warmup() {
+        let short: Vec<(f64, f64)> = (0..14).map(|i| (i as f64, 100.0 + i as f64)).collect();
+        assert!(rsi(&short, 14).is_empty());
+
+        let series: Vec<(f64, f64)> = (0..40)
+            .map(|i| (i as f64 * 1000.0, 100.0 + (i % 5) as f64))
+            .collect();
+        let values = rsi(&series, 14);
+        assert_eq!(values.len(), series.len() - 15);
+        assert_eq!(values.first().map(|(ts, _)| *ts), Some(15_000.0));
+        for (_, value) in &values {
+            assert!((0.0..=100.0).contains(value));
+        }
+    }
+
+    // 验证序列 MACD 的预热期与三条线的对应关系
+    #[test]
+    fn test_series_macd_warmup() {
+        let short: Vec<(f64, f64)> = (0..33).map(|i| (i as f64, 100.0 + i as f64)).collect();
+        assert_eq!(macd(&short, 12, 26, 9).histogram.len(), 0);
+
+        let series: Vec<(f64, f64)> = (0..60)
+            .map(|i| (i as f64 * 1000.0, 100.0 + (i % 7) as f64))
+            .collect();
+        let result = macd(&series, 12, 26, 9);
+        assert_eq!(result.macd.len(), series.len() - 33);
+        assert_eq!(result.signal.len(), result.macd.len());
+        assert_eq!(result.histogram.first().map(|(ts, _)| *ts), Some(33_000.0));
+        for ((macd, signal), hist) in result
+            .macd
+            .iter()
+            .zip(&result.signal)
+            .zip(&result.histogram)
+        {
+            assert!((macd.1 - signal.1 - hist.1).abs() < 1e-9);
+        }
+
+        // 平稳价格不应产生柱体
+        let flat: Vec<(f64, f64)> = (0..60).map(|i| (i as f64, 50.0)).collect();
+        assert!(
+            macd(&flat, 12, 26, 9)
+                .histogram
+                .iter()
+                .all(|(_, value)| value.abs() < 1e-9)
+        );
+    }
+
+    // 验证 SMA 的预热期与已知序列上的取值
+    #[test]
+    fn test_series_sma() {
+        let series: Vec<(f64, f64)> = [1.0, 2.0, 3.0, 4.0, 5.0, 9.0]
+            .iter()
+            .enumerate()
+            .map(|(i, price)| (i as f64 * 1000.0, *price))
+            .collect();
+        assert!(sma(&series[..2], 3).is_empty());
+        assert!(sma(&series, 0).is_empty());
+        let values = sma(&series, 3);
+        assert_eq!(values.len(), series.len() - 2);
+        assert_eq!(
+            values,
+            vec![(2000.0, 2.0), (3000.0, 3.0), (4000.0, 4.0), (5000.0, 6.0)]
+        );
+        assert_eq!(sma(&series, 1), series);
+    }
+
+    // 验证布林带的预热期、对称性与零波动时的退化
+    #[test]
+    fn test_series_bollinger_bands() {
+        let short: Vec<(f64, f64)> = (0..19).map(|i| (i as f64, 100.0 + i as f64)).collect();
+        assert!(bollinger(&short, 20, 2.0).middle.is_empty());
+
+        let series: Vec<(f64, f64)> = (0..30)
+            .map(|i| (i as f64 * 1000.0, 100.0 + (i % 4) as f64))
+            .collect();
+        let bands = bollinger(&series, 20, 2.0);
+        assert_eq!(bands.middle.len(), series.len() - 19);
+        assert_eq!(bands.middle.first().map(|(ts, _)| *ts), Some(19_000.0));
+        for ((upper, middle), lower) in bands.upper.iter().zip(&bands.middle).zip(&bands.lower) {
+            assert!(upper.1 > middle.1 && middle.1 > lower.1);
+            assert!(((upper.1 - middle.1) - (middle.1 - lower.1)).abs() < 1e-9);
+        }
+
+        let flat: Vec<(f64, f64)> = (0..25).map(|i| (i as f64, 50.0)).collect();
+        let flat_bands = bollinger(&flat, 20, 2.0);
+        assert_eq!(flat_bands.upper.len(), 6);
+        assert!(
+            flat_bands
+                .upper
+                .iter()
+                .chain(&flat_bands.lower)
+                .all(|(_, value)| (value - 50.0).abs() < 1e-9)
+        );
+    }
+
+    // 验证逐笔 ATR 的预热期与取值
+    #[test]
+    fn test_series_atr_ticks() {
+        let short: Vec<(f64, f64)> = (0..14).map(|i| (i as f64, 100.0 + i as f64)).collect();
+        assert!(atr(&short, 14).is_empty());
+
+        // 价格在 100 与 102 之间来回跳动，每笔波幅恒为 2
+        let series: Vec<(f64, f64)> = (0..30)
+            .map(|i| (i as f64 * 1000.0, if i % 2 == 0 { 100.0 } else { 102.0 }))
+            .collect();
+        let values = atr(&series, 14);
+        assert_eq!(values.len(), series.len() - 14);
+        assert_eq!(values.first().map(|(ts, _)| *ts), Some(14_000.0));
+        assert!(values.iter().all(|(_, value)| (value - 2.0).abs() < 1e-9));
+
+        let flat: Vec<(f64, f64)> = (0..20).map(|i| (i as f64, 50.0)).collect();
+        assert!(atr(&flat, 14).iter().all(|(_, value)| value.abs() < 1e-12));
+    }
+
     // 验证 MACD 零轴穿越
     #[test]
     fn test_macd_crossover() {
@@ -0,0 +1,125 @@
+use ratatui::style::{Color, Modifier, Style};
+
+pub const THEME_NAMES: [&str; 2] = ["dark", "light"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub palette: Vec<Color>,
+    pub text: Color,
+    pub muted: Color,
+    pub subtle: Color,
+    pub accent: Color,
+    pub secondary: Color,
+    pub emphasis: Color,
+    pub info: Color,
+    pub success: Color,
+    pub error: Color,
+    pub status_info: Color,
+    pub status_error: Color,
+    pub highlight_fg: Color,
+    pub highlight_bg: Color,
+    pub border: Color,
+    pub focus_border: Color,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Theme {
+            palette: vec![
+                Color::Cyan,
+                Color::Yellow,
+                Color::Magenta,
+                Color::Green,
+                Color::LightBlue,
+                Color::Red,
+                Color::LightMagenta,
+                Color::LightCyan,
+            ],
+            text: Color::White,
+            muted: Color::DarkGray,
+            subtle: Color::Gray,
+            accent: Color::LightCyan,
+            secondary: Color::LightMagenta,
+            emphasis: Color::Yellow,
+            info: Color::LightBlue,
+            success: Color::LightGreen,
+            error: Color::LightRed,
+            status_info: Color::Yellow,
+            status_error: Color::Red,
+            highlight_fg: Color::Black,
+            highlight_bg: Color::LightCyan,
+            border: Color::Reset,
+            focus_border: Color::LightCyan,
+        }
+    }
+
+    // 浅色终端：避免白色/亮黄文字，统一换成深色系
+    pub fn light() -> Self {
+        Theme {
+            palette: vec![
+                Color::Blue,
+                Color::Magenta,
+                Color::Red,
+                Color::Green,
+                Color::Indexed(130),
+                Color::Cyan,
+                Color::Indexed(90),
+                Color::Black,
+            ],
+            text: Color::Black,
+            muted: Color::DarkGray,
+            subtle: Color::DarkGray,
+            accent: Color::Blue,
+            secondary: Color::Magenta,
+            emphasis: Color::Indexed(130),
+            info: Color::Blue,
+            success: Color::Green,
+            error: Color::Red,
+            status_info: Color::Blue,
+            status_error: Color::Red,
+            highlight_fg: Color::White,
+            highlight_bg: Color::Blue,
+            border: Color::Reset,
+            focus_border: Color::Blue,
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            _ => None,
+        }
+    }
+
+    pub fn palette_color(&self, idx: usize) -> Color {
+        self.palette[idx % self.palette.len()]
+    }
+
+    pub fn row_style(&self, selected: bool) -> Style {
+        if selected {
+            Style::default()
+                .bg(self.highlight_bg)
+                .fg(self.highlight_fg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        }
+    }
+
+    pub fn border_style(&self, focused: bool) -> Style {
+        if focused {
+            Style::default()
+                .fg(self.focus_border)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(self.border)
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
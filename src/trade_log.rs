@@ -1,12 +1,16 @@
+#[cfg(not(feature = "sqlite"))]
 use std::fs::{self, File, OpenOptions};
+#[cfg(not(feature = "sqlite"))]
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 
 use anyhow::Result;
-use chrono::{DateTime, Local, LocalResult, TimeZone};
+use chrono::{DateTime, Duration, Local, LocalResult, TimeZone};
 use serde::{Deserialize, Serialize};
 
 use crate::command::TradeEvent;
+#[cfg(feature = "sqlite")]
+use crate::sqlite_store::SqliteLogTable;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct TradeLogEntry {
@@ -51,39 +55,90 @@ impl TradeLogEntry {
     }
 }
 
+// 委托日志保留策略：界面保留条数、日志文件大小上限（MB）与保留天数，后两者为 0 表示不限制
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TradeLogRetention {
+    pub memory_entries: usize,
+    pub max_file_mb: u64,
+    pub max_age_days: u32,
+}
+
+impl Default for TradeLogRetention {
+    fn default() -> Self {
+        TradeLogRetention {
+            memory_entries: 1000,
+            max_file_mb: 10,
+            max_age_days: 0,
+        }
+    }
+}
+
+impl TradeLogRetention {
+    pub fn is_default(&self) -> bool {
+        *self == TradeLogRetention::default()
+    }
+
+    fn max_file_bytes(&self) -> Option<u64> {
+        (self.max_file_mb > 0).then(|| self.max_file_mb.saturating_mul(1024 * 1024))
+    }
+
+    fn cutoff_ms(&self) -> Option<i64> {
+        (self.max_age_days > 0).then(|| {
+            (Local::now() - Duration::days(i64::from(self.max_age_days))).timestamp_millis()
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TradeLogStore {
+    #[cfg(not(feature = "sqlite"))]
     path: PathBuf,
     max_entries: usize,
+    retention: TradeLogRetention,
+    #[cfg(feature = "sqlite")]
+    table: SqliteLogTable,
 }
 
 impl TradeLogStore {
+    #[cfg(not(feature = "sqlite"))]
     const TAIL_CHUNK_SIZE: usize = 8 * 1024;
 
     pub fn new(path: PathBuf) -> Self {
         TradeLogStore {
+            #[cfg(feature = "sqlite")]
+            table: SqliteLogTable::new(&path),
+            #[cfg(not(feature = "sqlite"))]
             path,
             max_entries: 512,
+            retention: TradeLogRetention::default(),
         }
     }
 
+    pub fn set_retention(&mut self, retention: TradeLogRetention) {
+        self.retention = retention;
+    }
+
     pub fn default_path() -> PathBuf {
         PathBuf::from("trade_logs.jsonl")
     }
 
     pub fn load(&self) -> Result<Vec<TradeLogEntry>> {
-        let mut file = match File::open(&self.path) {
-            Ok(file) => file,
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
-            Err(err) => return Err(err.into()),
-        };
-        let lines = Self::read_tail_lines(&mut file, self.max_entries)?;
+        self.load_recent(self.max_entries)
+    }
+
+    pub fn load_recent(&self, limit: usize) -> Result<Vec<TradeLogEntry>> {
+        let lines = self.read_recent_lines(limit)?;
+        let cutoff = self.retention.cutoff_ms();
         let mut entries = Vec::with_capacity(lines.len());
         for line in lines {
             if line.trim().is_empty() {
                 continue;
             }
             if let Ok(stored) = serde_json::from_str::<StoredTradeLogEntry>(&line) {
+                if cutoff.is_some_and(|cutoff| stored.timestamp_ms < cutoff) {
+                    continue;
+                }
                 entries.push(stored.into_entry());
             }
         }
@@ -91,6 +146,120 @@ impl TradeLogStore {
     }
 
     pub fn append(&self, entry: &TradeLogEntry) -> Result<()> {
+        let line = serde_json::to_string(&StoredTradeLogEntry::from(entry))?;
+        self.append_line(entry.timestamp.timestamp_millis(), &line)?;
+        if self.exceeds_size_limit()? {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "sqlite")]
+    pub fn rotate(&self) -> Result<usize> {
+        self.table
+            .prune(self.retention.cutoff_ms(), self.retention.max_file_bytes())
+    }
+
+    // 数据库删除记录后文件不会缩小，只在启动时按保留策略清理
+    #[cfg(feature = "sqlite")]
+    fn exceeds_size_limit(&self) -> Result<bool> {
+        Ok(false)
+    }
+
+    // 过期记录与超出大小上限的旧记录移入 `<文件名>.1`，文件只保留最近的窗口
+    #[cfg(not(feature = "sqlite"))]
+    pub fn rotate(&self) -> Result<usize> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err.into()),
+        };
+        let lines: Vec<&str> = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let cutoff = self.retention.cutoff_ms();
+        // 超出上限时只保留一半容量，避免之后每次追加都触发轮转
+        let budget = self.retention.max_file_bytes().map(|bytes| bytes / 2);
+        let mut keep_from = lines.len();
+        let mut kept_bytes = 0_u64;
+        for (idx, line) in lines.iter().enumerate().rev() {
+            if cutoff.is_some_and(|cutoff| Self::line_timestamp(line).is_some_and(|ts| ts < cutoff))
+            {
+                break;
+            }
+            kept_bytes += line.len() as u64 + 1;
+            if budget.is_some_and(|budget| kept_bytes > budget) {
+                break;
+            }
+            keep_from = idx;
+        }
+        if keep_from == 0 {
+            return Ok(0);
+        }
+        let join = |lines: &[&str]| {
+            let mut text = lines.join("\n");
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text
+        };
+        fs::write(self.rotated_path(), join(&lines[..keep_from]))?;
+        let tmp_path = Self::with_suffix(&self.path, ".tmp");
+        fs::write(&tmp_path, join(&lines[keep_from..]))?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(keep_from)
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn exceeds_size_limit(&self) -> Result<bool> {
+        let Some(max_bytes) = self.retention.max_file_bytes() else {
+            return Ok(false);
+        };
+        Ok(fs::metadata(&self.path)?.len() > max_bytes)
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    pub fn rotated_path(&self) -> PathBuf {
+        Self::with_suffix(&self.path, ".1")
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn with_suffix(path: &std::path::Path, suffix: &str) -> PathBuf {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(suffix);
+        PathBuf::from(name)
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn line_timestamp(line: &str) -> Option<i64> {
+        serde_json::from_str::<StoredTimestamp>(line)
+            .ok()
+            .map(|stored| stored.timestamp_ms)
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn read_recent_lines(&self, limit: usize) -> Result<Vec<String>> {
+        self.table.load_recent(limit)
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn append_line(&self, timestamp_ms: i64, line: &str) -> Result<()> {
+        self.table.append(timestamp_ms, line)
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn read_recent_lines(&self, limit: usize) -> Result<Vec<String>> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        Self::read_tail_lines(&mut file, limit)
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn append_line(&self, _timestamp_ms: i64, line: &str) -> Result<()> {
         if let Some(parent) = self.path.parent() {
             if !parent.as_os_str().is_empty() {
                 fs::create_dir_all(parent)?;
@@ -100,11 +269,12 @@ impl TradeLogStore {
             .create(true)
             .append(true)
             .open(&self.path)?;
-        serde_json::to_writer(&mut file, &StoredTradeLogEntry::from(entry))?;
+        file.write_all(line.as_bytes())?;
         file.write_all(b"\n")?;
         Ok(())
     }
 
+    #[cfg(not(feature = "sqlite"))]
     fn read_tail_lines(file: &mut File, max_lines: usize) -> Result<Vec<String>> {
         if max_lines == 0 {
             return Ok(Vec::new());
@@ -141,6 +311,7 @@ impl TradeLogStore {
         Ok(lines)
     }
 
+    #[cfg(not(feature = "sqlite"))]
     fn push_pending_line(pending: &mut Vec<u8>, lines: &mut Vec<String>) -> bool {
         if pending.is_empty() {
             return false;
@@ -153,6 +324,12 @@ impl TradeLogStore {
     }
 }
 
+#[cfg(not(feature = "sqlite"))]
+#[derive(Deserialize)]
+struct StoredTimestamp {
+    timestamp_ms: i64,
+}
+
 #[derive(Serialize, Deserialize)]
 struct StoredTradeLogEntry {
     timestamp_ms: i64,
@@ -176,3 +353,33 @@ impl From<&TradeLogEntry> for StoredTradeLogEntry {
         }
     }
 }
+
+#[cfg(all(test, not(feature = "sqlite")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_moves_expired_entries() {
+        let path =
+            std::env::temp_dir().join(format!("trade_logs_rotate_{}.jsonl", std::process::id()));
+        let now_ms = Local::now().timestamp_millis();
+        let old_ms = now_ms - 3 * 24 * 60 * 60 * 1000;
+        let contents = format!(
+            "{{\"timestamp_ms\":{old_ms}}}\n{{\"timestamp_ms\":{now_ms}}}\n{{\"timestamp_ms\":{now_ms}}}\n"
+        );
+        fs::write(&path, contents).unwrap();
+        let mut store = TradeLogStore::new(path.clone());
+        store.set_retention(TradeLogRetention {
+            max_file_mb: 0,
+            max_age_days: 1,
+            ..TradeLogRetention::default()
+        });
+        assert_eq!(store.rotate().unwrap(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        let rotated = fs::read_to_string(store.rotated_path()).unwrap();
+        assert!(rotated.contains(&old_ms.to_string()));
+        assert_eq!(store.rotate().unwrap(), 0);
+        let _ = fs::remove_file(store.rotated_path());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,12 +1,19 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::time::{Duration, Instant};
 
 use anyhow::Result as AnyResult;
+use chrono::{Local, NaiveDate, TimeZone, Utc};
 use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::Frame;
-use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Position, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::symbols;
 use ratatui::text::{Line, Span};
@@ -19,26 +26,28 @@ use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::ai_log::{AiDecisionRecord, AiDecisionStore};
 use crate::command::{
-    AccountBalance, AccountSnapshot, AiInsightRecord, CancelOrderRequest, Command,
-    PendingOrderInfo, PositionInfo, PricePoint, SetLeverageRequest, TradeEvent, TradeOperator,
-    TradeOrderKind, TradeOrderType, TradeRequest, TradeSide, TradingCommand,
+    AccountBalance, AccountSnapshot, AiDecisionProposal, AiInsightRecord, AiTokenUsage,
+    AmendOrderRequest, BookLevel, BracketOrder, CancelAllRequest, CancelOrderRequest, Command,
+    FundingRateInfo, HistoryRequest, MarginMode, OrderBookLevels, OrderHistory, PendingOrderInfo,
+    PositionInfo, PositionMode, PricePoint, PriceThreshold, PublicTrade, SetLeverageRequest,
+    TickerInfo, TradeEvent, TradeOperator, TradeOrderKind, TradeOrderType, TradeRequest,
+    TradeResponse, TradeSide, TradingCommand, TrailingCallback,
+};
+use crate::config::{AppRunConfig, ChartPreferences, ConfiguredTimeZone, FeeRate, OrderLimits};
+use crate::deepseek::format_duration_brief;
+use crate::export::{self, AiExportFormat};
+use crate::indicators::{
+    self, DEFAULT_ATR_PERIOD, DEFAULT_BOLLINGER_K, DEFAULT_BOLLINGER_PERIOD, DEFAULT_MACD_FAST,
+    DEFAULT_MACD_SIGNAL, DEFAULT_MACD_SLOW, DEFAULT_RSI_PERIOD,
 };
-use crate::config::ConfiguredTimeZone;
-use crate::okx::MarketInfo;
-use crate::trade_log::{TradeLogEntry, TradeLogStore};
-
-const COLOR_PALETTE: [Color; 8] = [
-    Color::Cyan,
-    Color::Yellow,
-    Color::Magenta,
-    Color::Green,
-    Color::LightBlue,
-    Color::Red,
-    Color::LightMagenta,
-    Color::LightCyan,
-];
+use crate::keymap;
+use crate::okx::{self, LongShortRatio, MarketInfo};
+use crate::pnl_stats::{self, PnlSummary, StatsPeriod};
+use crate::price_series::{self, PriceSeries};
+use crate::theme::Theme;
+use crate::trade_log::{TradeLogEntry, TradeLogRetention, TradeLogStore};
+
 const EMPTY_SERIES: &[(f64, f64)] = &[];
-const MAX_TRADE_LOGS: usize = 1000;
 const MAX_POSITION_RECORDS: usize = 100;
 const MAX_ORDER_RECORDS: usize = 100;
 const MAX_AI_INSIGHTS: usize = 64;
@@ -46,7 +55,29 @@ const LEVERAGE_EPSILON: f64 = 1e-6;
 const AI_INDEX_COLUMN_WIDTH: usize = 5;
 const AI_TIME_COLUMN_WIDTH: usize = 8;
 const LOADING_SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
-
+const CANDLE_INTERVALS_SECS: [u64; 5] = [5, 15, 60, 300, 900];
+const DEFAULT_SMA_PERIOD: usize = 20;
+const SMA_PERIOD_STEP: usize = 5;
+const PAN_FRACTION: f64 = 0.1;
+const MACD_PANEL_HEIGHT: u16 = 8;
+const LABEL_CACHE_LIMIT: usize = 2048;
+const DEPTH_LADDER_LEVELS: usize = 5;
+const DEPTH_LADDER_WIDTH: u16 = 28;
+const MAX_PUBLIC_TRADES: usize = 200;
+const PRICE_NUDGE_PAGE_TICKS: i32 = 10;
+const TAPE_PANEL_WIDTH: u16 = 34;
+const TAPE_MIN_CHART_WIDTH: u16 = 60;
+// 超过该时长未收到推送视为过期，标题栏显示 --
+const TICKER_STALE_MS: i64 = 5 * 60 * 1000;
+const MAX_LONG_SHORT_SAMPLES: usize = 288;
+const EXIT_CANCEL_OPERATOR: &str = "退出撤单";
+// 超过该时长没有行情与按键即进入空闲模式，放慢输入轮询以节省 CPU
+const IDLE_AFTER: Duration = Duration::from_secs(3);
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const ORDER_UNDO_WINDOW: Duration = Duration::from_secs(3);
+const LAG_RESYNC_MIN_GAP: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
 struct LoadingOverlay {
     message: String,
     spinner_index: usize,
@@ -108,6 +139,29 @@ struct PricePanelEntry {
 enum ViewMode {
     Chart,
     Trade,
+    Stats,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ChartStyle {
+    Line,
+    Candles,
+}
+
+// 同色的影线与实体各合成一个散点数据集，避免每根 K 线单独建数据集
+#[derive(Default)]
+struct CandlePoints {
+    wicks: Vec<(f64, f64)>,
+    bodies: Vec<(f64, f64)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct OhlcBucket {
+    start: f64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -115,6 +169,10 @@ enum OrderInputField {
     Price,
     Size,
     Leverage,
+    Callback,
+    TakeProfit,
+    StopLoss,
+    Risk,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -124,6 +182,124 @@ enum TradeFocus {
     Orders,
     Logs,
     AiInsights,
+    History,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+enum HistoryRange {
+    Day,
+    #[default]
+    Week,
+    Month,
+    Quarter,
+}
+
+impl HistoryRange {
+    fn next(&self) -> Self {
+        match self {
+            HistoryRange::Day => HistoryRange::Week,
+            HistoryRange::Week => HistoryRange::Month,
+            HistoryRange::Month => HistoryRange::Quarter,
+            HistoryRange::Quarter => HistoryRange::Day,
+        }
+    }
+
+    fn days(&self) -> i64 {
+        match self {
+            HistoryRange::Day => 1,
+            HistoryRange::Week => 7,
+            HistoryRange::Month => 30,
+            HistoryRange::Quarter => 90,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            HistoryRange::Day => "近 1 日",
+            HistoryRange::Week => "近 7 日",
+            HistoryRange::Month => "近 30 日",
+            HistoryRange::Quarter => "近 90 日",
+        }
+    }
+}
+
+// 数量显示单位：币本位数量 = 张数 × 合约面值
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+enum SizeUnit {
+    #[default]
+    Coin,
+    Contracts,
+}
+
+impl SizeUnit {
+    fn toggle(&self) -> Self {
+        match self {
+            SizeUnit::Coin => SizeUnit::Contracts,
+            SizeUnit::Contracts => SizeUnit::Coin,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            SizeUnit::Coin => "币",
+            SizeUnit::Contracts => "张",
+        }
+    }
+
+    // 对话框中的数量换算成币数，按张输入时需要合约面值
+    fn to_coins(self, size: f64, market: Option<&MarketInfo>) -> Option<f64> {
+        match self {
+            SizeUnit::Coin => Some(size),
+            SizeUnit::Contracts => market
+                .map(|market| market.ct_val)
+                .filter(|ct_val| *ct_val > 0.0)
+                .map(|ct_val| size * ct_val),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+enum LogKindFilter {
+    #[default]
+    All,
+    Order,
+    Cancel,
+    Fill,
+}
+
+impl LogKindFilter {
+    fn next(&self) -> Self {
+        match self {
+            LogKindFilter::All => LogKindFilter::Order,
+            LogKindFilter::Order => LogKindFilter::Cancel,
+            LogKindFilter::Cancel => LogKindFilter::Fill,
+            LogKindFilter::Fill => LogKindFilter::All,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            LogKindFilter::All => "全部",
+            LogKindFilter::Order => "委托/改单",
+            LogKindFilter::Cancel => "撤单",
+            LogKindFilter::Fill => "成交",
+        }
+    }
+
+    fn matches(&self, event: &TradeEvent) -> bool {
+        match self {
+            LogKindFilter::All => true,
+            LogKindFilter::Order => matches!(event, TradeEvent::Order(_) | TradeEvent::Amend(_)),
+            LogKindFilter::Cancel => matches!(event, TradeEvent::Cancel(_)),
+            LogKindFilter::Fill => matches!(event, TradeEvent::Fill(_)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HistoryView {
+    Orders,
+    Positions,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -131,6 +307,7 @@ enum OrderIntent {
     Manual,
     TakeProfit,
     StopLoss,
+    TrailingStop,
     Modify,
 }
 
@@ -150,12 +327,69 @@ struct OrderInputState {
     reduce_only: bool,
     tag: Option<String>,
     replace_order_id: Option<String>,
+    ord_type: TradeOrderType,
+    callback: String,
+    margin_mode: Option<MarginMode>,
+    initial_margin_mode: Option<MarginMode>,
+    default_margin_mode: Option<MarginMode>,
+    spot: bool,
+    take_profit: String,
+    stop_loss: String,
+    post_only_confirmed: bool,
+    calculator: bool,
+    risk: String,
+}
+
+#[derive(Clone, Debug)]
+struct StagedOrder {
+    request: TradeRequest,
+    intent: OrderIntent,
+    replace_ord_id: Option<String>,
+    leverage_request: Option<SetLeverageRequest>,
+}
+
+// 手动下单后短时间内可按 u 撤销，订单号在 clOrdId 相同的 TradeResult 到达时回填
+#[derive(Clone, Debug)]
+struct UndoableOrder {
+    inst_id: String,
+    cl_ord_id: Option<String>,
+    kind: TradeOrderKind,
+    pos_side: Option<String>,
+    submitted_at: Instant,
+    ord_id: Option<String>,
+    cancel_requested: bool,
+}
+
+// 最近一次渲染的列表面板区域，rows 为表头下各可见行对应的选中下标
+#[derive(Clone, Debug)]
+struct PanelHitArea {
+    focus: TradeFocus,
+    area: Rect,
+    rows: Vec<Option<usize>>,
+}
+
+impl PanelHitArea {
+    fn row_at(&self, row: u16) -> Option<usize> {
+        // 跳过上边框与表头
+        let offset = row.checked_sub(self.area.y + 2)?;
+        self.rows.get(offset as usize).copied().flatten()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct RiskSizing {
+    size: f64,
+    contracts: f64,
+    notional: f64,
+    margin: Option<f64>,
+    loss: f64,
 }
 
 #[derive(Clone, Debug)]
 struct TradeState {
     selected_inst_idx: usize,
     selected_position_idx: usize,
+    net_positions: bool,
     selected_order_idx: usize,
     selected_log_idx: usize,
     selected_ai_idx: usize,
@@ -171,8 +405,11 @@ struct TradeState {
     open_orders: Vec<PendingOrderInfo>,
     focus: TradeFocus,
     log_store: Option<TradeLogStore>,
+    max_logs: usize,
     ai_store: Option<AiDecisionStore>,
     log_detail: Option<TradeLogEntry>,
+    log_inst_filter: Option<String>,
+    log_kind_filter: LogKindFilter,
     ai_detail: Option<AiDecisionRecord>,
     ai_detail_scroll: u16,
     ai_detail_view_height: u16,
@@ -181,6 +418,24 @@ struct TradeState {
     balance: AccountBalance,
     ai_enabled: bool,
     ai_label: Option<String>,
+    ai_interval: Option<Duration>,
+    ai_usage_today: AiTokenUsage,
+    ai_usage_day: Option<NaiveDate>,
+    ai_stream: Option<String>,
+    // 手动触发的 AI 分析进行中时在面板标题显示加载动画
+    ai_pending: Option<LoadingOverlay>,
+    margin_modes: HashMap<String, MarginMode>,
+    default_margin_mode: Option<MarginMode>,
+    position_mode: PositionMode,
+    order_limits: OrderLimits,
+    history: Option<OrderHistory>,
+    history_loading: bool,
+    history_range: HistoryRange,
+    history_view: HistoryView,
+    selected_history_idx: usize,
+    history_view_height: u16,
+    hit_areas: Vec<PanelHitArea>,
+    all_positions: bool,
 }
 
 impl TradeState {
@@ -191,10 +446,12 @@ impl TradeState {
         markets: HashMap<String, MarketInfo>,
         ai_enabled: bool,
         ai_label: Option<String>,
+        ai_interval: Option<Duration>,
     ) -> Self {
         TradeState {
             selected_inst_idx: 0,
             selected_position_idx: 0,
+            net_positions: false,
             selected_order_idx: 0,
             selected_log_idx: 0,
             selected_ai_idx: 0,
@@ -210,8 +467,11 @@ impl TradeState {
             open_orders: Vec::new(),
             focus: TradeFocus::Instruments,
             log_store,
+            max_logs: TradeLogRetention::default().memory_entries,
             ai_store,
             log_detail: None,
+            log_inst_filter: None,
+            log_kind_filter: LogKindFilter::All,
             ai_detail: None,
             ai_detail_scroll: 0,
             ai_detail_view_height: 0,
@@ -220,6 +480,23 @@ impl TradeState {
             balance: AccountBalance::default(),
             ai_enabled,
             ai_label,
+            ai_interval,
+            ai_usage_today: AiTokenUsage::default(),
+            ai_usage_day: None,
+            ai_stream: None,
+            ai_pending: None,
+            margin_modes: HashMap::new(),
+            default_margin_mode: None,
+            position_mode: PositionMode::default(),
+            order_limits: OrderLimits::default(),
+            history: None,
+            history_loading: false,
+            history_range: HistoryRange::default(),
+            history_view: HistoryView::Orders,
+            selected_history_idx: 0,
+            history_view_height: 0,
+            hit_areas: Vec::new(),
+            all_positions: false,
         }
     }
 
@@ -275,7 +552,56 @@ impl TradeState {
             TradeFocus::Orders => self.move_orders(delta),
             TradeFocus::Logs => self.move_logs(delta),
             TradeFocus::AiInsights => self.move_ai(delta),
+            TradeFocus::History => self.move_history(delta),
+        }
+    }
+
+    fn history_len(&self) -> usize {
+        match (&self.history, self.history_view) {
+            (Some(history), HistoryView::Orders) => history.orders.len(),
+            (Some(history), HistoryView::Positions) => history.closed_positions.len(),
+            (None, _) => 0,
+        }
+    }
+
+    fn move_history(&mut self, delta: isize) {
+        let len = self.history_len();
+        if len == 0 {
+            self.selected_history_idx = 0;
+            return;
         }
+        let len = len as isize;
+        let current = self.selected_history_idx.min((len - 1) as usize) as isize;
+        self.selected_history_idx = (current + delta).clamp(0, len - 1) as usize;
+    }
+
+    fn page_scroll_history(&mut self, pages: isize) {
+        let page = self.history_view_height.max(1) as isize;
+        self.move_history(page * pages);
+    }
+
+    fn scroll_history_to_start(&mut self) {
+        self.selected_history_idx = 0;
+    }
+
+    fn scroll_history_to_end(&mut self) {
+        self.selected_history_idx = self.history_len().saturating_sub(1);
+    }
+
+    fn toggle_history_view(&mut self) {
+        self.history_view = match self.history_view {
+            HistoryView::Orders => HistoryView::Positions,
+            HistoryView::Positions => HistoryView::Orders,
+        };
+        self.selected_history_idx = 0;
+    }
+
+    fn set_history(&mut self, history: OrderHistory) {
+        self.history = Some(history);
+        self.history_loading = false;
+        self.selected_history_idx = self
+            .selected_history_idx
+            .min(self.history_len().saturating_sub(1));
     }
 
     fn move_instruments(&mut self, inst_ids: &[String], delta: isize) {
@@ -295,20 +621,38 @@ impl TradeState {
     }
 
     fn move_positions(&mut self, delta: isize) {
-        let len = self.positions.len().min(MAX_POSITION_RECORDS);
-        if len == 0 {
+        let order = self.position_display_order();
+        if order.is_empty() {
             self.selected_position_idx = 0;
             return;
         }
-        let len = len as isize;
-        let current = self.selected_position_idx.min((len - 1) as usize) as isize;
-        let mut next = current + delta;
-        if next < 0 {
-            next = 0;
-        } else if next >= len {
-            next = len - 1;
+        let current = order
+            .iter()
+            .position(|idx| *idx == self.selected_position_idx)
+            .unwrap_or(0) as isize;
+        let next = (current + delta).clamp(0, order.len() as isize - 1);
+        self.selected_position_idx = order[next as usize];
+    }
+
+    // 净持仓视图把同一合约的各方向排在一起，其余情况保持快照顺序
+    fn position_display_order(&self) -> Vec<usize> {
+        let len = self.positions.len().min(MAX_POSITION_RECORDS);
+        let mut order: Vec<usize> = (0..len).collect();
+        if self.net_positions {
+            let positions = &self.positions[..len];
+            order.sort_by_key(|idx| {
+                positions
+                    .iter()
+                    .position(|position| position.inst_id == positions[*idx].inst_id)
+                    .unwrap_or(*idx)
+            });
         }
-        self.selected_position_idx = next as usize;
+        order
+    }
+
+    fn toggle_net_positions(&mut self) -> bool {
+        self.net_positions = !self.net_positions;
+        self.net_positions
     }
 
     fn move_orders(&mut self, delta: isize) {
@@ -362,7 +706,7 @@ impl TradeState {
         let Some(store) = &self.log_store else {
             return Ok(0);
         };
-        let entries = store.load()?;
+        let entries = store.load_recent(self.max_logs)?;
         let count = entries.len();
         for entry in entries {
             self.push_log(entry);
@@ -378,7 +722,15 @@ impl TradeState {
         let Some(store) = &self.ai_store else {
             return Ok(0);
         };
-        let entries = store.load()?;
+        let entries = store.load_recent(MAX_AI_INSIGHTS)?;
+        let today = Local::now().date_naive();
+        let day_start_ms = today
+            .and_hms_opt(0, 0, 0)
+            .and_then(|start| Local.from_local_datetime(&start).earliest())
+            .map(|start| start.timestamp_millis())
+            .unwrap_or_default();
+        self.ai_usage_today = store.usage_since(day_start_ms)?;
+        self.ai_usage_day = Some(today);
         let count = entries.len();
         for entry in entries {
             self.push_ai_entry(entry);
@@ -386,11 +738,20 @@ impl TradeState {
         Ok(count)
     }
 
+    // 跨日后从零开始累计
+    fn record_ai_usage(&mut self, usage: &AiTokenUsage, day: NaiveDate) {
+        if self.ai_usage_day != Some(day) {
+            self.ai_usage_today = AiTokenUsage::default();
+            self.ai_usage_day = Some(day);
+        }
+        self.ai_usage_today.add(usage);
+    }
+
     fn push_log(&mut self, entry: TradeLogEntry) {
         let was_empty = self.logs.is_empty();
         self.logs.push(entry);
-        if self.logs.len() > MAX_TRADE_LOGS {
-            let overflow = self.logs.len() - MAX_TRADE_LOGS;
+        if self.logs.len() > self.max_logs {
+            let overflow = self.logs.len() - self.max_logs;
             self.logs.drain(0..overflow);
             if self.logs.is_empty() {
                 self.selected_log_idx = 0;
@@ -420,7 +781,12 @@ impl TradeState {
         if !self.ai_enabled {
             return Ok(());
         }
+        self.ai_stream = None;
+        self.ai_pending = None;
         let entry = AiDecisionRecord::from_payload(payload);
+        if let Some(usage) = &entry.usage {
+            self.record_ai_usage(usage, entry.timestamp.date_naive());
+        }
         self.push_ai_entry(entry.clone());
         if let Some(store) = &self.ai_store {
             store.append(&entry)?;
@@ -633,7 +999,26 @@ impl TradeState {
             TradeFocus::Positions => self.ensure_position_selection(),
             TradeFocus::Orders => self.ensure_order_selection(),
             TradeFocus::AiInsights => self.ensure_ai_selection(),
-            TradeFocus::Logs => {}
+            TradeFocus::Logs | TradeFocus::History => {}
+        }
+    }
+
+    fn record_hit_area(&mut self, focus: TradeFocus, area: Rect, rows: Vec<Option<usize>>) {
+        self.hit_areas.push(PanelHitArea { focus, area, rows });
+    }
+
+    fn select_at(&mut self, focus: TradeFocus, target: Option<usize>) {
+        self.focus = focus;
+        match (focus, target) {
+            (TradeFocus::Positions, Some(idx)) => self.selected_position_idx = idx,
+            (TradeFocus::Orders, Some(idx)) => self.selected_order_idx = idx,
+            (TradeFocus::Logs, Some(idx)) => self.selected_log_idx = idx,
+            (TradeFocus::AiInsights, Some(idx)) => self.selected_ai_idx = idx,
+            (TradeFocus::History, Some(idx)) => self.selected_history_idx = idx,
+            (TradeFocus::Positions, None) => self.ensure_position_selection(),
+            (TradeFocus::Orders, None) => self.ensure_order_selection(),
+            (TradeFocus::AiInsights, None) => self.ensure_ai_selection(),
+            _ => {}
         }
     }
 
@@ -644,6 +1029,7 @@ impl TradeState {
             TradeFocus::Orders,
             TradeFocus::AiInsights,
             TradeFocus::Logs,
+            TradeFocus::History,
         ];
         order
     }
@@ -655,31 +1041,10 @@ impl TradeState {
             TradeFocus::Orders => "挂单",
             TradeFocus::Logs => "委托记录",
             TradeFocus::AiInsights => "AI 决策",
+            TradeFocus::History => "历史记录",
         }
     }
 
-    fn balance_lines(&self) -> Vec<String> {
-        if let Some(line) = self.balance_summary_line() {
-            vec![line]
-        } else {
-            Vec::new()
-        }
-    }
-
-    fn balance_summary_line(&self) -> Option<String> {
-        if self.balance.total_equity.is_none() && self.balance.delta.is_empty() {
-            return None;
-        }
-        let mut segments = Vec::new();
-        if let Some(total) = self.balance.total_equity {
-            segments.push(format!(
-                "总权益: {} USDT",
-                Self::format_balance_amount(total)
-            ));
-        }
-        Some(format!("账户余额：{}", segments.join(" · ")))
-    }
-
     fn format_balance_amount(value: f64) -> String {
         let abs = value.abs();
         if abs >= 1000.0 {
@@ -699,19 +1064,67 @@ impl TradeState {
         }
     }
 
+    fn log_filter_active(&self) -> bool {
+        self.log_inst_filter.is_some() || self.log_kind_filter != LogKindFilter::All
+    }
+
+    // 按显示顺序（最新在前）返回符合筛选条件的日志下标
+    fn filtered_log_indices(&self) -> Vec<usize> {
+        self.logs
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, entry)| {
+                self.log_kind_filter.matches(&entry.event)
+                    && self
+                        .log_inst_filter
+                        .as_deref()
+                        .is_none_or(|inst_id| entry.event.inst_id() == inst_id)
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    // 选中项不在筛选结果中时落到最新一条
+    fn selected_log_position(&self, filtered: &[usize]) -> usize {
+        filtered
+            .iter()
+            .position(|idx| *idx == self.selected_log_idx)
+            .unwrap_or(0)
+    }
+
+    fn cycle_log_inst_filter(&mut self, inst_ids: &[String]) {
+        let next = match &self.log_inst_filter {
+            None => inst_ids.first(),
+            Some(current) => inst_ids
+                .iter()
+                .position(|inst_id| inst_id == current)
+                .and_then(|pos| inst_ids.get(pos + 1)),
+        };
+        self.log_inst_filter = next.cloned();
+        self.reset_log_selection();
+    }
+
+    fn cycle_log_kind_filter(&mut self) {
+        self.log_kind_filter = self.log_kind_filter.next();
+        self.reset_log_selection();
+    }
+
+    fn reset_log_selection(&mut self) {
+        if let Some(latest) = self.filtered_log_indices().first() {
+            self.selected_log_idx = *latest;
+        }
+        self.log_detail = None;
+    }
+
     fn move_logs(&mut self, delta: isize) {
-        if delta == 0 || self.logs.is_empty() {
+        let filtered = self.filtered_log_indices();
+        if delta == 0 || filtered.is_empty() {
             return;
         }
-        let len = self.logs.len() as isize;
-        let current_display = len - 1 - self.selected_log_idx.min(self.logs.len() - 1) as isize;
-        let mut next_display = current_display + delta;
-        if next_display < 0 {
-            next_display = 0;
-        } else if next_display >= len {
-            next_display = len - 1;
-        }
-        self.selected_log_idx = (len - 1 - next_display) as usize;
+        let current = self.selected_log_position(&filtered) as isize;
+        let next = (current + delta).clamp(0, filtered.len() as isize - 1);
+        self.selected_log_idx = filtered[next as usize];
     }
 
     fn page_scroll_logs(&mut self, pages: isize) {
@@ -723,17 +1136,15 @@ impl TradeState {
     }
 
     fn scroll_logs_to_start(&mut self) {
-        if self.logs.is_empty() {
-            return;
+        if let Some(latest) = self.filtered_log_indices().first() {
+            self.selected_log_idx = *latest;
         }
-        self.selected_log_idx = self.logs.len().saturating_sub(1);
     }
 
     fn scroll_logs_to_end(&mut self) {
-        if self.logs.is_empty() {
-            return;
+        if let Some(oldest) = self.filtered_log_indices().last() {
+            self.selected_log_idx = *oldest;
         }
-        self.selected_log_idx = 0;
     }
 
     fn set_log_view_height(&mut self, view_height: u16) {
@@ -752,19 +1163,11 @@ impl TradeState {
         if pages == 0 {
             return;
         }
-        let len = self.positions.len().min(MAX_POSITION_RECORDS);
-        if len == 0 {
+        if self.positions.is_empty() {
             return;
         }
         let page = self.position_view_height.max(1) as isize;
-        let len = len as isize;
-        let mut next = self.selected_position_idx as isize + page * pages;
-        if next < 0 {
-            next = 0;
-        } else if next >= len {
-            next = len - 1;
-        }
-        self.selected_position_idx = next as usize;
+        self.move_positions(page * pages);
     }
 
     fn page_scroll_orders(&mut self, pages: isize) {
@@ -787,15 +1190,14 @@ impl TradeState {
     }
 
     fn scroll_positions_to_start(&mut self) {
-        if !self.positions.is_empty() {
-            self.selected_position_idx = 0;
+        if let Some(first) = self.position_display_order().first() {
+            self.selected_position_idx = *first;
         }
     }
 
     fn scroll_positions_to_end(&mut self) {
-        let len = self.positions.len().min(MAX_POSITION_RECORDS);
-        if len > 0 {
-            self.selected_position_idx = len - 1;
+        if let Some(last) = self.position_display_order().last() {
+            self.selected_position_idx = *last;
         }
     }
 
@@ -814,7 +1216,7 @@ impl TradeState {
 
     fn leverage_for_event(&self, event: &TradeEvent) -> Option<f64> {
         match event {
-            TradeEvent::Order(response) => {
+            TradeEvent::Order(response) | TradeEvent::Amend(response) => {
                 self.leverage_for_inst(&response.inst_id, response.pos_side.as_deref())
             }
             TradeEvent::Cancel(cancel) => {
@@ -860,22 +1262,18 @@ impl TradeState {
         self.markets = markets;
     }
 
-    fn selected_log_display_index(&self) -> usize {
-        if self.logs.is_empty() {
-            0
+    fn scope_label(&self) -> &'static str {
+        if self.all_positions {
+            "全部合约"
         } else {
-            let idx = self.selected_log_idx.min(self.logs.len() - 1);
-            self.logs.len().saturating_sub(1) - idx
+            "图表合约"
         }
     }
 
     fn selected_log_entry(&self) -> Option<&TradeLogEntry> {
-        if self.logs.is_empty() {
-            None
-        } else {
-            let idx = self.selected_log_idx.min(self.logs.len().saturating_sub(1));
-            self.logs.get(idx)
-        }
+        let filtered = self.filtered_log_indices();
+        let idx = filtered.get(self.selected_log_position(&filtered))?;
+        self.logs.get(*idx)
     }
 
     fn toggle_log_detail(&mut self) {
@@ -903,23 +1301,215 @@ impl OrderInputState {
             OrderInputField::Price => &mut self.price,
             OrderInputField::Size => &mut self.size,
             OrderInputField::Leverage => &mut self.leverage,
+            OrderInputField::Callback => &mut self.callback,
+            OrderInputField::TakeProfit => &mut self.take_profit,
+            OrderInputField::StopLoss => &mut self.stop_loss,
+            OrderInputField::Risk => &mut self.risk,
+        }
+    }
+
+    fn editable_fields(&self) -> Vec<OrderInputField> {
+        let mut fields = Vec::with_capacity(4);
+        if !self.is_market() {
+            fields.push(OrderInputField::Price);
+        }
+        fields.push(OrderInputField::Size);
+        if self.is_trailing() {
+            fields.push(OrderInputField::Callback);
+        }
+        if self.supports_bracket() {
+            fields.push(OrderInputField::TakeProfit);
+            fields.push(OrderInputField::StopLoss);
+        }
+        if self.calculator {
+            fields.push(OrderInputField::Risk);
         }
+        if !self.spot {
+            fields.push(OrderInputField::Leverage);
+        }
+        fields
     }
 
     fn focus_next_field(&mut self) {
-        self.active_field = match self.active_field {
-            OrderInputField::Price => OrderInputField::Size,
-            OrderInputField::Size => OrderInputField::Leverage,
-            OrderInputField::Leverage => OrderInputField::Price,
-        };
+        self.step_field(1);
     }
 
     fn focus_prev_field(&mut self) {
-        self.active_field = match self.active_field {
-            OrderInputField::Price => OrderInputField::Leverage,
-            OrderInputField::Size => OrderInputField::Price,
-            OrderInputField::Leverage => OrderInputField::Size,
+        self.step_field(-1);
+    }
+
+    fn step_field(&mut self, delta: isize) {
+        let fields = self.editable_fields();
+        let len = fields.len() as isize;
+        let current = fields
+            .iter()
+            .position(|field| *field == self.active_field)
+            .unwrap_or(0) as isize;
+        self.active_field = fields[(current + delta).rem_euclid(len) as usize];
+    }
+
+    fn is_trailing(&self) -> bool {
+        self.order_kind == TradeOrderKind::TrailingStop
+    }
+
+    // 仅新开仓的普通委托可附带止盈止损
+    fn supports_bracket(&self) -> bool {
+        self.order_kind == TradeOrderKind::Regular
+            && self.intent == OrderIntent::Manual
+            && !self.reduce_only
+            && self.replace_order_id.is_none()
+    }
+
+    fn parse_bracket(&self) -> Result<Option<BracketOrder>, &'static str> {
+        let parse = |raw: &str, invalid: &'static str| {
+            let trimmed = raw.trim();
+            if trimmed.is_empty() {
+                return Ok(None);
+            }
+            match trimmed.parse::<f64>() {
+                Ok(value) if value > 0.0 => Ok(Some(value)),
+                _ => Err(invalid),
+            }
+        };
+        let bracket = BracketOrder {
+            take_profit: parse(&self.take_profit, "止盈价格必须为正数")?,
+            stop_loss: parse(&self.stop_loss, "止损价格必须为正数")?,
+        };
+        Ok((!bracket.is_empty()).then_some(bracket))
+    }
+
+    fn toggle_calculator(&mut self) {
+        if !self.supports_bracket() {
+            self.error = Some("仅新开仓委托支持仓位计算器".to_string());
+            return;
+        }
+        self.calculator = !self.calculator;
+        self.active_field = if self.calculator {
+            OrderInputField::Risk
+        } else {
+            OrderInputField::Size
+        };
+        self.error = None;
+    }
+
+    // 按止损距离反推数量，使触发止损时亏损约等于风险金额
+    fn risk_sizing(
+        &self,
+        entry: Option<f64>,
+        market: Option<&MarketInfo>,
+    ) -> Result<RiskSizing, &'static str> {
+        let parse = |raw: &str| raw.trim().parse::<f64>().ok().filter(|value| *value > 0.0);
+        let risk = parse(&self.risk).ok_or("风险金额必须为正数")?;
+        let stop = parse(&self.stop_loss).ok_or("请填写止损价")?;
+        let entry = entry.filter(|value| *value > 0.0).ok_or("缺少入场价格")?;
+        let distance = match self.side {
+            TradeSide::Buy => entry - stop,
+            TradeSide::Sell => stop - entry,
         };
+        if distance <= 0.0 {
+            return Err(match self.side {
+                TradeSide::Buy => "买入止损价需低于入场价",
+                TradeSide::Sell => "卖出止损价需高于入场价",
+            });
+        }
+        let market = market.ok_or("无法获取合约信息")?;
+        let contracts = market.round_size(risk / distance / market.ct_val);
+        if contracts <= 0.0 || contracts < market.min_sz {
+            return Err("风险金额过小，低于最小下单量");
+        }
+        let size = contracts * market.ct_val;
+        let notional = size * entry;
+        Ok(RiskSizing {
+            size,
+            contracts,
+            notional,
+            margin: parse(&self.leverage)
+                .filter(|_| !self.spot)
+                .map(|leverage| notional / leverage),
+            loss: size * distance,
+        })
+    }
+
+    fn parse_callback(&self) -> Option<TrailingCallback> {
+        let trimmed = self.callback.trim();
+        if let Some(percent) = trimmed.strip_suffix('%') {
+            let ratio = percent.trim().parse::<f64>().ok()? / 100.0;
+            (ratio > 0.0 && ratio < 1.0).then_some(TrailingCallback::Ratio(ratio))
+        } else {
+            let spread = trimmed.parse::<f64>().ok()?;
+            (spread > 0.0).then_some(TrailingCallback::Spread(spread))
+        }
+    }
+
+    // 只减仓委托必须有可被减少的持仓且不超过持仓数量；开仓委托不受持仓约束
+    fn check_reduce_only_position(
+        &self,
+        positions: &[PositionInfo],
+        mode: PositionMode,
+        size: f64,
+    ) -> Result<(), String> {
+        if !self.reduce_only {
+            return Ok(());
+        }
+        let position = positions.iter().find(|pos| {
+            pos.inst_id == self.inst_id
+                && TuiApp::pos_side_for_position(pos, mode) == self.pos_side
+                && TuiApp::closing_side_for_position(pos, mode) == self.side
+        });
+        match position {
+            Some(position) if size > position.size.abs() => {
+                Err("平仓数量不能大于持仓数量".to_string())
+            }
+            Some(_) => Ok(()),
+            None if self.intent == OrderIntent::Manual => Err(format!(
+                "{} 没有可被{}减少的持仓，只减仓委托会被拒绝",
+                self.inst_id,
+                TuiApp::side_label(self.side)
+            )),
+            None => Err("未找到对应持仓，无法提交平仓单".to_string()),
+        }
+    }
+
+    fn is_market(&self) -> bool {
+        self.ord_type == TradeOrderType::Market
+    }
+
+    fn supports_market(&self) -> bool {
+        self.order_kind == TradeOrderKind::Regular && self.intent != OrderIntent::Modify
+    }
+
+    fn toggle_ord_type(&mut self) {
+        if !self.supports_market() {
+            self.error = Some("当前委托不支持切换委托类型".to_string());
+            return;
+        }
+        self.ord_type = self.ord_type.next();
+        if self.is_market() && self.active_field == OrderInputField::Price {
+            self.active_field = OrderInputField::Size;
+        }
+        self.error = None;
+    }
+
+    fn effective_margin_mode(&self) -> Option<MarginMode> {
+        self.margin_mode.or(self.default_margin_mode)
+    }
+
+    fn toggle_margin_mode(&mut self) {
+        if self.spot {
+            self.error = Some("现货交易不使用保证金模式".to_string());
+            return;
+        }
+        if self.intent == OrderIntent::Modify {
+            self.error = Some("改单不支持切换保证金模式".to_string());
+            return;
+        }
+        if self.reduce_only && self.initial_margin_mode.is_some() {
+            self.error = Some("平仓需沿用持仓的保证金模式".to_string());
+            return;
+        }
+        let current = self.effective_margin_mode().unwrap_or(MarginMode::Cross);
+        self.margin_mode = Some(current.toggled());
+        self.error = None;
     }
 }
 
@@ -929,6 +1519,7 @@ impl OrderIntent {
             OrderIntent::Manual => "",
             OrderIntent::TakeProfit => "止盈 ",
             OrderIntent::StopLoss => "止损 ",
+            OrderIntent::TrailingStop => "移动止损 ",
             OrderIntent::Modify => "修改 ",
         }
     }
@@ -938,21 +1529,178 @@ impl OrderIntent {
             OrderIntent::Manual => "下单",
             OrderIntent::TakeProfit => "止盈",
             OrderIntent::StopLoss => "止损",
+            OrderIntent::TrailingStop => "移动止损",
             OrderIntent::Modify => "改单",
         }
     }
 }
 
-pub struct TuiApp {
-    inst_ids: Vec<String>,
-    colors: HashMap<String, Color>,
-    data: HashMap<String, Vec<(f64, f64)>>,
-    window: [f64; 2],
-    last_draw: Instant,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ThresholdField {
+    Lower,
+    Upper,
+    ChangePct,
+    Atr,
+    Cooldown,
+}
+
+#[derive(Clone, Debug)]
+struct ThresholdInputState {
+    inst_id: String,
+    lower: String,
+    upper: String,
+    change_pct: String,
+    atr: String,
+    cooldown: String,
+    active_field: ThresholdField,
+    error: Option<String>,
+}
+
+impl ThresholdInputState {
+    fn new(inst_id: String, current: PriceThreshold) -> Self {
+        let format = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        ThresholdInputState {
+            inst_id,
+            lower: format(current.lower),
+            upper: format(current.upper),
+            change_pct: format(current.change_pct),
+            atr: format(current.atr),
+            cooldown: current
+                .cooldown_secs
+                .map(|secs| secs.to_string())
+                .unwrap_or_default(),
+            active_field: ThresholdField::Lower,
+            error: None,
+        }
+    }
+
+    fn active_value_mut(&mut self) -> &mut String {
+        match self.active_field {
+            ThresholdField::Lower => &mut self.lower,
+            ThresholdField::Upper => &mut self.upper,
+            ThresholdField::ChangePct => &mut self.change_pct,
+            ThresholdField::Atr => &mut self.atr,
+            ThresholdField::Cooldown => &mut self.cooldown,
+        }
+    }
+
+    fn focus_next_field(&mut self) {
+        self.active_field = match self.active_field {
+            ThresholdField::Lower => ThresholdField::Upper,
+            ThresholdField::Upper => ThresholdField::ChangePct,
+            ThresholdField::ChangePct => ThresholdField::Atr,
+            ThresholdField::Atr => ThresholdField::Cooldown,
+            ThresholdField::Cooldown => ThresholdField::Lower,
+        };
+    }
+
+    fn focus_prev_field(&mut self) {
+        self.active_field = match self.active_field {
+            ThresholdField::Lower => ThresholdField::Cooldown,
+            ThresholdField::Upper => ThresholdField::Lower,
+            ThresholdField::ChangePct => ThresholdField::Upper,
+            ThresholdField::Atr => ThresholdField::ChangePct,
+            ThresholdField::Cooldown => ThresholdField::Atr,
+        };
+    }
+
+    fn parse(&self) -> Result<PriceThreshold, String> {
+        let parse_field = |value: &str, name: &str| -> Result<Option<f64>, String> {
+            let trimmed = value.trim();
+            if trimmed.is_empty() {
+                return Ok(None);
+            }
+            match trimmed.parse::<f64>() {
+                Ok(value) if value > 0.0 => Ok(Some(value)),
+                _ => Err(format!("{name} must be a positive number")),
+            }
+        };
+        let threshold = PriceThreshold {
+            lower: parse_field(&self.lower, "Lower bound")?,
+            upper: parse_field(&self.upper, "Upper bound")?,
+            change_pct: parse_field(&self.change_pct, "Change %")?,
+            atr: parse_field(&self.atr, "ATR")?,
+            cooldown_secs: match self.cooldown.trim() {
+                "" => None,
+                value => match value.parse::<u64>() {
+                    Ok(secs) if secs > 0 => Some(secs),
+                    _ => return Err("Cooldown must be a whole number of seconds".to_string()),
+                },
+            },
+        };
+        if matches!(
+            (threshold.lower, threshold.upper),
+            (Some(lower), Some(upper)) if lower >= upper
+        ) {
+            return Err("Lower bound must be below upper bound".to_string());
+        }
+        Ok(threshold)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct InstrumentInputState {
+    value: String,
+    error: Option<String>,
+    // 打开输入框时的选中项，Esc 取消跳转时恢复
+    origin_idx: usize,
+    matched: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct LeverageInputState {
+    inst_id: String,
+    pos_side: Option<String>,
+    margin_mode: Option<MarginMode>,
+    value: String,
+    error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct HelpOverlayState {
+    scroll: u16,
+    view_height: u16,
+    total_rows: usize,
+}
+
+impl HelpOverlayState {
+    fn max_scroll(&self) -> u16 {
+        self.total_rows
+            .saturating_sub(self.view_height.max(1) as usize)
+            .min(u16::MAX as usize) as u16
+    }
+
+    fn scroll_by(&mut self, delta: i32) {
+        let next = (self.scroll as i32 + delta).clamp(0, self.max_scroll() as i32);
+        self.scroll = next as u16;
+    }
+}
+
+// 面板每帧都会格式化价格与数量，按 (合约, 数值, 精度) 缓存格式化结果
+#[derive(Default)]
+struct LabelCache {
+    prices: HashMap<String, HashMap<(u64, usize), Rc<str>>>,
+    sizes: HashMap<String, HashMap<u64, Rc<str>>>,
+}
+
+pub struct TuiApp {
+    inst_ids: Vec<String>,
+    colors: HashMap<String, Color>,
+    data: HashMap<String, PriceSeries>,
+    window: [f64; 2],
+    follow_latest: bool,
+    last_draw: Instant,
     min_redraw_gap: Duration,
+    last_activity: Instant,
     retention: Duration,
     latest_prices: HashMap<String, f64>,
+    // 每个合约最近一次收到标记价推送的时间，超过阈值视为价格过时
+    price_updated_at: HashMap<String, Instant>,
+    stale_price_after: Duration,
     price_precision: HashMap<String, usize>,
+    precision_overrides: HashMap<String, usize>,
+    label_cache: RefCell<LabelCache>,
+    size_unit: SizeUnit,
     last_update: Option<String>,
     status_message: Option<String>,
     status_visible_until: Option<Instant>,
@@ -960,11 +1708,50 @@ pub struct TuiApp {
     normalize: bool,
     y_zoom: f64,
     multi_axis: bool,
+    chart_style: ChartStyle,
+    candle_interval_idx: usize,
+    sma_enabled: bool,
+    sma_period: usize,
+    bollinger_enabled: bool,
+    log_scale: bool,
+    macd_enabled: bool,
     view_mode: ViewMode,
+    stats_period: StatsPeriod,
+    stats_return_view: ViewMode,
     trade: TradeState,
     exit_confirmation: bool,
+    close_all_confirmation: bool,
+    reverse_confirmation: Option<PositionInfo>,
+    confirm_orders: bool,
+    mouse_enabled: bool,
+    order_confirmation: Option<StagedOrder>,
+    undo_order: Option<UndoableOrder>,
+    ai_proposal: Option<AiDecisionProposal>,
     loading_overlay: Option<LoadingOverlay>,
     timezone: ConfiguredTimeZone,
+    funding_rates: HashMap<String, FundingRateInfo>,
+    // 频道顺序 -> (标签, 各连接编号的状态)
+    conn_states: BTreeMap<usize, (&'static str, HashMap<u32, bool>)>,
+    order_books: HashMap<String, OrderBookLevels>,
+    public_trades: HashMap<String, VecDeque<PublicTrade>>,
+    tickers: HashMap<String, TickerInfo>,
+    long_short_ratios: HashMap<String, VecDeque<LongShortRatio>>,
+    export_dir: PathBuf,
+    demo: bool,
+    dry_run: bool,
+    replay: bool,
+    theme: Theme,
+    thresholds: HashMap<String, PriceThreshold>,
+    threshold_input: Option<ThresholdInputState>,
+    instrument_input: Option<InstrumentInputState>,
+    leverage_input: Option<LeverageInputState>,
+    help_overlay: Option<HelpOverlayState>,
+    pending_instruments: HashSet<String>,
+    removed_instruments: HashSet<String>,
+    command_tx: Option<broadcast::Sender<Command>>,
+    // 消息积压触发的账户重同步，限频避免继续加重广播通道
+    last_lag_resync: Option<Instant>,
+    run_config: Option<AppRunConfig>,
 }
 impl TuiApp {
     fn is_exit_key(key: &KeyEvent) -> bool {
@@ -981,6 +1768,7 @@ impl TuiApp {
         order_tx: Option<mpsc::Sender<TradingCommand>>,
         ai_enabled: bool,
         ai_label: Option<String>,
+        ai_interval: Option<Duration>,
         wait_for_markets: bool,
         timezone: ConfiguredTimeZone,
     ) -> TuiApp {
@@ -990,11 +1778,12 @@ impl TuiApp {
         } else {
             inst_ids.to_vec()
         };
+        let theme = Theme::default();
         let mut data = HashMap::new();
         let mut colors = HashMap::new();
         for (idx, inst_id) in inst_ids.iter().enumerate() {
-            data.insert(inst_id.clone(), Vec::new());
-            colors.insert(inst_id.clone(), COLOR_PALETTE[idx % COLOR_PALETTE.len()]);
+            data.insert(inst_id.clone(), PriceSeries::new());
+            colors.insert(inst_id.clone(), theme.palette_color(idx));
         }
         let log_store = TradeLogStore::new(TradeLogStore::default_path());
         let ai_store = if ai_enabled {
@@ -1012,11 +1801,18 @@ impl TuiApp {
             colors,
             data,
             window: [0.0, 100.0],
+            follow_latest: true,
             last_draw: Instant::now() - min_redraw_gap,
             min_redraw_gap,
+            last_activity: Instant::now(),
             retention,
             latest_prices: HashMap::new(),
+            price_updated_at: HashMap::new(),
+            stale_price_after: Duration::from_secs(30),
             price_precision: HashMap::new(),
+            precision_overrides: HashMap::new(),
+            label_cache: RefCell::new(LabelCache::default()),
+            size_unit: SizeUnit::default(),
             last_update: None,
             status_message: None,
             status_visible_until: None,
@@ -1024,7 +1820,16 @@ impl TuiApp {
             normalize: false,
             y_zoom: 1.0,
             multi_axis: false,
+            chart_style: ChartStyle::Line,
+            candle_interval_idx: 2,
+            sma_enabled: false,
+            bollinger_enabled: false,
+            log_scale: false,
+            macd_enabled: false,
+            sma_period: DEFAULT_SMA_PERIOD,
             view_mode: ViewMode::Chart,
+            stats_period: StatsPeriod::default(),
+            stats_return_view: ViewMode::Chart,
             trade: TradeState::new(
                 order_tx,
                 Some(log_store),
@@ -1032,10 +1837,39 @@ impl TuiApp {
                 markets,
                 ai_enabled,
                 ai_label,
+                ai_interval,
             ),
             exit_confirmation: false,
+            close_all_confirmation: false,
+            reverse_confirmation: None,
+            confirm_orders: false,
+            mouse_enabled: false,
+            order_confirmation: None,
+            undo_order: None,
+            ai_proposal: None,
             loading_overlay,
             timezone,
+            funding_rates: HashMap::new(),
+            conn_states: BTreeMap::new(),
+            order_books: HashMap::new(),
+            public_trades: HashMap::new(),
+            tickers: HashMap::new(),
+            long_short_ratios: HashMap::new(),
+            export_dir: export::default_export_dir(),
+            demo: false,
+            dry_run: false,
+            replay: false,
+            theme,
+            thresholds: HashMap::new(),
+            threshold_input: None,
+            instrument_input: None,
+            leverage_input: None,
+            help_overlay: None,
+            pending_instruments: HashSet::new(),
+            removed_instruments: HashSet::new(),
+            command_tx: None,
+            last_lag_resync: None,
+            run_config: None,
         }
     }
 
@@ -1068,6 +1902,47 @@ impl TuiApp {
         self.status_is_error = false;
     }
 
+    fn status_expired(&self) -> bool {
+        self.status_message.is_some()
+            && self
+                .status_visible_until
+                .is_some_and(|visible_until| Instant::now() >= visible_until)
+    }
+
+    // 加载动画需要持续刷新，不进入空闲模式
+    fn input_poll_gap(&self) -> Duration {
+        if self.loading_overlay.is_none() && self.last_activity.elapsed() >= IDLE_AFTER {
+            IDLE_POLL_INTERVAL.max(self.min_redraw_gap)
+        } else {
+            self.min_redraw_gap
+        }
+    }
+
+    pub fn set_stale_price_after(&mut self, threshold: Duration) {
+        self.stale_price_after = threshold;
+    }
+
+    // 已收到过推送但超过阈值未更新时返回距上次更新的时长
+    fn price_stale_for(&self, inst_id: &str) -> Option<Duration> {
+        let elapsed = self.price_updated_at.get(inst_id)?.elapsed();
+        (elapsed >= self.stale_price_after).then_some(elapsed)
+    }
+
+    fn stale_price_label(elapsed: Duration) -> String {
+        format!("(stale {}s)", elapsed.as_secs())
+    }
+
+    fn has_stale_price(&self) -> bool {
+        self.inst_ids
+            .iter()
+            .any(|inst_id| self.price_stale_for(inst_id).is_some())
+    }
+
+    pub fn set_redraw_gap(&mut self, gap: Duration) {
+        self.min_redraw_gap = gap;
+        self.last_draw = Instant::now() - gap;
+    }
+
     fn loading_blocks_input(&self) -> bool {
         self.loading_overlay
             .as_ref()
@@ -1076,10 +1951,15 @@ impl TuiApp {
     }
 
     fn update_loading_overlay_animation(&mut self) -> bool {
+        let ai_ticked = self
+            .trade
+            .ai_pending
+            .as_mut()
+            .is_some_and(|pending| pending.tick());
         if let Some(overlay) = &mut self.loading_overlay {
-            return overlay.tick();
+            return overlay.tick() || ai_ticked;
         }
-        false
+        ai_ticked
     }
 
     fn finish_market_loading(&mut self, loaded: bool) {
@@ -1091,15 +1971,133 @@ impl TuiApp {
         }
     }
 
-    pub fn dispose(&self) {
+    pub fn dispose(&mut self) {
+        if self.mouse_enabled {
+            let _ = crossterm::execute!(std::io::stdout(), DisableMouseCapture);
+        }
         ratatui::restore();
+        let chart = self.chart_preferences();
+        if let Some(run_config) = self.run_config.as_mut()
+            && let Err(err) = run_config.save_chart_preferences(chart)
+        {
+            eprintln!("failed to save chart preferences: {err:#}");
+        }
+    }
+
+    pub fn set_precision_overrides(&mut self, overrides: HashMap<String, usize>) {
+        self.precision_overrides = overrides;
+        self.label_cache.get_mut().prices.clear();
+    }
+
+    pub fn set_trade_log_retention(&mut self, retention: TradeLogRetention) {
+        self.trade.max_logs = retention.memory_entries;
+        if let Some(store) = self.trade.log_store.as_mut() {
+            store.set_retention(retention);
+        }
+    }
+
+    pub fn set_chart_preferences(&mut self, chart: ChartPreferences) {
+        self.normalize = chart.normalize;
+        self.log_scale = chart.log_scale && !chart.normalize;
+        self.multi_axis = chart.multi_axis;
+        self.y_zoom = if chart.y_zoom.is_finite() {
+            chart.y_zoom.clamp(0.05, 100.0)
+        } else {
+            1.0
+        };
+        self.chart_style = if chart.candles {
+            ChartStyle::Candles
+        } else {
+            ChartStyle::Line
+        };
+        if let Some(idx) = CANDLE_INTERVALS_SECS
+            .iter()
+            .position(|secs| *secs == chart.candle_interval_secs)
+        {
+            self.candle_interval_idx = idx;
+        }
+    }
+
+    fn chart_preferences(&self) -> ChartPreferences {
+        ChartPreferences {
+            normalize: self.normalize,
+            log_scale: self.log_scale,
+            multi_axis: self.multi_axis,
+            y_zoom: self.y_zoom,
+            candles: self.chart_style == ChartStyle::Candles,
+            candle_interval_secs: self.candle_interval_secs(),
+        }
     }
 
     pub fn preload_history(&mut self, points: &[PricePoint]) {
         self.load_history(points);
     }
 
+    pub fn set_export_dir(&mut self, dir: PathBuf) {
+        self.export_dir = dir;
+    }
+
+    pub fn set_thresholds(
+        &mut self,
+        thresholds: HashMap<String, PriceThreshold>,
+        command_tx: broadcast::Sender<Command>,
+        run_config: AppRunConfig,
+    ) {
+        self.thresholds = thresholds;
+        self.command_tx = Some(command_tx);
+        self.run_config = Some(run_config);
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        for (idx, inst_id) in self.inst_ids.iter().enumerate() {
+            self.colors
+                .insert(inst_id.clone(), theme.palette_color(idx));
+        }
+        self.theme = theme;
+    }
+
+    pub fn set_demo_mode(&mut self, demo: bool) {
+        self.demo = demo;
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    // 回放的成交不写入本地委托日志，避免与真实记录混在一起
+    pub fn set_replay_mode(&mut self, replay: bool) {
+        self.replay = replay;
+        if replay {
+            self.trade.log_store = None;
+        }
+    }
+
+    pub fn set_confirm_orders(&mut self, confirm: bool) {
+        self.confirm_orders = confirm;
+    }
+
+    pub fn set_mouse_enabled(&mut self, enabled: bool) {
+        self.mouse_enabled = enabled;
+    }
+
+    pub fn set_default_margin_mode(&mut self, mode: Option<MarginMode>) {
+        self.trade.default_margin_mode = mode;
+    }
+
+    pub fn set_position_mode(&mut self, mode: PositionMode) {
+        self.trade.position_mode = mode;
+    }
+
+    pub fn set_order_limits(&mut self, limits: OrderLimits) {
+        self.trade.order_limits = limits;
+    }
+
     pub fn preload_trade_logs(&mut self) {
+        if let Some(store) = &self.trade.log_store
+            && let Err(err) = store.rotate()
+        {
+            self.set_error_status_message(format!("委托日志轮转失败: {err}"));
+        }
         if let Err(err) = self.trade.load_persisted_logs() {
             self.set_error_status_message(format!("加载历史委托记录失败: {err}"));
         }
@@ -1118,39 +2116,61 @@ impl TuiApp {
     ) -> Result<()> {
         color_eyre::install()?;
         let mut terminal = ratatui::init();
-        let mut input_tick = tokio::time::interval(self.min_redraw_gap);
+        if self.mouse_enabled {
+            crossterm::execute!(std::io::stdout(), EnableMouseCapture)?;
+        }
+        let mut poll_gap = self.min_redraw_gap;
+        let mut input_tick = tokio::time::interval(poll_gap);
         terminal.draw(|frame| self.render(frame))?;
         self.last_draw = Instant::now();
+        // 行情、账户等高频更新只标记 dirty，距上次绘制满 min_redraw_gap 后合并绘制一次；
+        // 错误、提醒与弹窗等需要立即可见的状态设置 draw_now
+        let mut dirty = false;
+        let mut draw_now = false;
         loop {
+            let redraw_at = tokio::time::Instant::from_std(self.last_draw + self.min_redraw_gap);
             tokio::select! {
                 biased;
                 _ = input_tick.tick() => {
-                    let mut should_redraw = false;
                     if self.update_loading_overlay_animation() {
-                        should_redraw = true;
+                        dirty = true;
+                    }
+                    // 没有行情推送时也要按时收起状态栏消息
+                    if self.status_expired() {
+                        self.clear_status_message();
+                        dirty = true;
+                    }
+                    // 价格过时标记需要随时间刷新秒数
+                    if self.last_draw.elapsed() >= Duration::from_secs(1) && self.has_stale_price() {
+                        dirty = true;
                     }
                     if self.poll_input()? {
                         return Ok(());
                     }
-                    if should_redraw && self.last_draw.elapsed() >= self.min_redraw_gap {
-                        terminal.draw(|frame| self.render(frame))?;
-                        self.last_draw = Instant::now();
+                    let next_gap = self.input_poll_gap();
+                    if next_gap != poll_gap {
+                        poll_gap = next_gap;
+                        input_tick = tokio::time::interval_at(
+                            tokio::time::Instant::now() + poll_gap,
+                            poll_gap,
+                        );
                     }
                 }
+                _ = tokio::time::sleep_until(redraw_at), if dirty => {}
                 result = rx.recv() => {
                     match result {
+                        // 退订生效前仍可能收到已移除币种的推送，直接丢弃
+                        Ok(Command::MarkPriceUpdate(inst_id, ..))
+                            if self.removed_instruments.contains(&inst_id) => {}
                         Ok(Command::MarkPriceUpdate(inst_id, mark_px, ts, precision)) => {
+                            self.last_activity = Instant::now();
                             self.clear_status_if_allowed();
                             self.on_tick(&inst_id, mark_px, ts, precision);
-                            if self.last_draw.elapsed() >= self.min_redraw_gap {
-                                terminal.draw(|frame| self.render(frame))?;
-                                self.last_draw = Instant::now();
-                            }
+                            dirty = true;
                         }
                         Ok(Command::Notify(inst_id, message)) => {
                             self.set_status_message(format!("{inst_id}: {message}"));
-                            terminal.draw(|frame| self.render(frame))?;
-                            self.last_draw = Instant::now();
+                            draw_now = true;
                         }
                         Ok(Command::AiInsight(record)) => {
                             if let Err(err) = self.trade.push_ai_insight(record) {
@@ -1168,17 +2188,19 @@ impl TuiApp {
                                     Some(Instant::now() + Duration::from_secs(15));
                                 self.status_is_error = false;
                             }
-                            terminal.draw(|frame| self.render(frame))?;
-                            self.last_draw = Instant::now();
+                            draw_now = true;
+                        }
+                        Ok(Command::AiInsightPartial(text)) => {
+                            self.trade.ai_stream = text;
+                            dirty = true;
                         }
                         Ok(Command::Error(message)) => {
                             self.set_error_status_message(message);
-                            terminal.draw(|frame| self.render(frame))?;
-                            self.last_draw = Instant::now();
+                            draw_now = true;
                         }
                         Ok(Command::TradeResult(event)) => {
                             let (message, is_error) = match &event {
-                                TradeEvent::Order(response) => {
+                                TradeEvent::Order(response) | TradeEvent::Amend(response) => {
                                     (response.message.to_string(), !response.success)
                                 }
                                 TradeEvent::Cancel(cancel) => {
@@ -1208,6 +2230,13 @@ impl TuiApp {
                                     )
                                 }
                             };
+                            let message = match &event {
+                                TradeEvent::Order(response) => {
+                                    let hint = self.correlate_undo_order(response);
+                                    format!("{message}{}", hint.unwrap_or_default())
+                                }
+                                _ => message,
+                            };
                             let event_for_log = event.clone();
                             if let Err(err) = self.trade.record_result(event_for_log) {
                                 self.set_error_status_message(format!(
@@ -1219,23 +2248,153 @@ impl TuiApp {
                             } else {
                                 self.set_status_message(message);
                             }
-                            terminal.draw(|frame| self.render(frame))?;
-                            self.last_draw = Instant::now();
+                            // 连续成交时合并绘制，失败回报需立即显示
+                            if is_error {
+                                draw_now = true;
+                            } else {
+                                dirty = true;
+                            }
                         }
                         Ok(Command::AccountSnapshot(snapshot)) => {
                             self.trade.update_snapshot(snapshot, &self.inst_ids);
-                            terminal.draw(|frame| self.render(frame))?;
-                            self.last_draw = Instant::now();
+                            dirty = true;
+                        }
+                        Ok(Command::OrderBook(inst_id, book)) => {
+                            let visible = self
+                                .trade
+                                .input
+                                .as_ref()
+                                .is_some_and(|input| input.inst_id == inst_id);
+                            self.order_books.insert(inst_id, book);
+                            dirty |= visible;
+                        }
+                        Ok(Command::PublicTrades(inst_id, ..))
+                            if self.removed_instruments.contains(&inst_id) => {}
+                        Ok(Command::PublicTrades(inst_id, trades)) => {
+                            let tape = self.public_trades.entry(inst_id).or_default();
+                            for trade in trades {
+                                tape.push_front(trade);
+                            }
+                            tape.truncate(MAX_PUBLIC_TRADES);
+                            dirty |= self.view_mode == ViewMode::Chart;
+                        }
+                        Ok(Command::Ticker(info)) => {
+                            self.tickers.insert(info.inst_id.clone(), info);
+                            dirty = true;
+                        }
+                        Ok(Command::LongShortRatio(inst_id, ratios)) => {
+                            self.record_long_short_ratios(inst_id, ratios);
+                            dirty = true;
+                        }
+                        Ok(Command::ConnState(channel, conn_id, connected)) => {
+                            if self.update_conn_state(&channel, conn_id, connected) {
+                                dirty = true;
+                            }
+                        }
+                        Ok(Command::FundingRate(info)) => {
+                            self.funding_rates.insert(info.inst_id.clone(), info);
+                            dirty = true;
+                        }
+                        Ok(Command::SetThreshold(..))
+                        | Ok(Command::AddInstrument(_))
+                        | Ok(Command::RemoveInstrument(_))
+                        | Ok(Command::CancelAllCompleted(_))
+                        | Ok(Command::ResyncAccount)
+                        | Ok(Command::ShowAllPositions(_))
+                        | Ok(Command::FetchOrderHistory(_)) => {}
+                        Ok(Command::MarketsAdded(markets)) => {
+                            for (inst_id, market) in &markets {
+                                if let Some(precision) = market.price_precision() {
+                                    self.update_precision(inst_id, precision);
+                                }
+                            }
+                            self.trade.markets.extend(markets);
+                            self.label_cache.borrow_mut().sizes.clear();
+                            dirty = true;
+                        }
+                        Ok(Command::OrderHistory(result)) => {
+                            match result {
+                                Ok(history) => {
+                                    self.set_status_message(format!(
+                                        "已加载{}历史委托 {} 条 · 平仓 {} 条",
+                                        self.trade.history_range.label(),
+                                        history.orders.len(),
+                                        history.closed_positions.len()
+                                    ));
+                                    self.trade.set_history(history);
+                                }
+                                Err(err) => {
+                                    self.trade.history_loading = false;
+                                    self.set_error_status_message(format!(
+                                        "加载历史记录失败: {err}"
+                                    ));
+                                }
+                            }
+                            draw_now = true;
+                        }
+                        Ok(Command::InstrumentAdded(inst_id, mark_px, precision)) => {
+                            self.pending_instruments.remove(&inst_id);
+                            self.removed_instruments.remove(&inst_id);
+                            if !self.inst_ids.contains(&inst_id) {
+                                self.on_tick(
+                                    &inst_id,
+                                    mark_px,
+                                    Utc::now().timestamp_millis(),
+                                    precision,
+                                );
+                                self.set_status_message(format!("Added {inst_id} to chart (/)"));
+                            }
+                            draw_now = true;
+                        }
+                        Ok(Command::InstrumentRejected(inst_id, reason)) => {
+                            self.pending_instruments.remove(&inst_id);
+                            self.set_error_status_message(format!(
+                                "Cannot add {inst_id}: {reason}"
+                            ));
+                            draw_now = true;
+                        }
+                        Ok(Command::AiDecisionProposed(proposal)) => {
+                            self.set_status_message(format!(
+                                "{} 决策待确认：Y/Enter 执行 · N/Esc 拒绝",
+                                proposal.operator
+                            ));
+                            self.ai_proposal = Some(proposal);
+                            draw_now = true;
+                        }
+                        // 本地确认后弹窗已关闭，这里只处理超时等由 AI 侧发起的结束
+                        Ok(Command::AiDecisionResolved(id, _))
+                            if self.ai_proposal.as_ref().is_some_and(|p| p.id == id) =>
+                        {
+                            self.ai_proposal = None;
+                            self.set_error_status_message("AI 决策确认超时，已放弃执行");
+                            draw_now = true;
+                        }
+                        Ok(Command::AiDecisionResolved(..)) | Ok(Command::AiAnalyzeNow) => {}
+                        Ok(Command::AiAnalysisRunning(running)) => {
+                            self.trade.ai_pending = running.then(|| {
+                                LoadingOverlay::new(
+                                    format!("{} 分析中", self.trade.ai_label()),
+                                    false,
+                                )
+                            });
+                            dirty = true;
                         }
                         Ok(Command::MarketsLoaded(markets)) => {
                             let has_data = !markets.is_empty();
+                            for (inst_id, market) in &markets {
+                                if let Some(precision) = market.price_precision() {
+                                    self.update_precision(inst_id, precision);
+                                }
+                            }
                             self.trade.update_markets(markets);
+                            self.label_cache.borrow_mut().sizes.clear();
                             self.finish_market_loading(has_data);
-                            terminal.draw(|frame| self.render(frame))?;
-                            self.last_draw = Instant::now();
+                            dirty = true;
                         }
                         Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
-                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                            self.request_account_resync(skipped);
+                        }
                     }
                 }
                 signal = exit_rx.recv() => match signal {
@@ -1245,9 +2404,56 @@ impl TuiApp {
                     Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
                 }
             }
+            if draw_now || (dirty && self.last_draw.elapsed() >= self.min_redraw_gap) {
+                terminal.draw(|frame| self.render(frame))?;
+                self.last_draw = Instant::now();
+                dirty = false;
+                draw_now = false;
+            }
         }
         Ok(())
     }
+    fn toggle_all_positions(&mut self) {
+        if !self.trade.trading_enabled() {
+            self.set_error_status_message("未配置 OKX API，无法查看账户持仓");
+            return;
+        }
+        let Some(tx) = self.command_tx.as_ref() else {
+            return;
+        };
+        let all = !self.trade.all_positions;
+        if tx.send(Command::ShowAllPositions(all)).is_err() {
+            self.set_error_status_message("切换持仓范围失败");
+            return;
+        }
+        self.trade.all_positions = all;
+        self.set_status_message(format!(
+            "持仓与挂单范围: {}，正在重新同步账户 (W)",
+            self.trade.scope_label()
+        ));
+    }
+
+    // 广播通道积压会丢弃消息，可能漏掉账户快照，请求主线程重新拉取一次
+    // 丢弃提示只写状态栏，避免经广播再转发给声音与远程推送
+    fn request_account_resync(&mut self, skipped: u64) {
+        let resync_due = self
+            .last_lag_resync
+            .is_none_or(|last| last.elapsed() >= LAG_RESYNC_MIN_GAP);
+        let resync_sent = resync_due
+            && self.trade.trading_enabled()
+            && self
+                .command_tx
+                .as_ref()
+                .is_some_and(|tx| tx.send(Command::ResyncAccount).is_ok());
+        let message = if resync_sent {
+            self.last_lag_resync = Some(Instant::now());
+            format!("界面消息积压，已丢弃 {skipped} 条消息，正在重新同步账户")
+        } else {
+            format!("界面消息积压，已丢弃 {skipped} 条消息")
+        };
+        self.set_error_status_message(message);
+    }
+
     fn load_history(&mut self, points: &[PricePoint]) {
         if points.is_empty() {
             return;
@@ -1265,21 +2471,17 @@ impl TuiApp {
         }
         if !self.colors.contains_key(inst_id) {
             let idx = self.colors.len();
-            self.colors.insert(
-                inst_id.to_string(),
-                COLOR_PALETTE[idx % COLOR_PALETTE.len()],
-            );
+            self.colors
+                .insert(inst_id.to_string(), self.theme.palette_color(idx));
         }
         let x = ts as f64;
         let retention_ms = self.retention.as_millis() as i64;
         let cutoff = (ts - retention_ms).max(0) as f64;
-        let series = self
-            .data
-            .entry(inst_id.to_string())
-            .or_insert_with(Vec::new);
-        series.push((x, mark_px));
-        series.retain(|(timestamp, _)| *timestamp >= cutoff);
+        let series = self.data.entry(inst_id.to_string()).or_default();
+        price_series::push_with_retention(series, (x, mark_px), cutoff);
         self.latest_prices.insert(inst_id.to_string(), mark_px);
+        self.price_updated_at
+            .insert(inst_id.to_string(), Instant::now());
         self.update_precision(inst_id, precision);
         self.last_update = Some(inst_id.to_string());
         self.trade.ensure_selection(&self.inst_ids);
@@ -1289,6 +2491,22 @@ impl TuiApp {
         match self.view_mode {
             ViewMode::Chart => self.render_chart_view(frame),
             ViewMode::Trade => self.render_trade_view(frame),
+            ViewMode::Stats => self.render_stats_view(frame),
+        }
+        if self.close_all_confirmation {
+            self.render_close_all_confirmation(frame);
+        }
+        if let Some(position) = &self.reverse_confirmation {
+            self.render_reverse_confirmation(frame, position);
+        }
+        if let Some(staged) = &self.order_confirmation {
+            self.render_order_confirmation(frame, staged);
+        }
+        if let Some(proposal) = &self.ai_proposal {
+            self.render_ai_proposal(frame, proposal);
+        }
+        if self.help_overlay.is_some() {
+            self.render_help_overlay(frame);
         }
         if self.exit_confirmation {
             self.render_exit_confirmation(frame);
@@ -1305,88 +2523,755 @@ impl TuiApp {
                 .direction(Direction::Vertical)
                 .constraints([Constraint::Min(3), Constraint::Length(3)])
                 .split(area);
-            self.render_chart(frame, chunks[0]);
+            self.render_chart_panels(frame, chunks[0]);
             self.render_status(frame, chunks[1]);
         } else {
-            self.render_chart(frame, area);
+            self.render_chart_panels(frame, area);
             if self.status_message.is_some() {
                 self.render_status(frame, area);
             }
         }
-    }
-
-    fn render_trade_view(&mut self, frame: &mut Frame) {
-        let area = frame.area();
-        let has_status = self.status_message.is_some() && area.height >= 6;
-        let (main_area, status_area) = if has_status {
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Min(5), Constraint::Length(3)])
-                .split(area);
-            (chunks[0], Some(chunks[1]))
-        } else {
-            (area, None)
-        };
-        self.render_trade_panel(frame, main_area);
-        if let Some(status_area) = status_area {
-            self.render_status(frame, status_area);
-        } else if self.status_message.is_some() {
-            self.render_status(frame, main_area);
-        }
-        if let Some(input) = &self.trade.input {
-            self.render_order_dialog(frame, main_area, input);
+        if let Some(input) = &self.threshold_input {
+            self.render_threshold_dialog(frame, area, input);
         }
-        if let Some(detail) = self.trade.ai_detail.clone() {
-            self.render_ai_detail(frame, main_area, &detail);
-        } else if let Some(detail) = &self.trade.log_detail {
-            self.render_log_detail(frame, main_area, detail);
+        if let Some(input) = &self.instrument_input {
+            self.render_instrument_dialog(frame, area, input);
         }
     }
 
-    fn render_exit_confirmation(&self, frame: &mut Frame) {
-        let area = frame.area();
-        if area.width < 24 || area.height < 5 {
+    fn render_instrument_dialog(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        input: &InstrumentInputState,
+    ) {
+        if area.width < 20 || area.height < 5 {
             return;
         }
-        let popup_width = area.width.saturating_sub(20).min(50).max(28);
-        let popup_height = 6;
+        let popup_width = area.width.saturating_sub(10).clamp(30, 60);
+        let mut lines = vec![
+            self.order_field_span("Instrument", &input.value, true),
+            match &input.matched {
+                Some(inst_id) if inst_id.eq_ignore_ascii_case(input.value.trim_matches('-')) => {
+                    Line::from(Span::styled(
+                        format!("Match {inst_id} · Enter jump"),
+                        Style::default().fg(self.theme.accent),
+                    ))
+                }
+                Some(inst_id) => Line::from(Span::styled(
+                    format!("Match {inst_id} · Tab jump · Enter add"),
+                    Style::default().fg(self.theme.accent),
+                )),
+                None if input.value.is_empty() => Line::from(Span::styled(
+                    "Type a prefix to jump, or a new id to add",
+                    Style::default().fg(self.theme.muted),
+                )),
+                None => Line::from(Span::styled(
+                    "No match · Enter add",
+                    Style::default().fg(self.theme.muted),
+                )),
+            },
+            Line::from("Esc cancel · e.g. SOL-USDT-SWAP"),
+        ];
+        if let Some(err) = &input.error {
+            lines.push(Line::from(Span::styled(
+                err.as_str(),
+                Style::default().fg(self.theme.error),
+            )));
+        }
+        let popup_height = (lines.len() as u16 + 2).min(area.height);
         let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
         let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
         let popup = Rect::new(left, top, popup_width, popup_height);
-        let lines = vec![
-            Line::from(Span::styled(
-                "确定要退出交易终端？",
-                Style::default()
-                    .fg(Color::LightRed)
-                    .add_modifier(Modifier::BOLD),
-            )),
-            Line::from("Y/Enter 确认退出 · N/Esc 取消"),
-            Line::from("再次按 q/Q 也可确认 · Ctrl+C 立即退出"),
-        ];
         let paragraph = Paragraph::new(lines)
             .alignment(Alignment::Left)
-            .block(Block::bordered().title("确认退出"));
+            .block(Block::bordered().title("Go to / add instrument"));
         frame.render_widget(Clear, popup);
         frame.render_widget(paragraph, popup);
     }
 
-    fn render_loading_overlay(&self, frame: &mut Frame) {
-        if let Some(overlay) = &self.loading_overlay {
-            let area = frame.area();
-            if area.width < 24 || area.height < 5 {
-                return;
-            }
-            let popup_width = area.width.saturating_sub(10).min(60).max(30);
-            let popup_height = 6;
-            let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
-            let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
-            let popup = Rect::new(left, top, popup_width, popup_height);
-            let message = format!("{} {}", overlay.spinner(), overlay.message());
-            let lines = vec![
+    fn render_threshold_dialog(&self, frame: &mut Frame, area: Rect, input: &ThresholdInputState) {
+        if area.width < 20 || area.height < 6 {
+            return;
+        }
+        let popup_width = area.width.saturating_sub(10).clamp(30, 60);
+        let mut lines = vec![
+            self.order_field_span(
+                "Lower bound",
+                &input.lower,
+                input.active_field == ThresholdField::Lower,
+            ),
+            self.order_field_span(
+                "Upper bound",
+                &input.upper,
+                input.active_field == ThresholdField::Upper,
+            ),
+            self.order_field_span(
+                "Change %",
+                &input.change_pct,
+                input.active_field == ThresholdField::ChangePct,
+            ),
+            self.order_field_span(
+                &format!("ATR{DEFAULT_ATR_PERIOD} above"),
+                &input.atr,
+                input.active_field == ThresholdField::Atr,
+            ),
+            self.order_field_span(
+                "Cooldown s",
+                &input.cooldown,
+                input.active_field == ThresholdField::Cooldown,
+            ),
+        ];
+        if let Some(last) = self.latest_prices.get(&input.inst_id) {
+            lines.push(Line::from(Span::styled(
+                format!("Last {}", self.format_price_for(&input.inst_id, *last)),
+                Style::default().fg(self.theme.muted),
+            )));
+        }
+        lines.push(Line::from(
+            "Enter apply · Esc cancel · Tab switch field · empty = off (cooldown: global)",
+        ));
+        if let Some(err) = &input.error {
+            lines.push(Line::from(Span::styled(
+                err.as_str(),
+                Style::default().fg(self.theme.error),
+            )));
+        }
+        let popup_height = (lines.len() as u16 + 2).min(area.height);
+        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup = Rect::new(left, top, popup_width, popup_height);
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .block(Block::bordered().title(format!("Price alerts {}", input.inst_id)));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(paragraph, popup);
+    }
+
+    fn render_stats_view(&self, frame: &mut Frame) {
+        let area = frame.area();
+        let has_status = self.status_message.is_some() && area.height >= 10;
+        let constraints = if has_status {
+            vec![
+                Constraint::Length(4),
+                Constraint::Percentage(50),
+                Constraint::Min(4),
+                Constraint::Length(3),
+            ]
+        } else {
+            vec![
+                Constraint::Length(4),
+                Constraint::Percentage(50),
+                Constraint::Min(4),
+            ]
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(constraints)
+            .split(area);
+        let stats = pnl_stats::summarize(
+            &self.trade.logs,
+            self.stats_period,
+            self.timezone,
+            Utc::now().timestamp_millis(),
+        );
+        let total = &stats.total;
+        let header = vec![
+            Line::from(vec![
+                Span::styled(
+                    "盈亏统计",
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" · 区间 "),
+                Span::styled(
+                    self.stats_period.label(),
+                    Style::default()
+                        .fg(self.theme.secondary)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" · 净收益 "),
+                Span::styled(
+                    pnl_stats::format_pnl(total.net()),
+                    Style::default().fg(self.pnl_color(total.net())),
+                ),
+                Span::raw(format!(
+                    " · 成交 {} · 胜率 {}",
+                    total.fills,
+                    Self::win_rate_label(total)
+                )),
+            ]),
+            Line::from(format!(
+                "P/Tab 切换 今日/近 7 日/全部 · d 返回 · t 交易页面 · 基于最近 {} 条委托日志",
+                self.trade.logs.len()
+            )),
+        ];
+        frame.render_widget(Paragraph::new(header).block(Block::bordered()), chunks[0]);
+        self.render_stats_table(
+            frame,
+            chunks[1],
+            "按合约",
+            "合约",
+            stats.by_inst.iter().collect(),
+        );
+        self.render_stats_table(
+            frame,
+            chunks[2],
+            "按日",
+            "日期",
+            stats.by_day.iter().rev().collect(),
+        );
+        if has_status {
+            self.render_status(frame, chunks[3]);
+        } else if self.status_message.is_some() {
+            self.render_status(frame, area);
+        }
+    }
+
+    fn render_stats_table(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        title: &str,
+        key_label: &str,
+        rows: Vec<(&String, &PnlSummary)>,
+    ) {
+        let mut lines = Vec::new();
+        if rows.is_empty() {
+            lines.push(Line::from("该区间内无成交记录"));
+        } else {
+            lines.push(Line::from(format_columns(&[
+                (key_label, ColumnAlign::Left, 16),
+                ("已实现盈亏", ColumnAlign::Right, 14),
+                ("手续费", ColumnAlign::Right, 12),
+                ("净收益", ColumnAlign::Right, 14),
+                ("成交", ColumnAlign::Right, 6),
+                ("平仓", ColumnAlign::Right, 6),
+                ("胜率", ColumnAlign::Right, 8),
+            ])));
+            let visible = area.height.saturating_sub(3) as usize;
+            for (key, summary) in rows.into_iter().take(visible) {
+                let row = format_columns(&[
+                    (key.as_str(), ColumnAlign::Left, 16),
+                    (
+                        pnl_stats::format_pnl(summary.realized_pnl).as_str(),
+                        ColumnAlign::Right,
+                        14,
+                    ),
+                    (
+                        pnl_stats::format_pnl(summary.fees).as_str(),
+                        ColumnAlign::Right,
+                        12,
+                    ),
+                    (
+                        pnl_stats::format_pnl(summary.net()).as_str(),
+                        ColumnAlign::Right,
+                        14,
+                    ),
+                    (summary.fills.to_string().as_str(), ColumnAlign::Right, 6),
+                    (
+                        summary.closing_trades.to_string().as_str(),
+                        ColumnAlign::Right,
+                        6,
+                    ),
+                    (
+                        Self::win_rate_label(summary).as_str(),
+                        ColumnAlign::Right,
+                        8,
+                    ),
+                ]);
+                lines.push(Line::styled(
+                    row,
+                    Style::default().fg(self.pnl_color(summary.net())),
+                ));
+            }
+        }
+        let paragraph = Paragraph::new(lines).block(Block::bordered().title(title.to_string()));
+        frame.render_widget(paragraph, area);
+    }
+
+    fn win_rate_label(summary: &PnlSummary) -> String {
+        summary
+            .win_rate()
+            .map(|rate| format!("{:.1}%", rate * 100.0))
+            .unwrap_or_else(|| "--".to_string())
+    }
+
+    fn pnl_color(&self, value: f64) -> Color {
+        if value > 0.0 {
+            self.theme.success
+        } else if value < 0.0 {
+            self.theme.error
+        } else {
+            self.theme.text
+        }
+    }
+
+    // 交易页顶部常驻的账户概览：总权益、未实现盈亏、持仓数与保证金占用
+    fn account_summary_line(&self) -> Line<'static> {
+        let muted = Style::default().fg(self.theme.muted);
+        let equity = self.trade.balance.total_equity;
+        let mut upnl = None;
+        let mut position_count = 0;
+        let mut used_margin = 0.0;
+        for position in self.closable_positions() {
+            position_count += 1;
+            used_margin += position.imr;
+            if let Some(pnl) = self.position_pnl(position) {
+                upnl = Some(upnl.unwrap_or(0.0) + pnl);
+            }
+        }
+        let mut spans = vec![
+            Span::styled("总权益 ", muted),
+            Span::styled(
+                equity
+                    .map(|value| format!("{} USDT", TradeState::format_balance_amount(value)))
+                    .unwrap_or_else(|| "--".to_string()),
+                Style::default()
+                    .fg(self.theme.text)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(" · 未实现盈亏 ", muted),
+        ];
+        spans.push(match upnl {
+            Some(value) => Span::styled(
+                format!(
+                    "{}{}",
+                    if value > 0.0 { "+" } else { "" },
+                    TradeState::format_balance_amount(value)
+                ),
+                Style::default().fg(self.pnl_color(value)),
+            ),
+            None => Span::raw("--"),
+        });
+        spans.push(Span::styled(" · 持仓 ", muted));
+        spans.push(Span::raw(position_count.to_string()));
+        spans.push(Span::styled(" · 保证金占用 ", muted));
+        let usage = equity
+            .filter(|equity| *equity > 0.0)
+            .map(|equity| used_margin / equity * 100.0);
+        spans.push(match usage {
+            Some(ratio) => Span::raw(format!("{ratio:.2}%")),
+            None => Span::raw("--"),
+        });
+        Line::from(spans)
+    }
+
+    fn render_leverage_dialog(&self, frame: &mut Frame, area: Rect, input: &LeverageInputState) {
+        if area.width < 20 || area.height < 5 {
+            return;
+        }
+        let popup_width = area.width.saturating_sub(10).clamp(30, 50);
+        let mut lines = vec![self.order_field_span("杠杆", &input.value, true)];
+        let mode_label = input.margin_mode.map(|mode| mode.label()).unwrap_or("默认");
+        let side_label = input.pos_side.as_deref().unwrap_or("-");
+        lines.push(Line::from(Span::styled(
+            format!("保证金模式 {mode_label} · 持仓方向 {side_label}"),
+            Style::default().fg(self.theme.muted),
+        )));
+        lines.push(Line::from("Enter 确认 · Esc 取消"));
+        if let Some(err) = &input.error {
+            lines.push(Line::from(Span::styled(
+                err.as_str(),
+                Style::default().fg(self.theme.error),
+            )));
+        }
+        let popup_height = (lines.len() as u16 + 2).min(area.height);
+        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup = Rect::new(left, top, popup_width, popup_height);
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .block(Block::bordered().title(format!("调整杠杆 {}", input.inst_id)));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(paragraph, popup);
+    }
+
+    fn render_trade_view(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+        let has_status = self.status_message.is_some() && area.height >= 6;
+        let (main_area, status_area) = if has_status {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(5), Constraint::Length(3)])
+                .split(area);
+            (chunks[0], Some(chunks[1]))
+        } else {
+            (area, None)
+        };
+        let main_area = if self.trade.trading_enabled() && main_area.height > 5 {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(4)])
+                .split(main_area);
+            frame.render_widget(Paragraph::new(self.account_summary_line()), chunks[0]);
+            chunks[1]
+        } else {
+            main_area
+        };
+        self.render_trade_panel(frame, main_area);
+        if let Some(status_area) = status_area {
+            self.render_status(frame, status_area);
+        } else if self.status_message.is_some() {
+            self.render_status(frame, main_area);
+        }
+        if let Some(input) = &self.trade.input {
+            self.render_order_dialog(frame, main_area, input);
+        }
+        if let Some(input) = &self.leverage_input {
+            self.render_leverage_dialog(frame, main_area, input);
+        }
+        if let Some(input) = &self.instrument_input {
+            self.render_instrument_dialog(frame, main_area, input);
+        }
+        if let Some(detail) = self.trade.ai_detail.clone() {
+            self.render_ai_detail(frame, main_area, &detail);
+        } else if let Some(detail) = &self.trade.log_detail {
+            self.render_log_detail(frame, main_area, detail);
+        }
+    }
+
+    fn render_help_overlay(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+        if area.width < 36 || area.height < 10 {
+            return;
+        }
+        let popup_width = area.width.saturating_sub(8).clamp(36, 72);
+        let popup_height = area.height.saturating_sub(4).clamp(10, 40);
+        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup = Rect::new(left, top, popup_width, popup_height);
+        let mut lines = Vec::new();
+        for (idx, group) in keymap::KEY_BINDINGS.iter().enumerate() {
+            if idx > 0 {
+                lines.push(Line::from(""));
+            }
+            lines.push(Line::from(Span::styled(
+                format!("[{}]", group.title),
+                Style::default()
+                    .fg(self.theme.info)
+                    .add_modifier(Modifier::BOLD),
+            )));
+            for binding in group.bindings {
+                let mut spans = vec![Span::styled(
+                    format!("{:<14}", keymap::keys_label(binding.keys)),
+                    Style::default().fg(self.theme.accent),
+                )];
+                if let Some(scope) = binding.scope {
+                    spans.push(Span::styled(
+                        format!("[{scope}] "),
+                        Style::default().fg(self.theme.muted),
+                    ));
+                }
+                spans.push(Span::raw(binding.action));
+                lines.push(Line::from(spans));
+            }
+        }
+        let content_height = popup_height.saturating_sub(2);
+        let content_width = popup_width.saturating_sub(2).max(1);
+        let total_rows = Self::wrapped_line_count(&lines, content_width);
+        let Some(state) = self.help_overlay.as_mut() else {
+            return;
+        };
+        state.view_height = content_height;
+        state.total_rows = total_rows;
+        state.scroll = state.scroll.min(state.max_scroll());
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .scroll((state.scroll, 0))
+            .block(
+                Block::bordered()
+                    .title("快捷键 · ?/Esc 关闭 · ↑↓/j k 滚动 · PgUp/PgDn 翻页")
+                    .border_style(Style::default().fg(self.theme.secondary)),
+            );
+        frame.render_widget(Clear, popup);
+        frame.render_widget(paragraph, popup);
+    }
+
+    fn render_exit_confirmation(&self, frame: &mut Frame) {
+        let area = frame.area();
+        if area.width < 24 || area.height < 5 {
+            return;
+        }
+        let popup_width = area.width.saturating_sub(20).min(50).max(28);
+        let popup_height = 6;
+        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup = Rect::new(left, top, popup_width, popup_height);
+        let lines = vec![
+            Line::from(Span::styled(
+                "确定要退出交易终端？",
+                Style::default()
+                    .fg(self.theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from("Y/Enter 确认退出 · N/Esc 取消"),
+            Line::from("再次按 q/Q 也可确认 · Ctrl+C 立即退出"),
+        ];
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .block(Block::bordered().title("确认退出"));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(paragraph, popup);
+    }
+
+    fn render_ai_proposal(&self, frame: &mut Frame, proposal: &AiDecisionProposal) {
+        let area = frame.area();
+        if area.width < 24 || area.height < 6 {
+            return;
+        }
+        let mut lines = vec![Line::from(Span::styled(
+            format!("{} 请求执行以下操作：", proposal.operator),
+            Style::default()
+                .fg(self.theme.emphasis)
+                .add_modifier(Modifier::BOLD),
+        ))];
+        lines.extend(
+            proposal
+                .operations
+                .iter()
+                .map(|operation| Line::from(format!("· {operation}"))),
+        );
+        let deadline = self
+            .timezone
+            .format_timestamp(proposal.expires_at_ms, "%H:%M:%S")
+            .unwrap_or_default();
+        lines.push(Line::from(Span::styled(
+            format!("{deadline} 前未确认将自动放弃"),
+            Style::default().fg(self.theme.muted),
+        )));
+        lines.push(Line::from("Y/Enter 确认执行 · N/Esc 拒绝"));
+        let popup_width = area.width.saturating_sub(10).clamp(28, 80);
+        let popup_height = (lines.len() as u16 + 2).min(area.height);
+        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup = Rect::new(left, top, popup_width, popup_height);
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title("确认 AI 决策"));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(paragraph, popup);
+    }
+
+    fn render_close_all_confirmation(&self, frame: &mut Frame) {
+        let area = frame.area();
+        if area.width < 24 || area.height < 5 {
+            return;
+        }
+        let count = self.closable_positions().count();
+        let popup_width = area.width.saturating_sub(20).clamp(28, 50);
+        let popup_height = 6;
+        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup = Rect::new(left, top, popup_width, popup_height);
+        let lines = vec![
+            Line::from(Span::styled(
+                format!("确定以市价平掉全部 {count} 个持仓？"),
+                Style::default()
+                    .fg(self.theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from("每个持仓单独提交只减仓市价单"),
+            Line::from("Y/Enter 确认平仓 · N/Esc 取消"),
+        ];
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .block(Block::bordered().title("一键平仓"));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(paragraph, popup);
+    }
+
+    fn render_reverse_confirmation(&self, frame: &mut Frame, position: &PositionInfo) {
+        let area = frame.area();
+        if area.width < 24 || area.height < 5 {
+            return;
+        }
+        let popup_width = area.width.saturating_sub(20).clamp(28, 56);
+        let popup_height = 6;
+        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup = Rect::new(left, top, popup_width, popup_height);
+        let size_label = self.format_contract_size(&position.inst_id, position.size.abs());
+        let from_label = Self::pos_side_label(position.pos_side.as_deref());
+        let hedge = Self::is_hedge_position(position);
+        let lines = vec![
+            Line::from(Span::styled(
+                format!(
+                    "确定将 {} {} {} 反手？",
+                    position.inst_id, from_label, size_label
+                ),
+                Style::default()
+                    .fg(self.theme.error)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(if hedge {
+                "先市价平仓，再按相同数量反向市价开仓"
+            } else {
+                "以两倍持仓数量提交一笔反向市价单"
+            }),
+            Line::from("Y/Enter 确认反手 · N/Esc 取消"),
+        ];
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .block(Block::bordered().title("反手"));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(paragraph, popup);
+    }
+
+    fn render_order_confirmation(&self, frame: &mut Frame, staged: &StagedOrder) {
+        let area = frame.area();
+        if area.width < 24 || area.height < 5 {
+            return;
+        }
+        let request = &staged.request;
+        let inst_id = request.inst_id.as_str();
+        let spot = okx::is_spot_inst(inst_id);
+        let ct_val = self
+            .trade
+            .markets
+            .get(inst_id)
+            .map(|market| market.ct_val)
+            .filter(|ct_val| *ct_val > 0.0)
+            .unwrap_or(1.0);
+        let notional = request.price * request.size * ct_val;
+        let number = |value: f64| Self::trim_formatted_number(format!("{value:.4}"));
+        let side_color = match request.side {
+            TradeSide::Buy => self.theme.success,
+            TradeSide::Sell => self.theme.error,
+        };
+        let mut size_label = format!("数量 {}", self.format_contract_size(inst_id, request.size));
+        if !spot {
+            let (other, unit) = match self.size_unit {
+                SizeUnit::Coin => (request.size, SizeUnit::Contracts),
+                SizeUnit::Contracts => (request.size * ct_val, SizeUnit::Coin),
+            };
+            size_label.push_str(&format!(
+                " {} ({} {})",
+                self.size_unit.label(),
+                Self::trim_formatted_number(format!("{other:.8}")),
+                unit.label()
+            ));
+        }
+        let price_label = if request.ord_type == Some(TradeOrderType::Market) {
+            format!("市价 ≈ {}", self.format_price_for(inst_id, request.price))
+        } else {
+            format!("价格 {}", self.format_price_for(inst_id, request.price))
+        };
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!(
+                    "{}{} {}",
+                    staged.intent.action_label(),
+                    Self::side_label(request.side),
+                    inst_id
+                ),
+                Style::default().fg(side_color).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(format!("{size_label} · {price_label}")),
+        ];
+        if !spot {
+            let leverage = request
+                .leverage
+                .map(|lever| format!("{}x", Self::trim_formatted_number(format!("{lever:.2}"))))
+                .unwrap_or_else(|| "--".to_string());
+            let mode = request.margin_mode.map(|mode| mode.label()).unwrap_or("--");
+            lines.push(Line::from(format!("杠杆 {leverage} · {mode}")));
+        }
+        let margin = if spot {
+            Some(notional)
+        } else {
+            request
+                .leverage
+                .filter(|lever| *lever > 0.0)
+                .map(|lever| notional / lever)
+        };
+        lines.push(Line::from(format!(
+            "名义 {} · {} {}",
+            number(notional),
+            if spot { "占用" } else { "保证金" },
+            margin.map(number).unwrap_or_else(|| "--".to_string())
+        )));
+        lines.push(Line::from(Span::styled(
+            self.liquidation_impact(request, margin),
+            Style::default().fg(self.theme.emphasis),
+        )));
+        lines.push(Line::from("Enter 确认提交 · Esc 返回修改"));
+        let popup_width = area.width.saturating_sub(20).clamp(28, 64);
+        let popup_height = (lines.len() as u16 + 2).min(area.height);
+        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup = Rect::new(left, top, popup_width, popup_height);
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered().title("确认委托"));
+        frame.render_widget(Clear, popup);
+        frame.render_widget(paragraph, popup);
+    }
+
+    // 逐仓按 入场价 × (1 ∓ 1/杠杆) 粗估强平价；全仓显示占用可用余额的比例
+    fn liquidation_impact(&self, request: &TradeRequest, margin: Option<f64>) -> String {
+        if okx::is_spot_inst(&request.inst_id) {
+            return "现货交易，无强平风险".to_string();
+        }
+        if request.reduce_only {
+            return "只减仓，不增加风险敞口".to_string();
+        }
+        match (
+            request.margin_mode,
+            request.leverage.filter(|lever| *lever > 0.0),
+        ) {
+            (Some(MarginMode::Isolated), Some(lever)) => {
+                let liq = match request.side {
+                    TradeSide::Buy => request.price * (1.0 - 1.0 / lever),
+                    TradeSide::Sell => request.price * (1.0 + 1.0 / lever),
+                };
+                format!(
+                    "预估强平价 ≈ {}（未计维持保证金与手续费）",
+                    self.format_price_for(&request.inst_id, liq.max(0.0))
+                )
+            }
+            _ => {
+                let quote = request.inst_id.split('-').nth(1).unwrap_or_default();
+                let available = self
+                    .trade
+                    .balance
+                    .delta
+                    .iter()
+                    .find(|delta| delta.currency.eq_ignore_ascii_case(quote))
+                    .and_then(|delta| delta.available)
+                    .filter(|available| *available > 0.0);
+                match (margin, available) {
+                    (Some(margin), Some(available)) => format!(
+                        "全仓占用可用 {quote} 的 {:.1}%，强平价取决于账户整体权益",
+                        margin / available * 100.0
+                    ),
+                    _ => "全仓强平价取决于账户整体权益".to_string(),
+                }
+            }
+        }
+    }
+
+    fn render_loading_overlay(&self, frame: &mut Frame) {
+        if let Some(overlay) = &self.loading_overlay {
+            let area = frame.area();
+            if area.width < 24 || area.height < 5 {
+                return;
+            }
+            let popup_width = area.width.saturating_sub(10).min(60).max(30);
+            let popup_height = 6;
+            let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+            let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+            let popup = Rect::new(left, top, popup_width, popup_height);
+            let message = format!("{} {}", overlay.spinner(), overlay.message());
+            let lines = vec![
                 Line::from(Span::styled(
                     message,
                     Style::default()
-                        .fg(Color::Yellow)
+                        .fg(self.theme.emphasis)
                         .add_modifier(Modifier::BOLD),
                 )),
                 Line::from("正在同步 OKX 合约信息，加载完成前仅支持退出 (Q/Esc)"),
@@ -1401,13 +3286,12 @@ impl TuiApp {
     }
 
     fn render_trade_panel(&mut self, frame: &mut Frame, area: Rect) {
+        self.trade.hit_areas.clear();
         if area.height < 4 || area.width < 20 {
             return;
         }
         let instruction_lines = self.trade_instruction_lines();
-        let balance_lines = self.trade.balance_lines();
-        let header_height =
-            Self::trade_header_height(instruction_lines.len() + balance_lines.len());
+        let header_height = Self::trade_header_height(instruction_lines.len());
         if area.height < header_height {
             return;
         }
@@ -1421,7 +3305,7 @@ impl TuiApp {
                     Constraint::Min(4),
                 ])
                 .split(area);
-            self.render_trade_header(frame, chunks[0], &instruction_lines, &balance_lines);
+            self.render_trade_header(frame, chunks[0], &instruction_lines);
             self.render_account_snapshot(frame, chunks[1]);
             self.render_trade_activity(frame, chunks[2]);
         } else {
@@ -1429,7 +3313,7 @@ impl TuiApp {
                 .direction(Direction::Vertical)
                 .constraints([Constraint::Length(header_height), Constraint::Min(4)])
                 .split(area);
-            self.render_trade_header(frame, chunks[0], &instruction_lines, &balance_lines);
+            self.render_trade_header(frame, chunks[0], &instruction_lines);
             self.render_trade_activity(frame, chunks[1]);
         }
     }
@@ -1457,17 +3341,12 @@ impl TuiApp {
     }
 
     fn focus_border_style(&self, focus: TradeFocus) -> Style {
-        if self.trade.focus == focus {
-            Style::default()
-                .fg(Color::LightCyan)
-                .add_modifier(Modifier::BOLD)
-        } else {
-            Style::default()
-        }
+        self.theme.border_style(self.trade.focus == focus)
     }
 
     fn render_positions_panel(&mut self, frame: &mut Frame, area: Rect) {
-        let block = self.section_block("Positions", TradeFocus::Positions);
+        let title = format!("Positions · {}", self.trade.scope_label());
+        let block = self.section_block(&title, TradeFocus::Positions);
         if area.height < 3 {
             frame.render_widget(block, area);
             self.trade.set_position_view_height(1);
@@ -1481,6 +3360,7 @@ impl TuiApp {
             .set_position_view_height(page_height.min(u16::MAX as usize) as u16);
         let total = self.trade.positions.len();
         let display_len = total.min(MAX_POSITION_RECORDS);
+        let mut hit_rows = Vec::new();
         if display_len == 0 {
             lines.push(Line::from("无持仓"));
         } else if list_visible == 0 {
@@ -1498,65 +3378,241 @@ impl TuiApp {
                 ("保证金", ColumnAlign::Right, 12),
                 ("盈亏", ColumnAlign::Right, 12),
                 ("盈亏%", ColumnAlign::Right, 10),
+                ("预估资金费", ColumnAlign::Right, 12),
             ])));
             let selected_idx = clamp_index(self.trade.selected_position_idx, display_len);
-            let (start, end) = visible_range(display_len, list_visible, selected_idx);
-            for (idx, position) in self
-                .trade
-                .positions
+            let positions = &self.trade.positions;
+            let order = self.trade.position_display_order();
+            let mut rows = Vec::new();
+            if self.trade.net_positions {
+                // 同一合约多个方向时折叠为一行净持仓，选中项所在的合约展开明细
+                let mut group_start = 0;
+                while group_start < order.len() {
+                    let inst_id = &positions[order[group_start]].inst_id;
+                    let group_len = order[group_start..]
+                        .iter()
+                        .take_while(|idx| positions[**idx].inst_id == *inst_id)
+                        .count();
+                    let group = &order[group_start..group_start + group_len];
+                    group_start += group_len;
+                    if let [idx] = group {
+                        let label = (idx + 1).to_string();
+                        rows.push((
+                            self.position_columns(label, &positions[*idx]),
+                            *idx == selected_idx,
+                            *idx,
+                        ));
+                        continue;
+                    }
+                    let legs: Vec<&PositionInfo> =
+                        group.iter().map(|idx| &positions[*idx]).collect();
+                    // 点击汇总行选中该合约的第一条明细
+                    rows.push((self.net_position_columns(&legs), false, group[0]));
+                    if group.contains(&selected_idx) {
+                        for idx in group {
+                            let label = format!("└{}", idx + 1);
+                            rows.push((
+                                self.position_columns(label, &positions[*idx]),
+                                *idx == selected_idx,
+                                *idx,
+                            ));
+                        }
+                    }
+                }
+            } else {
+                for idx in order {
+                    let label = (idx + 1).to_string();
+                    rows.push((
+                        self.position_columns(label, &positions[idx]),
+                        idx == selected_idx,
+                        idx,
+                    ));
+                }
+            }
+            let selected_row = rows
                 .iter()
-                .take(display_len)
-                .enumerate()
-                .skip(start)
-                .take(end.saturating_sub(start))
+                .position(|(_, selected, _)| *selected)
+                .unwrap_or(0);
+            let (start, end) = visible_range(rows.len(), list_visible, selected_row);
+            for (columns, selected, idx) in
+                rows.into_iter().skip(start).take(end.saturating_sub(start))
             {
-                let side_label = Self::pos_side_label(position.pos_side.as_deref());
-                let avg_label = position
-                    .avg_px
-                    .map(|value| self.format_price_for(&position.inst_id, value))
-                    .unwrap_or_else(|| "--".to_string());
-                let mark_label = self
-                    .latest_prices
-                    .get(&position.inst_id)
-                    .copied()
-                    .map(|value| self.format_price_for(&position.inst_id, value))
-                    .unwrap_or_else(|| "--".to_string());
-                let size_label = self.format_contract_size(&position.inst_id, position.size);
-                let lever_label = Self::leverage_label(position.lever);
-                let imr_label = Self::format_imr(position.imr);
-                let pnl_value = self.position_pnl(position);
-                let pnl_label = pnl_value
-                    .map(Self::format_pnl)
-                    .unwrap_or_else(|| "--".to_string());
-                let pnl_ratio_label = self
-                    .position_pnl_ratio(position)
-                    .map(Self::format_pnl_ratio)
-                    .unwrap_or_else(|| "--".to_string());
-                let ordinal_label = format!("{}", idx + 1);
-                let time_label = self.snapshot_time_label(position.create_time);
-                let row = format_columns(&[
-                    (ordinal_label.as_str(), ColumnAlign::Right, 4),
-                    (time_label.as_str(), ColumnAlign::Left, 10),
-                    (position.inst_id.as_str(), ColumnAlign::Left, 14),
-                    (side_label, ColumnAlign::Left, 4),
-                    (size_label.as_str(), ColumnAlign::Right, 12),
-                    (avg_label.as_str(), ColumnAlign::Right, 12),
-                    (mark_label.as_str(), ColumnAlign::Right, 12),
-                    (lever_label.as_str(), ColumnAlign::Right, 8),
-                    (imr_label.as_str(), ColumnAlign::Right, 12),
-                    (pnl_label.as_str(), ColumnAlign::Right, 12),
-                    (pnl_ratio_label.as_str(), ColumnAlign::Right, 10),
-                ]);
-                let selected = idx == selected_idx && self.trade.focus == TradeFocus::Positions;
-                lines.push(Line::styled(row, row_style(selected)));
+                let selected = selected && self.trade.focus == TradeFocus::Positions;
+                lines.push(self.render_column_spans(columns, selected));
+                hit_rows.push(Some(idx));
             }
         }
+        self.trade
+            .record_hit_area(TradeFocus::Positions, area, hit_rows);
         let paragraph = Paragraph::new(lines)
             .alignment(Alignment::Left)
             .block(block);
         frame.render_widget(paragraph, area);
     }
 
+    fn position_columns(
+        &self,
+        index_label: String,
+        position: &PositionInfo,
+    ) -> Vec<(String, ColumnAlign, usize, Option<Color>)> {
+        let side_label = Self::pos_side_label(position.pos_side.as_deref());
+        let avg_label = position
+            .avg_px
+            .map(|value| self.cached_price_label(&position.inst_id, value))
+            .unwrap_or_else(|| Rc::from("--"));
+        let mark_label = self
+            .latest_prices
+            .get(&position.inst_id)
+            .copied()
+            .map(|value| self.cached_price_label(&position.inst_id, value))
+            .unwrap_or_else(|| Rc::from("--"));
+        let size_label = self.cached_size_label(&position.inst_id, position.size);
+        let lever_label = match position.mgn_mode {
+            Some(mode) => {
+                format!("{} {}", mode.label(), Self::leverage_label(position.lever))
+            }
+            None => Self::leverage_label(position.lever),
+        };
+        let imr_label = Self::format_imr(position.imr);
+        let pnl_value = self.position_pnl(position);
+        let pnl_label = pnl_value
+            .map(pnl_stats::format_pnl)
+            .unwrap_or_else(|| "--".to_string());
+        let pnl_ratio = self.position_pnl_ratio(position);
+        let pnl_ratio_label = pnl_ratio
+            .map(Self::format_pnl_ratio)
+            .unwrap_or_else(|| "--".to_string());
+        let funding_value = self.position_funding_estimate(position);
+        let funding_label = funding_value
+            .map(pnl_stats::format_pnl)
+            .unwrap_or_else(|| "--".to_string());
+        let signed_color = |value: Option<f64>| {
+            value
+                .filter(|value| *value != 0.0)
+                .map(|value| self.pnl_color(value))
+        };
+        let columns = vec![
+            (index_label, ColumnAlign::Right, 4, None),
+            (
+                self.snapshot_time_label(position.create_time),
+                ColumnAlign::Left,
+                10,
+                None,
+            ),
+            (position.inst_id.clone(), ColumnAlign::Left, 14, None),
+            (side_label.to_string(), ColumnAlign::Left, 4, None),
+            (size_label.to_string(), ColumnAlign::Right, 12, None),
+            (avg_label.to_string(), ColumnAlign::Right, 12, None),
+            (mark_label.to_string(), ColumnAlign::Right, 12, None),
+            (lever_label, ColumnAlign::Right, 8, None),
+            (imr_label, ColumnAlign::Right, 12, None),
+            (pnl_label, ColumnAlign::Right, 12, signed_color(pnl_value)),
+            (
+                pnl_ratio_label,
+                ColumnAlign::Right,
+                10,
+                signed_color(pnl_ratio),
+            ),
+            (
+                funding_label,
+                ColumnAlign::Right,
+                12,
+                signed_color(funding_value),
+            ),
+        ];
+        columns
+    }
+
+    // 按 signed_position_size 轧差；均价按各方向持仓数量加权
+    fn net_position_columns(
+        &self,
+        legs: &[&PositionInfo],
+    ) -> Vec<(String, ColumnAlign, usize, Option<Color>)> {
+        let inst_id = legs[0].inst_id.as_str();
+        let net_size: f64 = legs.iter().map(|leg| Self::signed_position_size(leg)).sum();
+        let (weight, weighted_px) = legs
+            .iter()
+            .filter_map(|leg| leg.avg_px.map(|avg| (leg.size.abs(), avg * leg.size.abs())))
+            .fold((0.0, 0.0), |(weight, total), (size, value)| {
+                (weight + size, total + value)
+            });
+        let avg_label = if weight > 0.0 {
+            self.format_price_for(inst_id, weighted_px / weight)
+        } else {
+            "--".to_string()
+        };
+        let side_label = if net_size > 0.0 {
+            "净多"
+        } else if net_size < 0.0 {
+            "净空"
+        } else {
+            "持平"
+        };
+        let mark_label = self
+            .latest_prices
+            .get(inst_id)
+            .map(|value| self.format_price_for(inst_id, *value))
+            .unwrap_or_else(|| "--".to_string());
+        let imr: f64 = legs.iter().map(|leg| leg.imr).sum();
+        let pnl_value: Option<f64> = legs.iter().map(|leg| self.position_pnl(leg)).sum();
+        let pnl_ratio = pnl_value.and_then(|pnl| Self::net_pnl_ratio(legs, pnl));
+        let funding_value: Option<f64> = legs
+            .iter()
+            .map(|leg| self.position_funding_estimate(leg))
+            .sum();
+        let signed_color = |value: Option<f64>| {
+            value
+                .filter(|value| *value != 0.0)
+                .map(|value| self.pnl_color(value))
+        };
+        let optional = |value: Option<f64>, format: fn(f64) -> String| {
+            value.map(format).unwrap_or_else(|| "--".to_string())
+        };
+        vec![
+            ("Σ".to_string(), ColumnAlign::Right, 4, None),
+            (format!("{} 笔", legs.len()), ColumnAlign::Left, 10, None),
+            (inst_id.to_string(), ColumnAlign::Left, 14, None),
+            (side_label.to_string(), ColumnAlign::Left, 4, None),
+            (
+                self.cached_size_label(inst_id, net_size.abs()).to_string(),
+                ColumnAlign::Right,
+                12,
+                None,
+            ),
+            (avg_label, ColumnAlign::Right, 12, None),
+            (mark_label, ColumnAlign::Right, 12, None),
+            ("--".to_string(), ColumnAlign::Right, 8, None),
+            (Self::format_imr(imr), ColumnAlign::Right, 12, None),
+            (
+                optional(pnl_value, pnl_stats::format_pnl),
+                ColumnAlign::Right,
+                12,
+                signed_color(pnl_value),
+            ),
+            (
+                optional(pnl_ratio, Self::format_pnl_ratio),
+                ColumnAlign::Right,
+                10,
+                signed_color(pnl_ratio),
+            ),
+            (
+                optional(funding_value, pnl_stats::format_pnl),
+                ColumnAlign::Right,
+                12,
+                signed_color(funding_value),
+            ),
+        ]
+    }
+
+    // 与交易所 uplRatio 口径一致：Σ未实现盈亏 / Σ占用保证金，任一腿保证金未知时不计算
+    fn net_pnl_ratio(legs: &[&PositionInfo], pnl: f64) -> Option<f64> {
+        if legs.iter().any(|leg| leg.imr <= 0.0) {
+            return None;
+        }
+        let imr: f64 = legs.iter().map(|leg| leg.imr).sum();
+        Some(pnl / imr)
+    }
+
     fn position_pnl(&self, position: &PositionInfo) -> Option<f64> {
         if let Some(upl) = position.upl {
             return Some(upl);
@@ -1581,6 +3637,24 @@ impl TuiApp {
         }
     }
 
+    // 下次结算的资金费：费率为正时多头支付、空头收取，正数表示收取
+    fn position_funding_estimate(&self, position: &PositionInfo) -> Option<f64> {
+        let rate = self.funding_rates.get(&position.inst_id)?.rate;
+        let price = self
+            .latest_prices
+            .get(&position.inst_id)
+            .copied()
+            .or(position.avg_px)?;
+        let ct_val = self
+            .trade
+            .markets
+            .get(&position.inst_id)
+            .map(|market| market.ct_val)
+            .filter(|ct_val| *ct_val > 0.0)?;
+        let signed_notional = Self::signed_position_size(position) * ct_val * price;
+        Some(-signed_notional * rate)
+    }
+
     fn signed_position_size(position: &PositionInfo) -> f64 {
         match position.pos_side.as_deref() {
             Some("long") => position.size.abs(),
@@ -1590,7 +3664,8 @@ impl TuiApp {
     }
 
     fn render_open_orders_panel(&mut self, frame: &mut Frame, area: Rect) {
-        let block = self.section_block("Open Orders", TradeFocus::Orders);
+        let title = format!("Open Orders · {}", self.trade.scope_label());
+        let block = self.section_block(&title, TradeFocus::Orders);
         if area.height < 3 {
             frame.render_widget(block, area);
             self.trade.set_order_view_height(1);
@@ -1604,6 +3679,7 @@ impl TuiApp {
             .set_order_view_height(page_height.min(u16::MAX as usize) as u16);
         let total = self.trade.open_orders.len();
         let display_len = total.min(MAX_ORDER_RECORDS);
+        let mut hit_rows = Vec::new();
         if display_len == 0 {
             lines.push(Line::from("无挂单"));
         } else if list_visible == 0 {
@@ -1616,6 +3692,7 @@ impl TuiApp {
                 ("方向", ColumnAlign::Left, 10),
                 ("类型", ColumnAlign::Left, 10),
                 ("数量", ColumnAlign::Right, 10),
+                ("已成交", ColumnAlign::Right, 14),
                 ("价格", ColumnAlign::Right, 10),
                 ("杠杆", ColumnAlign::Right, 8),
                 ("状态", ColumnAlign::Left, 8),
@@ -1634,22 +3711,32 @@ impl TuiApp {
             {
                 let side_label = Self::order_side_label(&order.side, order.pos_side.as_deref());
                 let intent_label = self.order_intent_label(order);
-                let price_label = if let Some(trigger) = order.trigger_price {
-                    let trigger_text = self.format_price_for(&order.inst_id, trigger);
+                let price_label = if let Some(callback) = order.callback {
+                    match order.trigger_price {
+                        Some(trigger) => Rc::from(format!(
+                            "{}/回调{}",
+                            self.cached_price_label(&order.inst_id, trigger),
+                            callback.label()
+                        )),
+                        None => Rc::from(format!("回调{}", callback.label())),
+                    }
+                } else if let Some(trigger) = order.trigger_price {
+                    let trigger_text = self.cached_price_label(&order.inst_id, trigger);
                     match order.price {
                         Some(ord_px) if (ord_px - trigger).abs() > f64::EPSILON => {
-                            let ord_text = self.format_price_for(&order.inst_id, ord_px);
-                            format!("{}->{}", trigger_text, ord_text)
+                            let ord_text = self.cached_price_label(&order.inst_id, ord_px);
+                            Rc::from(format!("{}->{}", trigger_text, ord_text))
                         }
                         _ => trigger_text,
                     }
                 } else {
                     order
                         .price
-                        .map(|value| self.format_price_for(&order.inst_id, value))
-                        .unwrap_or_else(|| "--".to_string())
+                        .map(|value| self.cached_price_label(&order.inst_id, value))
+                        .unwrap_or_else(|| Rc::from("--"))
                 };
-                let size_label = self.format_contract_size(&order.inst_id, order.size);
+                let size_label = self.cached_size_label(&order.inst_id, order.size);
+                let fill_label = self.partial_fill_label(order);
                 let ord_label = Self::short_order_id(&order.ord_id);
                 let lever_label = Self::leverage_label(order.lever);
                 let ordinal_label = format!("{}", idx + 1);
@@ -1660,29 +3747,247 @@ impl TuiApp {
                     (order.inst_id.as_str(), ColumnAlign::Left, 14),
                     (side_label.as_str(), ColumnAlign::Left, 10),
                     (intent_label, ColumnAlign::Left, 10),
-                    (size_label.as_str(), ColumnAlign::Right, 10),
-                    (price_label.as_str(), ColumnAlign::Right, 10),
+                    (&size_label, ColumnAlign::Right, 10),
+                    (fill_label.as_str(), ColumnAlign::Right, 14),
+                    (&price_label, ColumnAlign::Right, 10),
                     (lever_label.as_str(), ColumnAlign::Right, 8),
                     (order.state.as_str(), ColumnAlign::Left, 8),
                     (ord_label.as_str(), ColumnAlign::Left, 12),
                 ]);
                 let selected = idx == selected_idx && self.trade.focus == TradeFocus::Orders;
-                lines.push(Line::styled(row, row_style(selected)));
+                lines.push(Line::styled(row, self.theme.row_style(selected)));
             }
+            hit_rows = (start..end).map(Some).collect();
         }
+        self.trade
+            .record_hit_area(TradeFocus::Orders, area, hit_rows);
         let paragraph = Paragraph::new(lines)
             .alignment(Alignment::Left)
             .block(block);
         frame.render_widget(paragraph, area);
     }
 
-    fn render_trade_header(
+    // 部分成交的挂单显示累计成交量与进度，未成交显示 --
+    fn partial_fill_label(&self, order: &PendingOrderInfo) -> String {
+        if order.filled_size <= 0.0 || order.size <= 0.0 {
+            return "--".to_string();
+        }
+        let pct = (order.filled_size / order.size * 100.0).min(100.0);
+        format!(
+            "{} {:.0}%",
+            self.cached_size_label(&order.inst_id, order.filled_size),
+            pct
+        )
+    }
+
+    fn render_history_panel(&mut self, frame: &mut Frame, area: Rect) {
+        let view_label = match self.trade.history_view {
+            HistoryView::Orders => "委托",
+            HistoryView::Positions => "平仓",
+        };
+        let mut title = format!(
+            "History {view_label} · {}",
+            self.trade.history_range.label()
+        );
+        if self.trade.history_loading {
+            title.push_str(" · 加载中");
+        }
+        let block = self.section_block(&title, TradeFocus::History);
+        let inner_height = area.height.saturating_sub(2) as usize;
+        let list_visible = inner_height.saturating_sub(1);
+        self.trade.history_view_height = list_visible.clamp(1, u16::MAX as usize) as u16;
+        let total = self.trade.history_len();
+        let mut lines = Vec::new();
+        let mut hit_rows = Vec::new();
+        if self.trade.history.is_none() {
+            lines.push(Line::from(if self.trade.history_loading {
+                "正在从 OKX 加载历史记录…"
+            } else {
+                "按 r 从 OKX 加载历史记录"
+            }));
+        } else if total == 0 {
+            lines.push(Line::from(format!(
+                "{}内无{view_label}记录",
+                self.trade.history_range.label()
+            )));
+        } else if list_visible == 0 {
+            lines.push(Line::from("窗口高度不足，无法显示历史记录"));
+        } else {
+            let selected_idx = clamp_index(self.trade.selected_history_idx, total);
+            let (start, end) = visible_range(total, list_visible, selected_idx);
+            match self.trade.history_view {
+                HistoryView::Orders => {
+                    lines.extend(self.history_order_lines(start, end, selected_idx))
+                }
+                HistoryView::Positions => {
+                    lines.extend(self.closed_position_lines(start, end, selected_idx))
+                }
+            }
+            hit_rows = (start..end).map(Some).collect();
+        }
+        self.trade
+            .record_hit_area(TradeFocus::History, area, hit_rows);
+        let paragraph = Paragraph::new(lines)
+            .alignment(Alignment::Left)
+            .block(block);
+        frame.render_widget(paragraph, area);
+    }
+
+    fn history_order_lines(
         &self,
-        frame: &mut Frame,
-        area: Rect,
-        instruction_lines: &[String],
-        balance_lines: &[String],
-    ) {
+        start: usize,
+        end: usize,
+        selected_idx: usize,
+    ) -> Vec<Line<'static>> {
+        let Some(history) = &self.trade.history else {
+            return Vec::new();
+        };
+        let mut lines = vec![Line::from(format_columns(&[
+            ("序号", ColumnAlign::Right, 4),
+            ("更新", ColumnAlign::Left, 11),
+            ("合约", ColumnAlign::Left, 14),
+            ("方向", ColumnAlign::Left, 10),
+            ("类型", ColumnAlign::Left, 10),
+            ("成交/数量", ColumnAlign::Right, 16),
+            ("委托价", ColumnAlign::Right, 10),
+            ("成交均价", ColumnAlign::Right, 10),
+            ("盈亏", ColumnAlign::Right, 10),
+            ("状态", ColumnAlign::Left, 16),
+            ("订单", ColumnAlign::Left, 12),
+        ]))];
+        for (idx, order) in history
+            .orders
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(end.saturating_sub(start))
+        {
+            let price = |value: Option<f64>| {
+                value
+                    .filter(|value| *value > 0.0)
+                    .map(|value| self.cached_price_label(&order.inst_id, value))
+                    .unwrap_or_else(|| Rc::from("--"))
+            };
+            let size_label = format!(
+                "{}/{}",
+                self.cached_size_label(&order.inst_id, order.filled_size),
+                self.cached_size_label(&order.inst_id, order.size)
+            );
+            let row = format_columns(&[
+                (format!("{}", idx + 1).as_str(), ColumnAlign::Right, 4),
+                (
+                    self.history_time_label(order.update_time).as_str(),
+                    ColumnAlign::Left,
+                    11,
+                ),
+                (order.inst_id.as_str(), ColumnAlign::Left, 14),
+                (
+                    Self::order_side_label(&order.side, order.pos_side.as_deref()).as_str(),
+                    ColumnAlign::Left,
+                    10,
+                ),
+                (order.ord_type.as_str(), ColumnAlign::Left, 10),
+                (size_label.as_str(), ColumnAlign::Right, 16),
+                (&price(order.price), ColumnAlign::Right, 10),
+                (&price(order.avg_price), ColumnAlign::Right, 10),
+                (
+                    order
+                        .pnl
+                        .map(pnl_stats::format_pnl)
+                        .unwrap_or_else(|| "--".to_string())
+                        .as_str(),
+                    ColumnAlign::Right,
+                    10,
+                ),
+                (order.state.as_str(), ColumnAlign::Left, 16),
+                (
+                    Self::short_order_id(&order.ord_id).as_str(),
+                    ColumnAlign::Left,
+                    12,
+                ),
+            ]);
+            lines.push(Line::styled(row, self.theme.row_style(idx == selected_idx)));
+        }
+        lines
+    }
+
+    fn closed_position_lines(
+        &self,
+        start: usize,
+        end: usize,
+        selected_idx: usize,
+    ) -> Vec<Line<'static>> {
+        let Some(history) = &self.trade.history else {
+            return Vec::new();
+        };
+        let mut lines = vec![Line::from(format_columns(&[
+            ("序号", ColumnAlign::Right, 4),
+            ("平仓", ColumnAlign::Left, 11),
+            ("合约", ColumnAlign::Left, 14),
+            ("方向", ColumnAlign::Left, 4),
+            ("数量", ColumnAlign::Right, 12),
+            ("开仓均价", ColumnAlign::Right, 12),
+            ("平仓均价", ColumnAlign::Right, 12),
+            ("杠杆", ColumnAlign::Right, 10),
+            ("已实现盈亏", ColumnAlign::Right, 12),
+        ]))];
+        for (idx, position) in history
+            .closed_positions
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(end.saturating_sub(start))
+        {
+            let price = |value: Option<f64>| {
+                value
+                    .map(|value| self.cached_price_label(&position.inst_id, value))
+                    .unwrap_or_else(|| Rc::from("--"))
+            };
+            let lever_label = match position.mgn_mode {
+                Some(mode) => format!("{} {}", mode.label(), Self::leverage_label(position.lever)),
+                None => Self::leverage_label(position.lever),
+            };
+            let row = format_columns(&[
+                (format!("{}", idx + 1).as_str(), ColumnAlign::Right, 4),
+                (
+                    self.history_time_label(position.close_time).as_str(),
+                    ColumnAlign::Left,
+                    11,
+                ),
+                (position.inst_id.as_str(), ColumnAlign::Left, 14),
+                (
+                    Self::pos_side_label(position.pos_side.as_deref()),
+                    ColumnAlign::Left,
+                    4,
+                ),
+                (
+                    &self.cached_size_label(&position.inst_id, position.size),
+                    ColumnAlign::Right,
+                    12,
+                ),
+                (&price(position.open_avg_px), ColumnAlign::Right, 12),
+                (&price(position.close_avg_px), ColumnAlign::Right, 12),
+                (lever_label.as_str(), ColumnAlign::Right, 10),
+                (
+                    position
+                        .realized_pnl
+                        .map(pnl_stats::format_pnl)
+                        .unwrap_or_else(|| "--".to_string())
+                        .as_str(),
+                    ColumnAlign::Right,
+                    12,
+                ),
+            ]);
+            lines.push(Line::styled(row, self.theme.row_style(idx == selected_idx)));
+        }
+        lines
+    }
+
+    fn history_time_label(&self, timestamp: Option<i64>) -> String {
+        self.format_optional_timestamp(timestamp, "%m-%d %H:%M", "--")
+    }
+
+    fn render_trade_header(&self, frame: &mut Frame, area: Rect, instruction_lines: &[String]) {
         let inst = self
             .trade
             .selected_inst(&self.inst_ids)
@@ -1694,28 +3999,47 @@ impl TuiApp {
             .get(&inst)
             .map(|value| self.format_price_for(&inst, *value))
             .unwrap_or_else(|| "--".to_string());
+        let price_span = match self.price_stale_for(&inst) {
+            Some(elapsed) => Span::styled(
+                format!("{price} {}", Self::stale_price_label(elapsed)),
+                Style::default().fg(self.theme.muted),
+            ),
+            None => Span::styled(price, Style::default().fg(self.theme.emphasis)),
+        };
         let focus_label = self.trade.focus_label();
-        let lines = vec![Line::from(vec![
+        let mut header_spans = Vec::new();
+        for badge in [self.replay_badge(), self.demo_badge(), self.dry_run_badge()]
+            .into_iter()
+            .flatten()
+        {
+            header_spans.push(badge);
+            header_spans.push(Span::raw(" "));
+        }
+        header_spans.extend([
             Span::styled(
                 "交易页面",
                 Style::default()
-                    .fg(Color::LightCyan)
+                    .fg(self.theme.accent)
                     .add_modifier(Modifier::BOLD),
             ),
             Span::raw(" · "),
-            Span::styled(inst.as_str(), Style::default().fg(Color::LightGreen)),
+            Span::styled(inst.as_str(), Style::default().fg(self.theme.success)),
             Span::raw(" · 最新价 "),
-            Span::styled(price, Style::default().fg(Color::Yellow)),
+            price_span,
             Span::raw(" · 焦点 "),
             Span::styled(
                 focus_label,
                 Style::default()
-                    .fg(Color::LightMagenta)
+                    .fg(self.theme.secondary)
                     .add_modifier(Modifier::BOLD),
             ),
-        ])];
-        let mut lines = lines;
-        lines.extend(balance_lines.iter().map(|line| Line::from(line.as_str())));
+        ]);
+        let connection = self.connection_spans();
+        if !connection.is_empty() {
+            header_spans.push(Span::raw(" · "));
+            header_spans.extend(connection);
+        }
+        let mut lines = vec![Line::from(header_spans)];
         lines.extend(
             instruction_lines
                 .iter()
@@ -1735,13 +4059,13 @@ impl TuiApp {
             let (pos_cnt, ord_cnt) = self.trade.snapshot_counts();
             let log_cnt = self.trade.logs.len();
             let mut summary_line = format!(
-                "Tab 切换 · Shift+Tab 返回 · ↑↓/j k 浏览/滚动 · 持仓 {} · 挂单 {} · 委托 {}",
+                "Tab 切换 · Shift+Tab 返回 · ↑↓/j k 浏览/滚动 · ? 快捷键 · 持仓 {} · 挂单 {} · 委托 {}",
                 pos_cnt, ord_cnt, log_cnt
             );
             if self.trade.ai_panel_enabled() {
                 summary_line.push_str(&format!(" · AI {}", self.trade.ai_insight_count()));
             }
-            summary_line.push_str(" · t 返回图表");
+            summary_line.push_str(" · d 盈亏统计 · t 返回图表");
             instruction_lines.push(summary_line);
             self.push_focus_hints(&mut instruction_lines);
         } else {
@@ -1763,14 +4087,21 @@ impl TuiApp {
             return None;
         }
         let hint = match self.trade.focus {
-            TradeFocus::Instruments => "焦点 合约：↑↓/j k 选择合约 · b 买入 · s 卖出",
-            TradeFocus::Positions => "焦点 持仓：↑↓/j k 选择持仓 · p 止盈 · l 止损",
-            TradeFocus::Orders => "焦点 挂单：↑↓/j k 选择挂单 · c 撤单 · r 改单",
+            TradeFocus::Instruments => "焦点 合约：↑↓/j k 选择合约 · b 买入 · s 卖出 · a 调整杠杆",
+            TradeFocus::Positions => {
+                "焦点 持仓：↑↓/j k 选择持仓 · p 止盈 · l 止损 · g 移动止损 · 1-4 平仓 25/50/75/100% · v 反手 · + 同向加仓 · a 调整杠杆 · C 一键全平"
+            }
+            TradeFocus::Orders => {
+                "焦点 挂单：↑↓/j k 选择挂单 · c 撤单 · r 改单 · x 撤销该品种全部挂单"
+            }
             TradeFocus::AiInsights => {
-                "焦点 AI 决策：↑↓/j k 浏览 · PageUp/PageDown 翻页 · Home/End 顶/底 · o 查看原始提示"
+                "焦点 AI 决策：↑↓/j k 浏览 · PageUp/PageDown 翻页 · Home/End 顶/底 · o 查看原始提示 · r 立即分析 · e/E 导出 CSV/JSON"
             }
             TradeFocus::Logs => {
-                "焦点 委托记录：↑↓/j k 选择 · PageUp/PageDown 翻页 · Home/End 顶/底 · o 详情"
+                "焦点 委托记录：↑↓/j k 选择 · PageUp/PageDown 翻页 · Home/End 顶/底 · o 详情 · i 按合约筛选 · f 按类型筛选"
+            }
+            TradeFocus::History => {
+                "焦点 历史记录：↑↓/j k 浏览 · PageUp/PageDown 翻页 · Home/End 顶/底 · v 委托/平仓 · p 时间范围 · r 刷新"
             }
         };
         Some(hint.to_string())
@@ -1783,6 +4114,11 @@ impl TuiApp {
     }
 
     fn render_trade_activity(&mut self, frame: &mut Frame, area: Rect) {
+        // 历史记录获得焦点时占用委托记录的位置
+        if self.trade.focus == TradeFocus::History {
+            self.render_history_panel(frame, area);
+            return;
+        }
         let show_ai_panel = self.trade.ai_panel_enabled();
         if !show_ai_panel {
             self.render_trade_logs(frame, area);
@@ -1823,10 +4159,16 @@ impl TuiApp {
 
     fn render_trade_logs(&mut self, frame: &mut Frame, area: Rect) {
         let log_count = self.trade.logs.len();
+        let filtered = self.trade.filtered_log_indices();
         let mut lines = Vec::new();
+        let mut hit_rows = Vec::new();
         let inner_height = area.height.saturating_sub(2) as usize;
         let list_visible = inner_height.saturating_sub(1);
-        if log_count == 0 {
+        if filtered.is_empty() && log_count > 0 {
+            lines.push(Line::from(
+                "没有符合筛选条件的记录 · i 切换合约 · f 切换类型",
+            ));
+        } else if log_count == 0 {
             if self.trade.trading_enabled() {
                 lines.push(Line::from("暂无委托，按 b/s 提交订单"));
             } else {
@@ -1848,29 +4190,39 @@ impl TuiApp {
                 ("操作者", ColumnAlign::Left, 10),
             ])));
             let log_focus = self.trade.focus == TradeFocus::Logs;
-            let selected_display_idx = self.trade.selected_log_display_index();
-            let (start, end) = visible_range(log_count, list_visible, selected_display_idx);
-            let mut display_idx = start;
-            for (offset, entry) in self
-                .trade
-                .logs
+            let filtered_count = filtered.len();
+            let selected_display_idx = self.trade.selected_log_position(&filtered);
+            let (start, end) = visible_range(filtered_count, list_visible, selected_display_idx);
+            for (display_idx, log_idx) in filtered
                 .iter()
-                .rev()
                 .enumerate()
                 .skip(start)
                 .take(end.saturating_sub(start))
             {
+                let Some(entry) = self.trade.logs.get(*log_idx) else {
+                    continue;
+                };
                 let highlight = log_focus && display_idx == selected_display_idx;
-                let ordinal = log_count.saturating_sub(offset);
-                let ordinal = if ordinal == 0 { 1 } else { ordinal };
+                let ordinal = filtered_count - display_idx;
                 lines.push(self.render_log_row(entry, highlight, ordinal));
-                display_idx += 1;
+                hit_rows.push(Some(*log_idx));
             }
         }
-        let title = format!("Logs {log_count}/{MAX_TRADE_LOGS}");
+        self.trade.record_hit_area(TradeFocus::Logs, area, hit_rows);
+        let title = if self.trade.log_filter_active() {
+            format!(
+                "Logs {}/{log_count}/{} · 筛选 合约 {} · 类型 {}",
+                filtered.len(),
+                self.trade.max_logs,
+                self.trade.log_inst_filter.as_deref().unwrap_or("全部"),
+                self.trade.log_kind_filter.label()
+            )
+        } else {
+            format!("Logs {log_count}/{}", self.trade.max_logs)
+        };
         let mut block = Block::bordered().title(title);
         if self.trade.focus == TradeFocus::Logs {
-            block = block.border_style(Style::default().fg(Color::LightMagenta));
+            block = block.border_style(Style::default().fg(self.theme.secondary));
         }
         let page_height = list_visible.max(1);
         self.trade
@@ -1882,14 +4234,31 @@ impl TuiApp {
     }
 
     fn render_ai_panel(&mut self, frame: &mut Frame, area: Rect) {
-        let title = format!(
+        let mut title = format!(
             "{} 决策 {}",
             self.trade.ai_label(),
             self.trade.ai_insight_count()
         );
+        if let Some(interval) = self.trade.ai_interval {
+            title.push_str(&format!(" · 每 {}", format_duration_brief(interval)));
+        }
+        if let Some(pending) = &self.trade.ai_pending {
+            title.push_str(&format!(" · {} {}", pending.spinner(), pending.message()));
+        }
+        let usage = &self.trade.ai_usage_today;
+        if usage.prompt_tokens + usage.completion_tokens > 0 {
+            title.push_str(&format!(
+                " · 今日 Token 输入 {} / 输出 {}",
+                format_token_count(usage.prompt_tokens),
+                format_token_count(usage.completion_tokens)
+            ));
+            if usage.cost > 0.0 {
+                title.push_str(&format!(" · 费用≈{:.4}", usage.cost));
+            }
+        }
         let mut block = Block::bordered().title(title);
         if self.trade.focus == TradeFocus::AiInsights {
-            block = block.border_style(Style::default().fg(Color::LightMagenta));
+            block = block.border_style(Style::default().fg(self.theme.secondary));
         }
         if area.height < 3 {
             frame.render_widget(block, area);
@@ -1897,8 +4266,12 @@ impl TuiApp {
             return;
         }
         let mut lines = Vec::new();
+        let mut hit_rows = Vec::new();
         let inner_height = area.height.saturating_sub(2) as usize;
         let list_visible = inner_height.saturating_sub(1);
+        let stream_lines =
+            self.ai_stream_lines(area.width.saturating_sub(2) as usize, list_visible / 2);
+        let list_visible = list_visible - stream_lines.len();
         let page_height = list_visible.max(1);
         self.trade
             .set_ai_view_height(page_height.min(u16::MAX as usize) as u16);
@@ -1947,16 +4320,42 @@ impl TuiApp {
                         (operation.as_str(), ColumnAlign::Left, total_width),
                     ]);
                     let highlight = self.trade.focus == TradeFocus::AiInsights && idx == selected;
-                    lines.push(Line::styled(row, row_style(highlight)));
+                    lines.push(Line::styled(row, self.theme.row_style(highlight)));
+                    hit_rows.push(Some(idx));
                 }
             }
         }
+        self.trade
+            .record_hit_area(TradeFocus::AiInsights, area, hit_rows);
+        lines.extend(stream_lines);
         let paragraph = Paragraph::new(lines)
             .alignment(Alignment::Left)
             .block(block);
         frame.render_widget(paragraph, area);
     }
 
+    // 流式生成中只展示末尾几行，完整内容在决策完成后可在详情中查看
+    fn ai_stream_lines(&self, width: usize, max_rows: usize) -> Vec<Line<'static>> {
+        let Some(text) = self.trade.ai_stream.as_deref() else {
+            return Vec::new();
+        };
+        if max_rows < 2 || width == 0 {
+            return Vec::new();
+        }
+        let rows = wrap_to_width(text, width);
+        let skip = rows.len().saturating_sub(max_rows - 1);
+        let mut lines = vec![Line::from(Span::styled(
+            format!("{} 生成中…", self.trade.ai_label()),
+            Style::default().fg(self.theme.muted),
+        ))];
+        lines.extend(
+            rows.into_iter()
+                .skip(skip)
+                .map(|row| Line::styled(row, Style::default().fg(self.theme.subtle))),
+        );
+        lines
+    }
+
     fn ai_operation_label(entry: &AiDecisionRecord) -> String {
         if entry.analysis_error.is_some() {
             return "解析失败".to_string();
@@ -1980,24 +4379,34 @@ impl TuiApp {
         highlight: bool,
         ordinal: usize,
     ) -> Line<'static> {
-        let columns = self.log_row_columns(entry, ordinal);
+        self.render_column_spans(self.log_row_columns(entry, ordinal), highlight)
+    }
+
+    // 逐列着色的表格行，选中时整行保留高亮背景
+    fn render_column_spans(
+        &self,
+        columns: Vec<(String, ColumnAlign, usize, Option<Color>)>,
+        highlight: bool,
+    ) -> Line<'static> {
         let column_count = columns.len();
         let mut spans = Vec::new();
         for (idx, (value, align, width, color)) in columns.into_iter().enumerate() {
             let text = format_column_value(&value, align, width);
             let mut style = Style::default();
-            if let Some(color) = color {
+            if let Some(color) = color.or(highlight.then_some(self.theme.highlight_fg)) {
                 style = style.fg(color);
             }
             if highlight {
-                style = style.bg(Color::LightCyan).add_modifier(Modifier::BOLD);
+                style = style
+                    .bg(self.theme.highlight_bg)
+                    .add_modifier(Modifier::BOLD);
             }
             spans.push(Span::styled(text, style));
             if idx + 1 != column_count {
                 let mut spacer_style = Style::default();
                 if highlight {
                     spacer_style = spacer_style
-                        .bg(Color::LightCyan)
+                        .bg(self.theme.highlight_bg)
                         .add_modifier(Modifier::BOLD);
                 }
                 spans.push(Span::styled(" ".to_string(), spacer_style));
@@ -2015,30 +4424,43 @@ impl TuiApp {
         let leverage_label = Self::leverage_label(entry.leverage);
         let ordinal_label = ordinal.to_string();
         match &entry.event {
-            TradeEvent::Order(response) => {
+            TradeEvent::Order(response) | TradeEvent::Amend(response) => {
+                let action_label = if matches!(entry.event, TradeEvent::Amend(_)) {
+                    "改单"
+                } else {
+                    "委托"
+                };
                 let mut side_label = Self::side_short_label(response.side).to_string();
-                let size_label = self.format_contract_size(&response.inst_id, response.size);
-                let price_label = self.format_price_for(&response.inst_id, response.price);
-                let status_color = Self::status_color(response.success);
-                let order_type = Self::order_kind_label(response.kind);
+                let size_label = self
+                    .cached_size_label(&response.inst_id, response.size)
+                    .to_string();
+                let price_label = self
+                    .cached_price_label(&response.inst_id, response.price)
+                    .to_string();
+                let (status, status_color) = self.result_status(response.success, response.dry_run);
+                let order_type =
+                    Self::order_kind_label(response.kind).or(match response.ord_type {
+                        Some(ord_type) if ord_type != TradeOrderType::Limit => {
+                            Some(ord_type.label())
+                        }
+                        _ => None,
+                    });
                 if let Some(kind_label) = order_type {
                     side_label = format!("{}({})", side_label, kind_label);
                 }
+                if response.bracket.is_some() {
+                    side_label.push_str("+TP/SL");
+                }
                 vec![
                     (ordinal_label, ColumnAlign::Right, 5, None),
                     (time, ColumnAlign::Left, 8, None),
-                    ("委托".to_string(), ColumnAlign::Left, 4, None),
+                    (action_label.to_string(), ColumnAlign::Left, 4, None),
                     (response.inst_id.clone(), ColumnAlign::Left, 14, None),
                     (side_label, ColumnAlign::Left, 10, None),
                     (size_label, ColumnAlign::Right, 10, None),
                     (price_label, ColumnAlign::Right, 10, None),
                     (leverage_label, ColumnAlign::Right, 6, None),
-                    (
-                        Self::status_label(response.success).to_string(),
-                        ColumnAlign::Left,
-                        6,
-                        Some(status_color),
-                    ),
+                    (status.to_string(), ColumnAlign::Left, 6, Some(status_color)),
                     (
                         Self::operator_label(&response.operator),
                         ColumnAlign::Left,
@@ -2049,7 +4471,7 @@ impl TuiApp {
             }
             TradeEvent::Cancel(cancel) => {
                 let ord_short = Self::short_order_id(&cancel.ord_id);
-                let status_color = Self::status_color(cancel.success);
+                let (status, status_color) = self.result_status(cancel.success, cancel.dry_run);
                 vec![
                     (ordinal_label, ColumnAlign::Right, 5, None),
                     (time, ColumnAlign::Left, 8, None),
@@ -2059,12 +4481,7 @@ impl TuiApp {
                     ("--".to_string(), ColumnAlign::Right, 10, None),
                     ("--".to_string(), ColumnAlign::Right, 10, None),
                     (leverage_label, ColumnAlign::Right, 6, None),
-                    (
-                        Self::status_label(cancel.success).to_string(),
-                        ColumnAlign::Left,
-                        6,
-                        Some(status_color),
-                    ),
+                    (status.to_string(), ColumnAlign::Left, 6, Some(status_color)),
                     (
                         Self::operator_label(&cancel.operator),
                         ColumnAlign::Left,
@@ -2076,8 +4493,10 @@ impl TuiApp {
             TradeEvent::Fill(fill) => {
                 let side_label =
                     Self::order_side_label(fill.side.as_okx_side(), fill.pos_side.as_deref());
-                let size_label = self.format_contract_size(&fill.inst_id, fill.size);
-                let price_label = self.format_price_for(&fill.inst_id, fill.price);
+                let size_label = self.cached_size_label(&fill.inst_id, fill.size).to_string();
+                let price_label = self
+                    .cached_price_label(&fill.inst_id, fill.price)
+                    .to_string();
                 let exec_label = Self::exec_type_label(fill.exec_type.as_deref());
                 let order_short = Self::short_order_id(&fill.order_id);
                 vec![
@@ -2124,11 +4543,11 @@ impl TuiApp {
         }
     }
 
-    fn status_color(success: bool) -> Color {
+    fn status_color(&self, success: bool) -> Color {
         if success {
-            Color::LightGreen
+            self.theme.success
         } else {
-            Color::LightRed
+            self.theme.error
         }
     }
 
@@ -2136,10 +4555,19 @@ impl TuiApp {
         if success { "成功" } else { "失败" }
     }
 
+    fn result_status(&self, success: bool, dry_run: bool) -> (&'static str, Color) {
+        if dry_run {
+            ("模拟", self.theme.secondary)
+        } else {
+            (Self::status_label(success), self.status_color(success))
+        }
+    }
+
     fn order_kind_label(kind: TradeOrderKind) -> Option<&'static str> {
         match kind {
             TradeOrderKind::TakeProfit => Some("止盈"),
             TradeOrderKind::StopLoss => Some("止损"),
+            TradeOrderKind::TrailingStop => Some("移动止损"),
             TradeOrderKind::Regular => None,
         }
     }
@@ -2187,6 +4615,7 @@ impl TuiApp {
         match order.kind {
             TradeOrderKind::TakeProfit => return "止盈",
             TradeOrderKind::StopLoss => return "止损",
+            TradeOrderKind::TrailingStop => return "移动止损",
             TradeOrderKind::Regular => {}
         }
         if let Some(label) = Self::tagged_order_intent(order) {
@@ -2209,6 +4638,7 @@ impl TuiApp {
         match lowered.as_str() {
             "tp" | "takeprofit" | "take_profit" | "take-profit" | "dstp" => Some("止盈"),
             "sl" | "stoploss" | "stop_loss" | "stop-loss" | "dssl" => Some("止损"),
+            "ts" | "trailing" | "trailingstop" => Some("移动止损"),
             _ => None,
         }
     }
@@ -2293,17 +4723,14 @@ impl TuiApp {
         }
     }
 
-    fn closing_side_for_position(position: &PositionInfo) -> TradeSide {
-        match position.pos_side.as_deref() {
+    fn closing_side_for_position(position: &PositionInfo, mode: PositionMode) -> TradeSide {
+        let pos_side = match mode {
+            PositionMode::LongShort => position.pos_side.as_deref(),
+            PositionMode::Net => None,
+        };
+        match pos_side {
             Some("long") => TradeSide::Sell,
             Some("short") => TradeSide::Buy,
-            Some("net") => {
-                if position.size < 0.0 {
-                    TradeSide::Buy
-                } else {
-                    TradeSide::Sell
-                }
-            }
             _ => {
                 if position.size < 0.0 {
                     TradeSide::Buy
@@ -2314,7 +4741,10 @@ impl TuiApp {
         }
     }
 
-    fn pos_side_for_position(position: &PositionInfo) -> Option<String> {
+    fn pos_side_for_position(position: &PositionInfo, mode: PositionMode) -> Option<String> {
+        if mode == PositionMode::Net {
+            return Some("net".to_string());
+        }
         if let Some(pos_side) = &position.pos_side {
             if matches!(pos_side.as_str(), "long" | "short" | "net") {
                 return Some(pos_side.clone());
@@ -2348,23 +4778,36 @@ impl TuiApp {
             return;
         }
         let popup_width = area.width.saturating_sub(10).min(60).max(30);
-        let popup_height = area.height.min(8).max(6);
-        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
-        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
-        let popup = Rect::new(left, top, popup_width, popup_height);
         let block = Block::bordered().title(format!(
             "{}{} {}",
             input.intent.title_prefix(),
             Self::side_label(input.side),
             input.inst_id.as_str()
         ));
-        let price_span = self.order_field_span(
-            "价格",
-            &input.price,
-            input.active_field == OrderInputField::Price,
-        );
+        let price_span = if input.is_market() {
+            Line::from(vec![
+                Span::raw("价格: "),
+                Span::styled(
+                    TradeOrderType::Market.label(),
+                    Style::default().fg(self.theme.muted),
+                ),
+            ])
+        } else {
+            self.order_field_span(
+                if input.is_trailing() {
+                    "激活价(可空)"
+                } else {
+                    "价格"
+                },
+                &input.price,
+                input.active_field == OrderInputField::Price,
+            )
+        };
         let size_span = self.order_field_span(
-            "数量",
+            match self.size_unit {
+                SizeUnit::Coin => "数量(币)",
+                SizeUnit::Contracts => "数量(张)",
+            },
             &input.size,
             input.active_field == OrderInputField::Size,
         );
@@ -2373,42 +4816,308 @@ impl TuiApp {
             &input.leverage,
             input.active_field == OrderInputField::Leverage,
         );
-        let mut lines = vec![
-            Line::from(vec![
-                Span::raw("合约 "),
-                Span::styled(
-                    input.inst_id.as_str(),
-                    Style::default().fg(Color::LightCyan),
-                ),
-            ]),
-            price_span,
-            size_span,
-            leverage_span,
+        let margin_label = if input.spot {
+            "现货"
+        } else {
+            input
+                .effective_margin_mode()
+                .map(|mode| mode.label())
+                .unwrap_or("默认")
+        };
+        let callback_span = input.is_trailing().then(|| {
+            self.order_field_span(
+                "回调(%/价差)",
+                &input.callback,
+                input.active_field == OrderInputField::Callback,
+            )
+        });
+        let mut header = vec![
+            Span::raw("合约 "),
+            Span::styled(
+                input.inst_id.as_str(),
+                Style::default().fg(self.theme.accent),
+            ),
+            Span::raw(" · 保证金 "),
+            Span::styled(margin_label, Style::default().fg(self.theme.emphasis)),
         ];
+        if !input.spot {
+            let (label, color) = if input.reduce_only {
+                ("开", self.theme.emphasis)
+            } else {
+                ("关", self.theme.muted)
+            };
+            header.push(Span::raw(" · 只减仓 "));
+            header.push(Span::styled(label, Style::default().fg(color)));
+        }
+        let mut lines = vec![Line::from(header), price_span, size_span];
+        lines.extend(callback_span);
+        if input.supports_bracket() {
+            lines.push(self.order_field_span(
+                "止盈(可空)",
+                &input.take_profit,
+                input.active_field == OrderInputField::TakeProfit,
+            ));
+            lines.push(self.order_field_span(
+                "止损(可空)",
+                &input.stop_loss,
+                input.active_field == OrderInputField::StopLoss,
+            ));
+        }
+        if input.calculator {
+            lines.push(self.order_field_span(
+                "风险(USDT)",
+                &input.risk,
+                input.active_field == OrderInputField::Risk,
+            ));
+            lines.push(self.risk_sizing_line(input));
+        }
+        if !input.spot {
+            lines.push(leverage_span);
+        }
+        lines.extend(self.order_cost_lines(input));
+        if !self.trade.order_limits.is_empty() && !input.reduce_only {
+            lines.push(Line::from(Span::styled(
+                format!("下单上限 {}", self.trade.order_limits.describe()),
+                Style::default().fg(self.theme.muted),
+            )));
+        }
         if let Some(ord_id) = &input.replace_order_id {
             lines.push(Line::from(vec![
                 Span::raw("原单 "),
                 Span::styled(
                     Self::short_order_id(ord_id),
-                    Style::default().fg(Color::LightMagenta),
+                    Style::default().fg(self.theme.secondary),
                 ),
             ]));
         }
+        let ord_type_hint = if input.supports_market() {
+            format!(" · M {}", input.ord_type.label())
+        } else {
+            String::new()
+        };
+        let margin_hint = if input.spot {
+            ""
+        } else {
+            " · I 全仓/逐仓"
+        };
+        let calculator_hint = if input.supports_bracket() {
+            " · R 仓位计算"
+        } else {
+            ""
+        };
+        let reduce_only_hint = if input.intent == OrderIntent::Manual
+            && input.order_kind == TradeOrderKind::Regular
+            && input.replace_order_id.is_none()
+            && !input.spot
+        {
+            " · O 只减仓"
+        } else {
+            ""
+        };
+        let nudge_hint = if input.active_field == OrderInputField::Price {
+            " · ↑↓ ±1 跳 · PgUp/PgDn ±10 跳"
+        } else {
+            ""
+        };
+        let submit_hint = if input.calculator {
+            "Enter 填入数量".to_string()
+        } else {
+            format!("Enter 提交{}", input.intent.action_label())
+        };
         lines.push(Line::from(format!(
-            "Enter 提交{} · Esc 取消 · Tab/Shift+Tab 切换字段",
-            input.intent.action_label()
+            "{submit_hint} · Esc 取消 · Tab/Shift+Tab 切换字段{nudge_hint}{ord_type_hint}{margin_hint}{reduce_only_hint}{calculator_hint}"
         )));
         if let Some(err) = &input.error {
             lines.push(Line::from(Span::styled(
                 err.as_str(),
-                Style::default().fg(Color::LightRed),
+                Style::default().fg(self.theme.error),
             )));
         }
+        let popup_height = (lines.len() as u16 + 2).max(8).min(area.height);
+        let left = area.x + (area.width.saturating_sub(popup_width)) / 2;
+        let top = area.y + (area.height.saturating_sub(popup_height)) / 2;
+        let popup = Rect::new(left, top, popup_width, popup_height);
         let paragraph = Paragraph::new(lines)
             .alignment(Alignment::Left)
             .block(block);
         frame.render_widget(Clear, popup);
         frame.render_widget(paragraph, popup);
+        self.render_depth_ladder(frame, area, popup, &input.inst_id);
+    }
+
+    fn risk_sizing_line(&self, input: &OrderInputState) -> Line<'static> {
+        let sizing = match self.order_risk_sizing(input) {
+            Ok(sizing) => sizing,
+            Err(err) => {
+                return Line::from(Span::styled(err, Style::default().fg(self.theme.muted)));
+            }
+        };
+        let number = |value: f64| Self::trim_formatted_number(format!("{value:.4}"));
+        let mut text = format!(
+            "建议数量 {} ({} 张) · 名义 {}",
+            Self::trim_formatted_number(format!("{:.8}", sizing.size)),
+            number(sizing.contracts),
+            number(sizing.notional)
+        );
+        if let Some(margin) = sizing.margin {
+            text.push_str(&format!(" · 保证金 {}", number(margin)));
+        }
+        text.push_str(&format!(" · 止损亏损≈{}", number(sizing.loss)));
+        Line::from(Span::styled(text, Style::default().fg(self.theme.info)))
+    }
+
+    fn order_cost_lines(&self, input: &OrderInputState) -> Vec<Line<'static>> {
+        let (Some(price), Some(size)) = (
+            self.order_entry_price(input),
+            input
+                .size
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|size| *size > 0.0)
+                .and_then(|size| {
+                    self.size_unit
+                        .to_coins(size, self.trade.markets.get(&input.inst_id))
+                }),
+        ) else {
+            return Vec::new();
+        };
+        let notional = price * size;
+        let rate = self
+            .run_config
+            .as_ref()
+            .map(|config| config.fee_rate(&input.inst_id))
+            .unwrap_or_else(|| FeeRate::okx_default(input.spot));
+        let last = self.latest_prices.get(&input.inst_id).copied();
+        // 触发类委托与会立即成交的限价单按吃单费率估算
+        let taker = input.order_kind != TradeOrderKind::Regular
+            || match input.ord_type {
+                TradeOrderType::PostOnly => false,
+                TradeOrderType::Market | TradeOrderType::Ioc | TradeOrderType::Fok => true,
+                TradeOrderType::Limit => last.is_some_and(|last| match input.side {
+                    TradeSide::Buy => price >= last,
+                    TradeSide::Sell => price <= last,
+                }),
+            };
+        let (fee_label, fee_rate) = if taker {
+            ("吃单", rate.taker)
+        } else {
+            ("挂单", rate.maker)
+        };
+        let fee = notional * fee_rate;
+        let number = |value: f64| Self::trim_formatted_number(format!("{value:.4}"));
+        let leverage = input
+            .leverage
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|leverage| *leverage > 0.0)
+            .or(input.initial_leverage);
+        // 现货按成交额全额占用，合约按名义价值除以杠杆；只减仓不占用保证金
+        let required = if input.reduce_only {
+            None
+        } else if input.spot {
+            Some(notional + fee)
+        } else {
+            leverage.map(|leverage| notional / leverage)
+        };
+        let mut text = format!("预估 名义 {}", number(notional));
+        match required {
+            Some(required) if input.spot => text.push_str(&format!(" · 占用 {}", number(required))),
+            Some(required) => text.push_str(&format!(" · 保证金 {}", number(required))),
+            None if !input.reduce_only => text.push_str(" · 保证金 --"),
+            None => {}
+        }
+        text.push_str(&format!(
+            " · 手续费({fee_label} {}%) {}",
+            Self::trim_formatted_number(format!("{:.4}", fee_rate * 100.0)),
+            number(fee)
+        ));
+        let mut lines = vec![Line::from(Span::styled(
+            text,
+            Style::default().fg(self.theme.subtle),
+        ))];
+        let mut segments = input.inst_id.split('-');
+        let base = segments.next().unwrap_or_default();
+        let quote = segments.next().unwrap_or_default();
+        let needed = match required {
+            Some(_) if input.spot && input.side == TradeSide::Sell => Some((base, size)),
+            Some(required) => Some((quote, required)),
+            None => None,
+        };
+        let available = needed.and_then(|(currency, needed)| {
+            self.trade
+                .balance
+                .delta
+                .iter()
+                .find(|delta| delta.currency.eq_ignore_ascii_case(currency))
+                .and_then(|delta| delta.available)
+                .filter(|available| needed > *available)
+                .map(|available| (currency, available))
+        });
+        if let Some((currency, available)) = available {
+            lines.push(Line::from(Span::styled(
+                format!("可用 {} {currency} 不足，提交可能被拒绝", number(available)),
+                Style::default().fg(self.theme.error),
+            )));
+        }
+        lines
+    }
+
+    fn render_depth_ladder(&self, frame: &mut Frame, area: Rect, popup: Rect, inst_id: &str) {
+        let Some(book) = self.order_books.get(inst_id) else {
+            return;
+        };
+        let asks: Vec<_> = book.asks.iter().take(DEPTH_LADDER_LEVELS).collect();
+        let bids: Vec<_> = book.bids.iter().take(DEPTH_LADDER_LEVELS).collect();
+        let height = (asks.len() + bids.len() + 3) as u16;
+        if height > area.height {
+            return;
+        }
+        let left = if area.right() >= popup.right() + DEPTH_LADDER_WIDTH {
+            popup.right()
+        } else if popup.x >= area.x + DEPTH_LADDER_WIDTH {
+            popup.x - DEPTH_LADDER_WIDTH
+        } else {
+            return;
+        };
+        let top = popup.y.min(area.bottom().saturating_sub(height));
+        let ladder = Rect::new(left, top, DEPTH_LADDER_WIDTH, height);
+        let level_line = |level: &BookLevel, color: Color| {
+            let row = format_columns(&[
+                (
+                    self.format_price_for(inst_id, level.price).as_str(),
+                    ColumnAlign::Right,
+                    12,
+                ),
+                (
+                    self.format_contract_size(inst_id, level.size).as_str(),
+                    ColumnAlign::Right,
+                    11,
+                ),
+            ]);
+            Line::styled(row, Style::default().fg(color))
+        };
+        let mut lines: Vec<Line> = asks
+            .iter()
+            .rev()
+            .map(|level| level_line(level, self.theme.error))
+            .collect();
+        let spread = match (book.asks.first(), book.bids.first()) {
+            (Some(ask), Some(bid)) => format!(
+                "价差 {}",
+                self.format_price_for(inst_id, ask.price - bid.price)
+            ),
+            _ => "价差 --".to_string(),
+        };
+        lines.push(Line::styled(spread, Style::default().fg(self.theme.muted)));
+        lines.extend(
+            bids.iter()
+                .map(|level| level_line(level, self.theme.success)),
+        );
+        let paragraph = Paragraph::new(lines).block(Block::bordered().title("盘口"));
+        frame.render_widget(Clear, ladder);
+        frame.render_widget(paragraph, ladder);
     }
 
     fn render_ai_detail(&mut self, frame: &mut Frame, area: Rect, entry: &AiDecisionRecord) {
@@ -2427,14 +5136,20 @@ impl TuiApp {
         lines.push(Line::from(Span::styled(
             "[Decision]",
             Style::default()
-                .fg(Color::LightBlue)
+                .fg(self.theme.info)
                 .add_modifier(Modifier::BOLD),
         )));
         if let Some(error) = entry.analysis_error.as_deref() {
             lines.push(Line::from(Span::styled(
-                format!("解析失败: {error}"),
-                Style::default().fg(Color::LightRed),
+                "解析失败，该决策不会自动执行:",
+                Style::default().fg(self.theme.error),
             )));
+            for item in error.split("; ") {
+                lines.push(Line::from(Span::styled(
+                    format!("  {item}"),
+                    Style::default().fg(self.theme.error),
+                )));
+            }
         } else if entry.operations.is_empty() {
             lines.push(Line::from("未解析到决策操作"));
         } else {
@@ -2458,7 +5173,7 @@ impl TuiApp {
         lines.push(Line::from(Span::styled(
             "[AI Response]",
             Style::default()
-                .fg(Color::Yellow)
+                .fg(self.theme.emphasis)
                 .add_modifier(Modifier::BOLD),
         )));
         if entry.response.trim().is_empty() {
@@ -2472,7 +5187,7 @@ impl TuiApp {
         lines.push(Line::from(Span::styled(
             "[User Prompt]",
             Style::default()
-                .fg(Color::LightGreen)
+                .fg(self.theme.success)
                 .add_modifier(Modifier::BOLD),
         )));
         if entry.user_prompt.trim().is_empty() {
@@ -2499,7 +5214,7 @@ impl TuiApp {
             .block(
                 Block::bordered()
                     .title(format!("{} 提示详情", self.trade.ai_label()))
-                    .border_style(Style::default().fg(Color::LightMagenta)),
+                    .border_style(Style::default().fg(self.theme.secondary)),
             );
         frame.render_widget(Clear, popup);
         frame.render_widget(paragraph, popup);
@@ -2518,7 +5233,7 @@ impl TuiApp {
             self.format_timestamp_or_default(entry.timestamp_ms(), "%Y-%m-%d %H:%M:%S", "--");
         let mut lines = vec![Line::from(format!("时间 {timestamp}"))];
         let (title, status_success, message) = match &entry.event {
-            TradeEvent::Order(response) => {
+            TradeEvent::Order(response) | TradeEvent::Amend(response) => {
                 lines.push(Line::from(format!("合约 {}", response.inst_id)));
                 lines.push(Line::from(format!(
                     "方向 {} · 数量 {}",
@@ -2532,14 +5247,36 @@ impl TuiApp {
                     "价格 {}",
                     self.format_price_for(&response.inst_id, response.price),
                 )));
+                if let Some(bracket) = &response.bracket {
+                    let format_px = |px: Option<f64>| {
+                        px.map(|value| self.format_price_for(&response.inst_id, value))
+                            .unwrap_or_else(|| "--".to_string())
+                    };
+                    lines.push(Line::from(format!(
+                        "附带止盈 {} · 止损 {}",
+                        format_px(bracket.take_profit),
+                        format_px(bracket.stop_loss)
+                    )));
+                }
                 if let Some(ord_id) = &response.order_id {
                     lines.push(Line::from(format!("订单ID {}", ord_id)));
                 }
+                if let Some(cl_ord_id) = &response.cl_ord_id {
+                    lines.push(Line::from(format!("客户订单ID {cl_ord_id}")));
+                }
                 lines.push(Line::from(format!(
                     "操作者 {}",
                     Self::operator_label(&response.operator)
                 )));
-                ("委托详情", response.success, response.message.clone())
+                if let Some(code) = &response.error_code {
+                    lines.push(Line::from(format!("错误码 {code}")));
+                }
+                let title = if matches!(entry.event, TradeEvent::Amend(_)) {
+                    "改单详情"
+                } else {
+                    "委托详情"
+                };
+                (title, response.success, response.message.clone())
             }
             TradeEvent::Cancel(cancel) => {
                 lines.push(Line::from(format!("合约 {}", cancel.inst_id)));
@@ -2551,6 +5288,9 @@ impl TuiApp {
                     "操作者 {}",
                     Self::operator_label(&cancel.operator)
                 )));
+                if let Some(code) = &cancel.error_code {
+                    lines.push(Line::from(format!("错误码 {code}")));
+                }
                 ("撤单详情", cancel.success, cancel.message.clone())
             }
             TradeEvent::Fill(fill) => {
@@ -2575,6 +5315,9 @@ impl TuiApp {
                 if let Some(trade_id) = &fill.trade_id {
                     lines.push(Line::from(format!("成交ID {}", trade_id)));
                 }
+                if let Some(cl_ord_id) = &fill.cl_ord_id {
+                    lines.push(Line::from(format!("客户订单ID {cl_ord_id}")));
+                }
                 if let Some(acc) = fill.acc_fill_size {
                     lines.push(Line::from(format!(
                         "累计成交 {}",
@@ -2595,7 +5338,7 @@ impl TuiApp {
                     lines.push(Line::from(format!("手续费 {} {}", fee, ccy)));
                 }
                 if let Some(pnl) = fill.pnl {
-                    lines.push(Line::from(format!("PNL {}", pnl)));
+                    lines.push(Line::from(format!("PNL {}", pnl_stats::format_pnl(pnl))));
                 }
                 (
                     "成交详情",
@@ -2614,8 +5357,12 @@ impl TuiApp {
             "杠杆 {}",
             Self::leverage_label(entry.leverage)
         )));
-        let status_color = Self::status_color(status_success);
-        let status_label = Self::status_label(status_success);
+        let dry_run = match &entry.event {
+            TradeEvent::Order(response) | TradeEvent::Amend(response) => response.dry_run,
+            TradeEvent::Cancel(cancel) => cancel.dry_run,
+            TradeEvent::Fill(_) => false,
+        };
+        let (status_label, status_color) = self.result_status(status_success, dry_run);
         lines.push(Line::from(vec![
             Span::raw("状态 "),
             Span::styled(status_label, Style::default().fg(status_color)),
@@ -2661,42 +5408,222 @@ impl TuiApp {
         total.max(lines.len())
     }
 
-    fn order_field_span(&self, label: &str, value: &str, active: bool) -> Line<'static> {
-        let mut spans = vec![Span::raw(format!("{label} "))];
-        let mut style = Style::default().fg(Color::White);
-        if active {
-            style = style
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD | Modifier::REVERSED);
+    fn order_field_span(&self, label: &str, value: &str, active: bool) -> Line<'static> {
+        let mut spans = vec![Span::raw(format!("{label} "))];
+        let mut style = Style::default().fg(self.theme.text);
+        if active {
+            style = style
+                .fg(self.theme.emphasis)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED);
+        }
+        spans.push(Span::styled(
+            if value.is_empty() {
+                "<空>".to_string()
+            } else {
+                value.to_string()
+            },
+            style,
+        ));
+        Line::from(spans)
+    }
+
+    fn leverage_input_value(value: f64) -> String {
+        let mut formatted = format!("{value:.4}");
+        if let Some(dot_pos) = formatted.find('.') {
+            let mut trim_idx = formatted.len();
+            while trim_idx > dot_pos + 1 && formatted.as_bytes()[trim_idx - 1] == b'0' {
+                trim_idx -= 1;
+            }
+            if trim_idx == dot_pos + 1 {
+                trim_idx -= 1;
+            }
+            formatted.truncate(trim_idx);
+        }
+        if formatted == "-0" {
+            formatted = "0".to_string();
+        }
+        formatted
+    }
+    fn render_chart_panels(&self, frame: &mut Frame, area: Rect) {
+        let area = match self.selected_tape() {
+            Some((inst_id, tape)) if area.width >= TAPE_MIN_CHART_WIDTH + TAPE_PANEL_WIDTH => {
+                let chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([
+                        Constraint::Min(TAPE_MIN_CHART_WIDTH),
+                        Constraint::Length(TAPE_PANEL_WIDTH),
+                    ])
+                    .split(area);
+                self.render_tape_panel(frame, chunks[1], inst_id, tape);
+                chunks[0]
+            }
+            _ => area,
+        };
+        if self.macd_enabled && area.height >= MACD_PANEL_HEIGHT * 2 {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Min(MACD_PANEL_HEIGHT),
+                    Constraint::Length(MACD_PANEL_HEIGHT),
+                ])
+                .split(area);
+            self.render_chart(frame, chunks[0]);
+            self.render_macd_panel(frame, chunks[1]);
+        } else {
+            self.render_chart(frame, area);
+        }
+    }
+
+    fn selected_tape(&self) -> Option<(&str, &VecDeque<PublicTrade>)> {
+        let inst_id = self
+            .inst_ids
+            .get(self.trade.selected_inst_idx)
+            .or_else(|| self.inst_ids.first())?;
+        let tape = self
+            .public_trades
+            .get(inst_id)
+            .filter(|tape| !tape.is_empty())?;
+        Some((inst_id.as_str(), tape))
+    }
+
+    fn render_tape_panel(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        inst_id: &str,
+        tape: &VecDeque<PublicTrade>,
+    ) {
+        let rows = area.height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = tape
+            .iter()
+            .take(rows)
+            .map(|trade| {
+                let time = self
+                    .timezone
+                    .format_timestamp(trade.ts, "%H:%M:%S")
+                    .unwrap_or_default();
+                let row = format_columns(&[
+                    (time.as_str(), ColumnAlign::Left, 8),
+                    (
+                        self.format_price_for(inst_id, trade.price).as_str(),
+                        ColumnAlign::Right,
+                        12,
+                    ),
+                    (
+                        self.format_contract_size(inst_id, trade.size).as_str(),
+                        ColumnAlign::Right,
+                        10,
+                    ),
+                ]);
+                let color = match trade.side {
+                    TradeSide::Buy => self.theme.success,
+                    TradeSide::Sell => self.theme.error,
+                };
+                Line::styled(row, Style::default().fg(color))
+            })
+            .collect();
+        let paragraph =
+            Paragraph::new(lines).block(Block::bordered().title(format!("Tape {inst_id}")));
+        frame.render_widget(paragraph, area);
+    }
+
+    fn render_macd_panel(&self, frame: &mut Frame, area: Rect) {
+        let Some(inst_id) = self
+            .inst_ids
+            .get(self.trade.selected_inst_idx)
+            .or_else(|| self.inst_ids.first())
+        else {
+            return;
+        };
+        let series = self
+            .data
+            .get(inst_id)
+            .map(price_series::as_slice)
+            .unwrap_or(EMPTY_SERIES);
+        let result = indicators::macd(
+            series,
+            DEFAULT_MACD_FAST,
+            DEFAULT_MACD_SLOW,
+            DEFAULT_MACD_SIGNAL,
+        );
+        let x_bounds = self.x_bounds();
+        let in_window = |(x, _): &&(f64, f64)| *x >= x_bounds[0] && *x <= x_bounds[1];
+        let (positive, negative): (Vec<_>, Vec<_>) = result
+            .histogram
+            .iter()
+            .filter(in_window)
+            .partition(|(_, value)| *value >= 0.0);
+        let max_abs = result
+            .macd
+            .iter()
+            .chain(&result.signal)
+            .chain(&result.histogram)
+            .filter(in_window)
+            .map(|(_, value)| value.abs())
+            .filter(|value| value.is_finite())
+            .fold(0.0, f64::max);
+        let bound = if max_abs > 0.0 { max_abs * 1.1 } else { 1.0 };
+        let precision = self.price_precision.get(inst_id).copied().unwrap_or(2) + 2;
+        let title = match (result.macd.last(), result.signal.last()) {
+            (Some((_, macd)), Some((_, signal))) => format!(
+                "MACD({DEFAULT_MACD_FAST},{DEFAULT_MACD_SLOW},{DEFAULT_MACD_SIGNAL}) {inst_id} {macd:.precision$} / {signal:.precision$}"
+            ),
+            _ => format!(
+                "MACD({DEFAULT_MACD_FAST},{DEFAULT_MACD_SLOW},{DEFAULT_MACD_SIGNAL}) {inst_id} warming up"
+            ),
+        };
+        let datasets = vec![
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Bar)
+                .style(Style::default().fg(self.theme.success))
+                .data(&positive),
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Bar)
+                .style(Style::default().fg(self.theme.error))
+                .data(&negative),
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(self.theme.accent))
+                .data(&result.macd),
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(self.theme.emphasis))
+                .data(&result.signal),
+        ];
+        let chart = Chart::new(datasets)
+            .block(Block::bordered().title(title))
+            .x_axis(
+                Axis::default()
+                    .style(Style::default().fg(self.theme.subtle))
+                    .bounds(x_bounds),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(Style::default().fg(self.theme.subtle))
+                    .labels(vec![
+                        Span::raw(format!("{:.precision$}", -bound)),
+                        Span::raw("0"),
+                        Span::raw(format!("{bound:.precision$}")),
+                    ])
+                    .bounds([-bound, bound]),
+            );
+        frame.render_widget(chart, area);
+    }
+
+    fn x_bounds(&self) -> [f64; 2] {
+        if self.window[0] < self.window[1] {
+            self.window
+        } else if (self.window[0] - self.window[1]).abs() < f64::EPSILON {
+            [self.window[0] - 1.0, self.window[1] + 1.0]
+        } else {
+            [self.window[1], self.window[0]]
         }
-        spans.push(Span::styled(
-            if value.is_empty() {
-                "<空>".to_string()
-            } else {
-                value.to_string()
-            },
-            style,
-        ));
-        Line::from(spans)
     }
 
-    fn leverage_input_value(value: f64) -> String {
-        let mut formatted = format!("{value:.4}");
-        if let Some(dot_pos) = formatted.find('.') {
-            let mut trim_idx = formatted.len();
-            while trim_idx > dot_pos + 1 && formatted.as_bytes()[trim_idx - 1] == b'0' {
-                trim_idx -= 1;
-            }
-            if trim_idx == dot_pos + 1 {
-                trim_idx -= 1;
-            }
-            formatted.truncate(trim_idx);
-        }
-        if formatted == "-0" {
-            formatted = "0".to_string();
-        }
-        formatted
-    }
     fn render_chart(&self, frame: &mut Frame, area: Rect) {
         let multi_axis_active = self.multi_axis_active();
         let x_mid = f64::midpoint(self.window[0], self.window[1]);
@@ -2721,23 +5648,24 @@ impl TuiApp {
             let source = self
                 .data
                 .get(inst_id)
-                .map(|series| series.as_slice())
+                .map(price_series::as_slice)
                 .unwrap_or(EMPTY_SERIES);
             let (points, axis_info) = self.series_view(inst_id, source, multi_axis_active, color);
             if let Some(info) = axis_info {
                 axis_infos.push(info);
             }
-            if self.normalize {
-                if let Some(price) = self.latest_prices.get(inst_id).copied() {
-                    price_entries.push(PricePanelEntry {
-                        inst_id: inst_id.clone(),
-                        color,
-                        price,
-                        change_pct: self.normalized_latest_value(inst_id),
-                    });
-                }
+            if let Some(price) = self.latest_prices.get(inst_id).copied() {
+                price_entries.push(PricePanelEntry {
+                    inst_id: inst_id.clone(),
+                    color,
+                    price,
+                    change_pct: self.session_change_pct(inst_id),
+                });
             }
-            for (_, y) in points.iter() {
+            // 冻结时只按可见时间段计算 Y 轴范围
+            for (_, y) in points.iter().filter(|(x, _)| {
+                self.follow_latest || (*x >= self.window[0] && *x <= self.window[1])
+            }) {
                 if y.is_finite() {
                     raw_min_y = raw_min_y.min(*y);
                     raw_max_y = raw_max_y.max(*y);
@@ -2746,13 +5674,18 @@ impl TuiApp {
             views.push((inst_id.as_str(), points, color));
         }
         if views.is_empty() {
-            views.push(("N/A", Cow::Borrowed(EMPTY_SERIES), Color::White));
+            views.push(("N/A", Cow::Borrowed(EMPTY_SERIES), self.theme.text));
         }
         let (label_min_y, label_max_y, bounds_min_y, bounds_max_y) =
             if !raw_min_y.is_finite() || !raw_max_y.is_finite() {
                 (0.0, 1.0, 0.0, 1.0)
             } else if (raw_max_y - raw_min_y).abs() < f64::EPSILON {
-                let padding = (raw_max_y.abs() * 0.05).max(1.0);
+                // 对数坐标下 1.0 相当于一个数量级，平直序列只留少量余量
+                let padding = if self.log_scale_active() {
+                    0.01
+                } else {
+                    (raw_max_y.abs() * 0.05).max(1.0)
+                };
                 (
                     raw_min_y,
                     raw_max_y,
@@ -2780,38 +5713,145 @@ impl TuiApp {
                 Style::default().add_modifier(Modifier::BOLD),
             ),
         ];
-        let x_bounds = if self.window[0] < self.window[1] {
-            self.window
-        } else if (self.window[0] - self.window[1]).abs() < f64::EPSILON {
-            [self.window[0] - 1.0, self.window[1] + 1.0]
-        } else {
-            [self.window[1], self.window[0]]
-        };
+        let x_bounds = self.x_bounds();
         let y_bounds = self.apply_y_zoom(bounds_min_y, bounds_max_y);
-        let datasets: Vec<Dataset> = views
-            .iter()
-            .map(|(inst_id, points, color)| {
-                let d = Dataset::default();
-                if !(self.normalize || multi_axis_active) {
-                    d.name(self.legend_label(inst_id))
-                        .marker(symbols::Marker::Braille)
-                        .graph_type(GraphType::Line)
-                        .style(Style::default().fg(*color))
-                        .data(points.as_ref())
-                } else {
-                    d.marker(symbols::Marker::Braille)
-                        .graph_type(GraphType::Line)
-                        .style(Style::default().fg(*color))
-                        .data(points.as_ref())
+        let candles_active = self.candles_active();
+        let mut rising = CandlePoints::default();
+        let mut falling = CandlePoints::default();
+        if candles_active {
+            let interval_ms = self.candle_interval_secs() as f64 * 1000.0;
+            // 散点不会连线，按图表行数采样竖线：盲文每格 4 个点，方块每格 1 个
+            let rows = f64::from(area.height.saturating_sub(3).max(1));
+            let y_span = y_bounds[1] - y_bounds[0];
+            for (_, points, _) in &views {
+                for bucket in bucket_ohlc(points.as_ref(), interval_ms, x_bounds) {
+                    let x = bucket.start + interval_ms / 2.0;
+                    let target = if bucket.close >= bucket.open {
+                        &mut rising
+                    } else {
+                        &mut falling
+                    };
+                    push_vertical(
+                        &mut target.wicks,
+                        x,
+                        [bucket.low, bucket.high],
+                        y_bounds,
+                        y_span / (rows * 4.0),
+                    );
+                    push_vertical(
+                        &mut target.bodies,
+                        x,
+                        [bucket.open, bucket.close],
+                        y_bounds,
+                        y_span / (rows * 2.0),
+                    );
                 }
-            })
-            .collect();
+            }
+        }
+        let sma_series: Vec<(Vec<(f64, f64)>, Color)> = if self.sma_enabled {
+            views
+                .iter()
+                .map(|(_, points, color)| {
+                    (indicators::sma(points.as_ref(), self.sma_period), *color)
+                })
+                .filter(|(series, _)| !series.is_empty())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        // 布林带只在绝对价格单轴下绘制，按可见窗口裁剪
+        let bollinger_series: Vec<(Vec<(f64, f64)>, Color)> =
+            if self.bollinger_enabled && !(self.normalize || multi_axis_active) {
+                let in_window = |points: Vec<(f64, f64)>| -> Vec<(f64, f64)> {
+                    points
+                        .into_iter()
+                        .filter(|(x, _)| *x >= x_bounds[0] && *x <= x_bounds[1])
+                        .collect()
+                };
+                views
+                    .iter()
+                    .flat_map(|(_, points, color)| {
+                        let bands = indicators::bollinger(
+                            points.as_ref(),
+                            DEFAULT_BOLLINGER_PERIOD,
+                            DEFAULT_BOLLINGER_K,
+                        );
+                        [
+                            (in_window(bands.upper), *color),
+                            (in_window(bands.middle), *color),
+                            (in_window(bands.lower), *color),
+                        ]
+                    })
+                    .filter(|(series, _)| !series.is_empty())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+        let mut datasets: Vec<Dataset> = if candles_active {
+            let candles = [(&rising, self.theme.success), (&falling, self.theme.error)];
+            let wicks = candles.iter().map(|(points, color)| {
+                Dataset::default()
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Scatter)
+                    .style(Style::default().fg(*color))
+                    .data(&points.wicks)
+            });
+            let bodies = candles.iter().map(|(points, color)| {
+                Dataset::default()
+                    .marker(symbols::Marker::Block)
+                    .graph_type(GraphType::Scatter)
+                    .style(Style::default().fg(*color))
+                    .data(&points.bodies)
+            });
+            wicks.chain(bodies).collect()
+        } else {
+            views
+                .iter()
+                .map(|(inst_id, points, color)| {
+                    let d = Dataset::default();
+                    if !(self.normalize || multi_axis_active) {
+                        d.name(self.legend_label(inst_id))
+                            .marker(symbols::Marker::Braille)
+                            .graph_type(GraphType::Line)
+                            .style(Style::default().fg(*color))
+                            .data(points.as_ref())
+                    } else {
+                        d.marker(symbols::Marker::Braille)
+                            .graph_type(GraphType::Line)
+                            .style(Style::default().fg(*color))
+                            .data(points.as_ref())
+                    }
+                })
+                .collect()
+        };
+        for (series, _) in &bollinger_series {
+            datasets.push(
+                Dataset::default()
+                    .marker(symbols::Marker::Dot)
+                    .graph_type(GraphType::Line)
+                    .style(
+                        Style::default()
+                            .fg(self.theme.muted)
+                            .add_modifier(Modifier::DIM),
+                    )
+                    .data(series),
+            );
+        }
+        for (series, color) in &sma_series {
+            datasets.push(
+                Dataset::default()
+                    .marker(symbols::Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(*color).add_modifier(Modifier::DIM))
+                    .data(series),
+            );
+        }
         let chart = Chart::new(datasets)
             .block(Block::bordered().title(self.chart_title_line()))
             .x_axis(
                 Axis::default()
                     .title("Time")
-                    .style(Style::default().fg(Color::Gray))
+                    .style(Style::default().fg(self.theme.subtle))
                     .labels(x_labels)
                     .labels_alignment(Alignment::Left)
                     .bounds(x_bounds),
@@ -2819,7 +5859,7 @@ impl TuiApp {
             .y_axis(
                 Axis::default()
                     .title(self.axis_title())
-                    .style(Style::default().fg(Color::Gray))
+                    .style(Style::default().fg(self.theme.subtle))
                     .labels(y_labels)
                     .bounds(y_bounds),
             );
@@ -2830,7 +5870,7 @@ impl TuiApp {
         }
         let panel_kind = if multi_axis_active && !axis_infos.is_empty() {
             Some(PanelKind::MultiAxis)
-        } else if self.normalize && !price_entries.is_empty() {
+        } else if !multi_axis_active && !price_entries.is_empty() {
             Some(PanelKind::Price)
         } else {
             None
@@ -2854,17 +5894,345 @@ impl TuiApp {
         }
     }
     fn chart_title_line(&self) -> Line<'static> {
-        let mut spans = vec![Span::styled(
+        let mut spans = Vec::new();
+        for badge in [self.replay_badge(), self.demo_badge(), self.dry_run_badge()]
+            .into_iter()
+            .flatten()
+        {
+            spans.push(badge);
+            spans.push(Span::raw(" "));
+        }
+        spans.push(Span::styled(
             self.chart_title_text(),
             Style::default().add_modifier(Modifier::BOLD),
-        )];
+        ));
         for badge in self.mode_badges() {
             spans.push(Span::raw(" "));
             spans.push(badge);
         }
+        if let Some(rsi) = self.rsi_badge() {
+            spans.push(Span::raw(" "));
+            spans.push(rsi);
+        }
+        if let Some(atr) = self.atr_badge() {
+            spans.push(Span::raw(" "));
+            spans.push(atr);
+        }
+        if let Some(ticker) = self.ticker_badge() {
+            spans.push(Span::raw(" "));
+            spans.push(ticker);
+        }
+        if let Some(funding) = self.funding_badge() {
+            spans.push(Span::raw(" "));
+            spans.push(funding);
+        }
+        if let Some(ratio) = self.long_short_badge() {
+            spans.push(Span::raw(" "));
+            spans.push(ratio);
+        }
+        for inst_id in &self.inst_ids {
+            if let Some(elapsed) = self.price_stale_for(inst_id) {
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(
+                    format!("[{inst_id} {}]", Self::stale_price_label(elapsed)),
+                    Style::default().fg(self.theme.muted),
+                ));
+            }
+        }
+        let connection = self.connection_spans();
+        if !connection.is_empty() {
+            spans.push(Span::raw(" "));
+            spans.extend(connection);
+        }
         Line::from(spans)
     }
 
+    // 按固定顺序排列各 WebSocket 频道，同一频道的全部连接都成功才显示为已连接；返回显示是否有变化
+    fn update_conn_state(&mut self, channel: &str, conn_id: u32, connected: bool) -> bool {
+        let (order, label) = match channel {
+            "mark-price" => (0, "标记价"),
+            "books5" => (1, "盘口"),
+            "tickers" => (2, "行情"),
+            "trades" => (3, "成交"),
+            "private" => (4, "账户"),
+            "business" => (5, "策略"),
+            "replay" => (6, "回放"),
+            _ => return false,
+        };
+        let before = self.channel_connected(order);
+        self.conn_states
+            .entry(order)
+            .or_insert_with(|| (label, HashMap::new()))
+            .1
+            .insert(conn_id, connected);
+        before != self.channel_connected(order)
+    }
+
+    fn channel_connected(&self, order: usize) -> Option<bool> {
+        self.conn_states
+            .get(&order)
+            .map(|(_, conns)| conns.values().all(|connected| *connected))
+    }
+
+    // 每个频道一个圆点，绿色为已连接、红色为断线重连中
+    fn connection_spans(&self) -> Vec<Span<'static>> {
+        if self.conn_states.is_empty() {
+            return Vec::new();
+        }
+        let mut spans = vec![Span::styled("WS", Style::default().fg(self.theme.muted))];
+        for (label, conns) in self.conn_states.values() {
+            let connected = conns.values().all(|connected| *connected);
+            let color = if connected {
+                self.theme.success
+            } else {
+                self.theme.error
+            };
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled("●", Style::default().fg(color)));
+            spans.push(Span::styled(
+                label.to_string(),
+                Style::default().fg(if connected {
+                    self.theme.muted
+                } else {
+                    self.theme.error
+                }),
+            ));
+        }
+        spans
+    }
+
+    fn demo_badge(&self) -> Option<Span<'static>> {
+        if !self.demo {
+            return None;
+        }
+        Some(Span::styled(
+            "[模拟盘 DEMO]",
+            Style::default()
+                .fg(self.theme.highlight_fg)
+                .bg(self.theme.emphasis)
+                .add_modifier(Modifier::BOLD),
+        ))
+    }
+
+    fn replay_badge(&self) -> Option<Span<'static>> {
+        if !self.replay {
+            return None;
+        }
+        Some(Span::styled(
+            "[回放 REPLAY]",
+            Style::default()
+                .fg(self.theme.highlight_fg)
+                .bg(self.theme.muted)
+                .add_modifier(Modifier::BOLD),
+        ))
+    }
+
+    fn dry_run_badge(&self) -> Option<Span<'static>> {
+        if !self.dry_run {
+            return None;
+        }
+        Some(Span::styled(
+            okx::DRY_RUN_TAG,
+            Style::default()
+                .fg(self.theme.highlight_fg)
+                .bg(self.theme.secondary)
+                .add_modifier(Modifier::BOLD),
+        ))
+    }
+
+    // 每次拉取都会返回最近若干期，只追加比已有样本更新的数据
+    fn record_long_short_ratios(&mut self, inst_id: String, ratios: Vec<LongShortRatio>) {
+        let series = self.long_short_ratios.entry(inst_id).or_default();
+        for ratio in ratios {
+            if !ratio.ratio.is_finite() || series.back().is_some_and(|last| ratio.ts <= last.ts) {
+                continue;
+            }
+            series.push_back(ratio);
+        }
+        while series.len() > MAX_LONG_SHORT_SAMPLES {
+            series.pop_front();
+        }
+    }
+
+    fn long_short_badge(&self) -> Option<Span<'static>> {
+        let inst_id = self
+            .inst_ids
+            .get(self.trade.selected_inst_idx)
+            .or_else(|| self.inst_ids.first())?;
+        let series = self.long_short_ratios.get(inst_id)?;
+        let latest = series.back()?;
+        let previous = series.len().checked_sub(2).and_then(|idx| series.get(idx));
+        let (arrow, color) = match previous {
+            Some(prev) if latest.ratio > prev.ratio => ("▲", self.theme.success),
+            Some(prev) if latest.ratio < prev.ratio => ("▼", self.theme.error),
+            _ => ("-", self.theme.subtle),
+        };
+        Some(Span::styled(
+            format!("[L/S {:.2} {arrow}]", latest.ratio),
+            Style::default().fg(color),
+        ))
+    }
+
+    fn funding_badge(&self) -> Option<Span<'static>> {
+        let inst_id = self
+            .inst_ids
+            .get(self.trade.selected_inst_idx)
+            .or_else(|| self.inst_ids.first())?;
+        let info = self.funding_rates.get(inst_id)?;
+        let remaining_ms = info.funding_time - Utc::now().timestamp_millis();
+        let countdown = if remaining_ms > 0 {
+            let secs = remaining_ms / 1000;
+            format!(
+                "{:02}:{:02}:{:02}",
+                secs / 3600,
+                (secs % 3600) / 60,
+                secs % 60
+            )
+        } else {
+            "结算中".to_string()
+        };
+        let color = if info.rate > 0.0 {
+            self.theme.error
+        } else if info.rate < 0.0 {
+            self.theme.success
+        } else {
+            self.theme.subtle
+        };
+        Some(Span::styled(
+            format!("[Funding {:+.4}% {countdown}]", info.rate * 100.0),
+            Style::default().fg(color),
+        ))
+    }
+
+    fn ticker_badge(&self) -> Option<Span<'static>> {
+        let inst_id = self
+            .inst_ids
+            .get(self.trade.selected_inst_idx)
+            .or_else(|| self.inst_ids.first())?;
+        let now_ms = Utc::now().timestamp_millis();
+        let ticker = self
+            .tickers
+            .get(inst_id)
+            .filter(|ticker| now_ms - ticker.ts <= TICKER_STALE_MS);
+        let price = |value: Option<f64>| {
+            value
+                .map(|value| self.format_price_for(inst_id, value))
+                .unwrap_or_else(|| "--".to_string())
+        };
+        let change = ticker.and_then(|ticker| match (ticker.last, ticker.open_24h) {
+            (Some(last), Some(open)) if open > 0.0 => Some((last - open) / open * 100.0),
+            _ => None,
+        });
+        let (change_label, color) = match change {
+            Some(pct) if pct >= 0.0 => (format!("{pct:+.2}%"), self.theme.success),
+            Some(pct) => (format!("{pct:+.2}%"), self.theme.error),
+            None => ("--".to_string(), self.theme.muted),
+        };
+        let volume = ticker
+            .and_then(|ticker| ticker.volume_24h)
+            .map(format_volume)
+            .unwrap_or_else(|| "--".to_string());
+        Some(Span::styled(
+            format!(
+                "[24h {change_label} H {} L {} Vol {volume}]",
+                price(ticker.and_then(|ticker| ticker.high_24h)),
+                price(ticker.and_then(|ticker| ticker.low_24h)),
+            ),
+            Style::default().fg(color),
+        ))
+    }
+
+    fn rsi_badge(&self) -> Option<Span<'static>> {
+        let inst_id = self
+            .inst_ids
+            .get(self.trade.selected_inst_idx)
+            .or_else(|| self.inst_ids.first())?;
+        let series = price_series::as_slice(self.data.get(inst_id)?);
+        let label = format!("[RSI{} {}", DEFAULT_RSI_PERIOD, inst_id);
+        let Some(value) = indicators::latest_rsi(series, DEFAULT_RSI_PERIOD) else {
+            return Some(Span::styled(
+                format!("{label} --]"),
+                Style::default().fg(self.theme.muted),
+            ));
+        };
+        let color = if value >= 70.0 {
+            self.theme.error
+        } else if value <= 30.0 {
+            self.theme.success
+        } else {
+            self.theme.subtle
+        };
+        Some(Span::styled(
+            format!("{label} {value:.1}]"),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ))
+    }
+
+    // 设置了 ATR 提醒时，超过阈值以警示色显示
+    fn atr_badge(&self) -> Option<Span<'static>> {
+        let inst_id = self
+            .inst_ids
+            .get(self.trade.selected_inst_idx)
+            .or_else(|| self.inst_ids.first())?;
+        let series = price_series::as_slice(self.data.get(inst_id)?);
+        let Some(value) = indicators::latest_atr(series, DEFAULT_ATR_PERIOD) else {
+            return Some(Span::styled(
+                format!("[ATR{DEFAULT_ATR_PERIOD} --]"),
+                Style::default().fg(self.theme.muted),
+            ));
+        };
+        let limit = self.thresholds.get(inst_id).and_then(|t| t.atr);
+        let color = if limit.is_some_and(|limit| value > limit) {
+            self.theme.emphasis
+        } else {
+            self.theme.subtle
+        };
+        Some(Span::styled(
+            format!(
+                "[ATR{DEFAULT_ATR_PERIOD} {}]",
+                self.format_price_for(inst_id, value)
+            ),
+            Style::default().fg(color),
+        ))
+    }
+
+    // 鼠标只用于交易页面的焦点与选中，弹窗打开时忽略点击
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left)
+            || self.view_mode != ViewMode::Trade
+            || self.mouse_blocked()
+        {
+            return;
+        }
+        let position = Position::new(mouse.column, mouse.row);
+        let Some((focus, target)) = self
+            .trade
+            .hit_areas
+            .iter()
+            .find(|hit| hit.area.contains(position))
+            .map(|hit| (hit.focus, hit.row_at(mouse.row)))
+        else {
+            return;
+        };
+        self.trade.select_at(focus, target);
+    }
+
+    fn mouse_blocked(&self) -> bool {
+        self.exit_confirmation
+            || self.help_overlay.is_some()
+            || self.loading_blocks_input()
+            || self.close_all_confirmation
+            || self.reverse_confirmation.is_some()
+            || self.ai_proposal.is_some()
+            || self.order_confirmation.is_some()
+            || self.trade.input.is_some()
+            || self.threshold_input.is_some()
+            || self.instrument_input.is_some()
+            || self.leverage_input.is_some()
+            || self.trade.ai_detail_active()
+            || self.trade.log_detail.is_some()
+    }
+
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<bool> {
         if self.exit_confirmation {
             return self.handle_exit_confirmation_key(key);
@@ -2875,14 +6243,46 @@ impl TuiApp {
                 return Ok(false);
             }
         }
+        if self.help_overlay.is_some() {
+            self.handle_help_overlay_key(key);
+            return Ok(false);
+        }
         if self.loading_blocks_input() && !Self::is_exit_key(&key) {
             self.set_status_message("币种数据正在加载，完成后即可操作. 按 Q/Esc 可退出");
             return Ok(false);
         }
+        if self.close_all_confirmation {
+            self.handle_close_all_confirmation_key(key);
+            return Ok(false);
+        }
+        if self.reverse_confirmation.is_some() {
+            self.handle_reverse_confirmation_key(key);
+            return Ok(false);
+        }
+        if self.ai_proposal.is_some() {
+            self.handle_ai_proposal_key(key);
+            return Ok(false);
+        }
+        if self.order_confirmation.is_some() {
+            self.handle_order_confirmation_key(key);
+            return Ok(false);
+        }
         if self.trade.input.is_some() {
             self.handle_order_input_key(key);
             return Ok(false);
         }
+        if self.threshold_input.is_some() {
+            self.handle_threshold_input_key(key);
+            return Ok(false);
+        }
+        if self.instrument_input.is_some() {
+            self.handle_instrument_input_key(key);
+            return Ok(false);
+        }
+        if self.leverage_input.is_some() {
+            self.handle_leverage_input_key(key);
+            return Ok(false);
+        }
         match key.code {
             KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
                 self.prompt_exit_confirmation();
@@ -2897,16 +6297,61 @@ impl TuiApp {
                         self.set_status_message("返回图表页面 (T)");
                         ViewMode::Chart
                     }
+                    ViewMode::Stats => {
+                        self.set_status_message("进入交易页面 (T)");
+                        ViewMode::Trade
+                    }
                 };
             }
+            KeyCode::Char('?') => {
+                self.help_overlay = Some(HelpOverlayState::default());
+            }
+            KeyCode::Char('u') | KeyCode::Char('U') if self.undo_order.is_some() => {
+                self.undo_last_order();
+            }
+            KeyCode::Char('z') | KeyCode::Char('Z') => {
+                self.size_unit = self.size_unit.toggle();
+                self.label_cache.get_mut().sizes.clear();
+                self.set_status_message(format!("数量单位: {} (Z)", self.size_unit.label()));
+            }
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                if self.view_mode == ViewMode::Stats {
+                    self.view_mode = self.stats_return_view;
+                    self.set_status_message("已关闭盈亏统计 (D)");
+                } else {
+                    self.stats_return_view = self.view_mode;
+                    self.view_mode = ViewMode::Stats;
+                    self.set_status_message("进入盈亏统计 (D)");
+                }
+            }
             _ => match self.view_mode {
                 ViewMode::Chart => self.handle_chart_key(key),
                 ViewMode::Trade => self.handle_trade_key(key),
+                ViewMode::Stats => self.handle_stats_key(key),
             },
         }
         Ok(false)
     }
 
+    fn handle_help_overlay_key(&mut self, key: KeyEvent) {
+        let Some(state) = self.help_overlay.as_mut() else {
+            return;
+        };
+        let page = state.view_height.max(1) as i32;
+        match key.code {
+            KeyCode::Char('?') | KeyCode::Esc => {
+                self.help_overlay = None;
+            }
+            KeyCode::Char('j') | KeyCode::Down => state.scroll_by(1),
+            KeyCode::Char('k') | KeyCode::Up => state.scroll_by(-1),
+            KeyCode::PageDown => state.scroll_by(page),
+            KeyCode::PageUp => state.scroll_by(-page),
+            KeyCode::Home => state.scroll = 0,
+            KeyCode::End => state.scroll = state.max_scroll(),
+            _ => {}
+        }
+    }
+
     fn prompt_exit_confirmation(&mut self) {
         if self.exit_confirmation {
             return;
@@ -2932,11 +6377,75 @@ impl TuiApp {
                 Ok(true)
             }
             KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                self.exit_confirmation = false;
-                self.set_status_message("已取消退出");
-                Ok(false)
+                self.exit_confirmation = false;
+                self.set_status_message("已取消退出");
+                Ok(false)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn handle_close_all_confirmation_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                self.close_all_confirmation = false;
+                self.close_all_positions();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.close_all_confirmation = false;
+                self.set_status_message("已取消一键平仓");
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_ai_proposal_key(&mut self, key: KeyEvent) {
+        let approved = match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => true,
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => false,
+            _ => return,
+        };
+        let Some(proposal) = self.ai_proposal.take() else {
+            return;
+        };
+        let sent = self.command_tx.as_ref().is_some_and(|tx| {
+            tx.send(Command::AiDecisionResolved(proposal.id, approved))
+                .is_ok()
+        });
+        match (sent, approved) {
+            (false, _) => self.set_error_status_message("AI 决策确认发送失败"),
+            (true, true) => self.set_status_message("已确认执行 AI 决策"),
+            (true, false) => self.set_status_message("已拒绝 AI 决策"),
+        }
+    }
+
+    fn handle_order_confirmation_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => {
+                if let Some(staged) = self.order_confirmation.take() {
+                    self.submit_order(staged);
+                }
+            }
+            KeyCode::Esc => {
+                self.order_confirmation = None;
+                self.set_status_message("已取消提交，可继续修改委托");
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_reverse_confirmation_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                if let Some(position) = self.reverse_confirmation.take() {
+                    self.reverse_position(&position);
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.reverse_confirmation = None;
+                self.set_status_message("已取消反手");
             }
-            _ => Ok(false),
+            _ => {}
         }
     }
 
@@ -2944,6 +6453,9 @@ impl TuiApp {
         match key.code {
             KeyCode::Char('n') | KeyCode::Char('N') => {
                 self.normalize = !self.normalize;
+                if self.normalize {
+                    self.log_scale = false;
+                }
                 self.y_zoom = 1.0;
                 self.set_status_message(match (self.normalize, self.multi_axis) {
                     (true, true) => {
@@ -2984,10 +6496,458 @@ impl TuiApp {
                 self.y_zoom = 1.0;
                 self.set_status_message("Reset Y axis (0)");
             }
+            KeyCode::Char('g') | KeyCode::Char('G') => {
+                self.log_scale = !self.log_scale;
+                self.y_zoom = 1.0;
+                if self.log_scale && self.normalize {
+                    self.normalize = false;
+                    self.set_status_message("Log scale enabled; relative change mode disabled (G)");
+                } else if self.log_scale && self.multi_axis_active() {
+                    self.set_status_message(
+                        "Log scale enabled; applies once you leave multi Y mode (G)",
+                    );
+                } else {
+                    self.set_status_message(if self.log_scale {
+                        "Log scale enabled (G)"
+                    } else {
+                        "Log scale disabled (G)"
+                    });
+                }
+            }
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                if self.chart_style == ChartStyle::Candles {
+                    self.chart_style = ChartStyle::Line;
+                    self.set_status_message("Line chart enabled (C)");
+                } else if self.normalize || self.multi_axis_active() {
+                    self.set_error_status_message(
+                        "Candles need absolute single-axis mode; leave N/M modes first (C)",
+                    );
+                } else {
+                    self.chart_style = ChartStyle::Candles;
+                    self.set_status_message(format!(
+                        "Candlestick chart enabled, {} buckets (C)",
+                        self.candle_interval_label()
+                    ));
+                }
+            }
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                self.sma_enabled = !self.sma_enabled;
+                self.set_status_message(if self.sma_enabled {
+                    format!("SMA {} overlay enabled (A)", self.sma_period)
+                } else {
+                    "SMA overlay disabled (A)".to_string()
+                });
+            }
+            KeyCode::Char('b') | KeyCode::Char('B') => {
+                self.bollinger_enabled = !self.bollinger_enabled;
+                self.set_status_message(if !self.bollinger_enabled {
+                    "Bollinger Bands overlay disabled (B)"
+                } else if self.normalize || self.multi_axis_active() {
+                    "Bollinger Bands enabled; shown in absolute single-axis mode only (B)"
+                } else {
+                    "Bollinger Bands overlay enabled (B)"
+                });
+            }
+            KeyCode::Char('o') | KeyCode::Char('O') => {
+                self.macd_enabled = !self.macd_enabled;
+                self.set_status_message(if self.macd_enabled {
+                    "MACD histogram panel enabled (O)"
+                } else {
+                    "MACD histogram panel disabled (O)"
+                });
+            }
+            KeyCode::Char('[') => {
+                self.sma_period = self.sma_period.saturating_sub(SMA_PERIOD_STEP).max(2);
+                self.set_status_message(format!("SMA period set to {} ([ / ])", self.sma_period));
+            }
+            KeyCode::Char(']') => {
+                self.sma_period = (self.sma_period + SMA_PERIOD_STEP).min(500);
+                self.set_status_message(format!("SMA period set to {} ([ / ])", self.sma_period));
+            }
+            KeyCode::Char('i') | KeyCode::Char('I') => {
+                self.candle_interval_idx =
+                    (self.candle_interval_idx + 1) % CANDLE_INTERVALS_SECS.len();
+                self.set_status_message(format!(
+                    "Candle interval set to {} (I)",
+                    self.candle_interval_label()
+                ));
+            }
+            KeyCode::Char('e') | KeyCode::Char('E') => {
+                self.export_selected_series();
+            }
+            KeyCode::Char('w') | KeyCode::Char('W') => {
+                self.open_threshold_dialog();
+            }
+            KeyCode::Char('/') => self.open_instrument_input(),
+            KeyCode::Char('x') | KeyCode::Char('X') => {
+                self.remove_selected_instrument();
+            }
+            KeyCode::Char('K') => {
+                self.move_selected_instrument(-1);
+            }
+            KeyCode::Char('J') => {
+                self.move_selected_instrument(1);
+            }
+            KeyCode::Char('f') | KeyCode::Char('F') => {
+                self.toggle_follow_latest();
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('H') => {
+                self.pan_window(-PAN_FRACTION);
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('L') => {
+                self.pan_window(PAN_FRACTION);
+            }
+            _ => {}
+        }
+    }
+
+    fn open_threshold_dialog(&mut self) {
+        let Some(inst_id) = self
+            .inst_ids
+            .get(self.trade.selected_inst_idx)
+            .or_else(|| self.inst_ids.first())
+            .cloned()
+        else {
+            self.set_error_status_message("No instrument selected for alerts");
+            return;
+        };
+        let current = self.thresholds.get(&inst_id).copied().unwrap_or_default();
+        self.threshold_input = Some(ThresholdInputState::new(inst_id, current));
+    }
+
+    fn handle_threshold_input_key(&mut self, key: KeyEvent) {
+        let Some(input) = self.threshold_input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.threshold_input = None;
+                self.set_status_message("Alert edit cancelled");
+            }
+            KeyCode::Enter => self.submit_threshold_input(),
+            KeyCode::Tab | KeyCode::Down => input.focus_next_field(),
+            KeyCode::BackTab | KeyCode::Up => input.focus_prev_field(),
+            KeyCode::Backspace => {
+                input.active_value_mut().pop();
+                input.error = None;
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => {
+                let field = input.active_value_mut();
+                if c == '.' && field.contains('.') {
+                    return;
+                }
+                field.push(c);
+                input.error = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn submit_threshold_input(&mut self) {
+        let Some(input) = self.threshold_input.as_mut() else {
+            return;
+        };
+        let threshold = match input.parse() {
+            Ok(threshold) => threshold,
+            Err(err) => {
+                input.error = Some(err);
+                return;
+            }
+        };
+        let Some(command_tx) = self.command_tx.as_ref() else {
+            input.error = Some("Alert monitor is not running".to_string());
+            return;
+        };
+        let inst_id = input.inst_id.clone();
+        if command_tx
+            .send(Command::SetThreshold(inst_id.clone(), threshold))
+            .is_err()
+        {
+            input.error = Some("Alert monitor is not running".to_string());
+            return;
+        }
+        self.threshold_input = None;
+        if threshold.is_empty() {
+            self.thresholds.remove(&inst_id);
+        } else {
+            self.thresholds.insert(inst_id.clone(), threshold);
+        }
+        let saved = self
+            .run_config
+            .as_mut()
+            .map(|config| config.save_threshold(&inst_id, threshold));
+        match saved {
+            Some(Err(err)) => self.set_error_status_message(format!(
+                "Alerts for {inst_id} applied but not saved: {err}"
+            )),
+            _ if threshold.is_empty() => {
+                self.set_status_message(format!("Alerts cleared for {inst_id} (W)"))
+            }
+            _ => self.set_status_message(format!(
+                "Alerts for {inst_id} set: {} (W)",
+                self.describe_threshold(&inst_id, &threshold)
+            )),
+        }
+    }
+
+    fn handle_instrument_input_key(&mut self, key: KeyEvent) {
+        let Some(input) = self.instrument_input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                let origin = input.origin_idx;
+                self.instrument_input = None;
+                self.trade.selected_inst_idx = origin;
+                self.trade.ensure_selection(&self.inst_ids);
+                self.set_status_message("Instrument jump cancelled");
+            }
+            KeyCode::Enter => self.submit_instrument_input(),
+            KeyCode::Tab => self.confirm_instrument_jump(),
+            KeyCode::Backspace => {
+                input.value.pop();
+                input.error = None;
+                self.jump_to_instrument_prefix();
+            }
+            KeyCode::Char(c) if c.is_ascii_alphanumeric() || c == '-' => {
+                input.value.push(c.to_ascii_uppercase());
+                input.error = None;
+                self.jump_to_instrument_prefix();
+            }
             _ => {}
         }
     }
 
+    fn open_instrument_input(&mut self) {
+        self.instrument_input = Some(InstrumentInputState {
+            origin_idx: self.trade.selected_inst_idx,
+            ..InstrumentInputState::default()
+        });
+    }
+
+    // 输入过程中实时选中第一个前缀匹配的合约，图表与交易页共用同一选中项
+    fn jump_to_instrument_prefix(&mut self) {
+        let Some(input) = self.instrument_input.as_mut() else {
+            return;
+        };
+        let found = if input.value.is_empty() {
+            None
+        } else {
+            self.inst_ids
+                .iter()
+                .position(|inst_id| inst_id.to_ascii_uppercase().starts_with(&input.value))
+        };
+        input.matched = found.map(|idx| self.inst_ids[idx].clone());
+        self.trade.selected_inst_idx = found.unwrap_or(input.origin_idx);
+        self.trade.ensure_selection(&self.inst_ids);
+    }
+
+    // Tab 明确跳转到当前前缀匹配的合约
+    fn confirm_instrument_jump(&mut self) {
+        let Some(input) = self.instrument_input.as_mut() else {
+            return;
+        };
+        match input.matched.take() {
+            Some(inst_id) => {
+                self.instrument_input = None;
+                self.set_status_message(format!("Jumped to {inst_id} (/)"));
+            }
+            None => input.error = Some("No charted instrument matches".to_string()),
+        }
+    }
+
+    // Enter 只在与已有合约完全一致时跳转，否则按输入添加，避免前缀匹配挡住添加如 BTC-USDT
+    fn submit_instrument_input(&mut self) {
+        let Some(input) = self.instrument_input.as_mut() else {
+            return;
+        };
+        let inst_id = input.value.trim_matches('-').to_string();
+        if input
+            .matched
+            .as_ref()
+            .is_some_and(|matched| matched.eq_ignore_ascii_case(&inst_id))
+        {
+            self.confirm_instrument_jump();
+            return;
+        }
+        if inst_id.is_empty() {
+            input.error = Some("Instrument id is required".to_string());
+            return;
+        }
+        if self.inst_ids.contains(&inst_id) {
+            input.error = Some(format!("{inst_id} is already on the chart"));
+            return;
+        }
+        if self.pending_instruments.contains(&inst_id) {
+            input.error = Some(format!("{inst_id} is already being added"));
+            return;
+        }
+        let sent = self
+            .command_tx
+            .as_ref()
+            .is_some_and(|tx| tx.send(Command::AddInstrument(inst_id.clone())).is_ok());
+        if !sent {
+            input.error = Some("Market data service is not running".to_string());
+            return;
+        }
+        // 添加时撤销输入过程中的前缀预选
+        self.trade.selected_inst_idx = input.origin_idx;
+        self.trade.ensure_selection(&self.inst_ids);
+        self.instrument_input = None;
+        self.pending_instruments.insert(inst_id.clone());
+        self.set_status_message(format!("Validating {inst_id}..."));
+    }
+
+    fn remove_selected_instrument(&mut self) {
+        let Some(inst_id) = self.inst_ids.get(self.trade.selected_inst_idx).cloned() else {
+            self.set_error_status_message("No instrument selected to remove");
+            return;
+        };
+        if self.inst_ids.len() <= 1 {
+            self.set_error_status_message("Cannot remove the last instrument (X)");
+            return;
+        }
+        if let Some(tx) = self.command_tx.as_ref() {
+            let _ = tx.send(Command::RemoveInstrument(inst_id.clone()));
+        }
+        self.inst_ids.retain(|id| *id != inst_id);
+        self.data.remove(&inst_id);
+        self.colors.remove(&inst_id);
+        self.latest_prices.remove(&inst_id);
+        self.price_updated_at.remove(&inst_id);
+        self.price_precision.remove(&inst_id);
+        self.order_books.remove(&inst_id);
+        self.public_trades.remove(&inst_id);
+        self.tickers.remove(&inst_id);
+        self.long_short_ratios.remove(&inst_id);
+        self.funding_rates.remove(&inst_id);
+        self.label_cache.get_mut().prices.remove(&inst_id);
+        if self.last_update.as_deref() == Some(inst_id.as_str()) {
+            self.last_update = None;
+        }
+        self.removed_instruments.insert(inst_id.clone());
+        self.trade.ensure_selection(&self.inst_ids);
+        self.update_window();
+        self.set_status_message(format!("Removed {inst_id} from chart (X)"));
+    }
+
+    fn move_selected_instrument(&mut self, delta: isize) {
+        let len = self.inst_ids.len();
+        if len < 2 {
+            self.set_error_status_message("Need at least two instruments to reorder (J/K)");
+            return;
+        }
+        let from = self.trade.selected_inst_idx.min(len - 1);
+        let Some(to) = from.checked_add_signed(delta).filter(|to| *to < len) else {
+            self.set_status_message(if delta < 0 {
+                "Instrument is already first (K)"
+            } else {
+                "Instrument is already last (J)"
+            });
+            return;
+        };
+        self.inst_ids.swap(from, to);
+        self.trade.selected_inst_idx = to;
+        self.trade.ensure_selection(&self.inst_ids);
+        let inst_id = self.inst_ids[to].clone();
+        let saved = self
+            .run_config
+            .as_mut()
+            .map(|config| config.save_instrument_order(&self.inst_ids));
+        match saved {
+            Some(Err(err)) => self.set_error_status_message(format!(
+                "Moved {inst_id} to #{} but order not saved: {err}",
+                to + 1
+            )),
+            _ => self.set_status_message(format!("Moved {inst_id} to #{} of {len} (J/K)", to + 1)),
+        }
+    }
+
+    fn describe_threshold(&self, inst_id: &str, threshold: &PriceThreshold) -> String {
+        let mut parts = Vec::new();
+        if let Some(lower) = threshold.lower {
+            parts.push(format!("below {}", self.format_price_for(inst_id, lower)));
+        }
+        if let Some(upper) = threshold.upper {
+            parts.push(format!("above {}", self.format_price_for(inst_id, upper)));
+        }
+        if let Some(change_pct) = threshold.change_pct {
+            parts.push(format!("move ±{change_pct}%"));
+        }
+        if let Some(atr) = threshold.atr {
+            parts.push(format!(
+                "ATR{DEFAULT_ATR_PERIOD} above {}",
+                self.format_price_for(inst_id, atr)
+            ));
+        }
+        if let Some(secs) = threshold.cooldown_secs {
+            parts.push(format!("cooldown {secs}s"));
+        }
+        parts.join(", ")
+    }
+
+    fn handle_stats_key(&mut self, key: KeyEvent) {
+        if let KeyCode::Char('p') | KeyCode::Char('P') | KeyCode::Tab = key.code {
+            self.stats_period = self.stats_period.next();
+            self.set_status_message(format!("统计区间：{} (P)", self.stats_period.label()));
+        }
+    }
+
+    fn export_selected_series(&mut self) {
+        let Some(inst_id) = self
+            .inst_ids
+            .get(self.trade.selected_inst_idx)
+            .or_else(|| self.inst_ids.first())
+            .cloned()
+        else {
+            self.set_error_status_message("No instrument selected to export");
+            return;
+        };
+        let series = self
+            .data
+            .get(&inst_id)
+            .map(price_series::as_slice)
+            .unwrap_or(&[]);
+        if series.is_empty() {
+            self.set_error_status_message(format!("No price data captured for {inst_id} yet"));
+            return;
+        }
+        match export::write_price_csv(&self.export_dir, &inst_id, series, self.timezone) {
+            Ok(path) => self.set_status_message(format!(
+                "Exported {} points of {inst_id} to {} (E)",
+                series.len(),
+                path.display()
+            )),
+            Err(err) => self.set_error_status_message(format!("CSV export failed: {err}")),
+        }
+    }
+
+    // 优先导出决策文件中的全部记录，未启用持久化时退回内存中保留的记录
+    fn export_ai_decisions(&mut self, format: AiExportFormat) {
+        let records = match &self.trade.ai_store {
+            Some(store) => match store.load_all() {
+                Ok(records) => records,
+                Err(err) => {
+                    self.set_error_status_message(format!("读取 AI 决策记录失败: {err}"));
+                    return;
+                }
+            },
+            None => self.trade.ai_insights.clone(),
+        };
+        if records.is_empty() {
+            self.set_error_status_message("暂无 AI 决策记录可导出");
+            return;
+        }
+        match export::write_ai_decisions(&self.export_dir, &records, format, self.timezone) {
+            Ok(path) => self.set_status_message(format!(
+                "已导出 {} 条 AI 决策到 {}",
+                records.len(),
+                path.display()
+            )),
+            Err(err) => self.set_error_status_message(format!("AI 决策导出失败: {err}")),
+        }
+    }
+
     fn handle_trade_key(&mut self, key: KeyEvent) {
         if self.trade.ai_detail_active() {
             match key.code {
@@ -3015,9 +6975,11 @@ impl TuiApp {
         match key.code {
             KeyCode::Tab => {
                 self.trade.cycle_focus(false);
+                self.load_history_on_focus();
             }
             KeyCode::BackTab => {
                 self.trade.cycle_focus(true);
+                self.load_history_on_focus();
             }
             KeyCode::Up => {
                 self.trade.move_focus(&self.inst_ids, -1);
@@ -3037,6 +6999,10 @@ impl TuiApp {
             KeyCode::Char('s') | KeyCode::Char('S') => {
                 self.start_order_entry(TradeSide::Sell);
             }
+            KeyCode::Char('p') | KeyCode::Char('P') if self.trade.focus == TradeFocus::History => {
+                self.trade.history_range = self.trade.history_range.next();
+                self.request_order_history();
+            }
             KeyCode::Char('p') | KeyCode::Char('P') => {
                 if self.trade.focus == TradeFocus::Positions {
                     self.start_position_close(OrderIntent::TakeProfit);
@@ -3047,16 +7013,79 @@ impl TuiApp {
                     self.start_position_close(OrderIntent::StopLoss);
                 }
             }
-            KeyCode::Char('c') | KeyCode::Char('C') => {
-                if self.trade.focus == TradeFocus::Orders {
-                    self.cancel_selected_order();
+            KeyCode::Char('g') | KeyCode::Char('G') => {
+                if self.trade.focus == TradeFocus::Positions {
+                    self.start_position_close(OrderIntent::TrailingStop);
+                }
+            }
+            KeyCode::Char(c @ '1'..='4') => {
+                if self.trade.focus == TradeFocus::Positions {
+                    let percent = (c as u8 - b'0') * 25;
+                    self.start_position_quick_close(percent);
                 }
             }
-            KeyCode::Char('r') | KeyCode::Char('R') => {
+            KeyCode::Char('v') | KeyCode::Char('V')
+                if self.trade.focus == TradeFocus::Positions =>
+            {
+                self.prompt_reverse_confirmation();
+            }
+            KeyCode::Char('+') | KeyCode::Char('=')
+                if self.trade.focus == TradeFocus::Positions =>
+            {
+                self.start_position_copy_order();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N')
+                if self.trade.focus == TradeFocus::Positions =>
+            {
+                let net = self.trade.toggle_net_positions();
+                self.set_status_message(if net {
+                    "持仓按合约汇总净持仓，选中合约展开各方向明细 (N)"
+                } else {
+                    "持仓按方向逐条显示 (N)"
+                });
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') if self.trade.focus == TradeFocus::History => {
+                self.trade.toggle_history_view();
+            }
+            KeyCode::Char('c') => {
                 if self.trade.focus == TradeFocus::Orders {
-                    self.start_order_replace();
+                    self.cancel_selected_order();
                 }
             }
+            KeyCode::Char('C') => {
+                self.prompt_close_all_confirmation();
+            }
+            KeyCode::Char('w') | KeyCode::Char('W') => self.toggle_all_positions(),
+            KeyCode::Char('/') => self.open_instrument_input(),
+            KeyCode::Char('r') | KeyCode::Char('R') => match self.trade.focus {
+                TradeFocus::Orders => self.start_order_replace(),
+                TradeFocus::History => self.request_order_history(),
+                TradeFocus::AiInsights => self.request_ai_analysis(),
+                _ => {}
+            },
+            KeyCode::Char('x') | KeyCode::Char('X') if self.trade.focus == TradeFocus::Orders => {
+                self.cancel_all_orders();
+            }
+            KeyCode::Char('i') | KeyCode::Char('I') if self.trade.focus == TradeFocus::Logs => {
+                self.trade.cycle_log_inst_filter(&self.inst_ids);
+            }
+            KeyCode::Char('f') | KeyCode::Char('F') if self.trade.focus == TradeFocus::Logs => {
+                self.trade.cycle_log_kind_filter();
+            }
+            KeyCode::Char('a') | KeyCode::Char('A')
+                if matches!(
+                    self.trade.focus,
+                    TradeFocus::Positions | TradeFocus::Instruments
+                ) =>
+            {
+                self.open_leverage_dialog();
+            }
+            KeyCode::Char('e') if self.trade.focus == TradeFocus::AiInsights => {
+                self.export_ai_decisions(AiExportFormat::Csv);
+            }
+            KeyCode::Char('E') if self.trade.focus == TradeFocus::AiInsights => {
+                self.export_ai_decisions(AiExportFormat::Json);
+            }
             KeyCode::Char('o') | KeyCode::Char('O') => match self.trade.focus {
                 TradeFocus::Logs => self.trade.toggle_log_detail(),
                 TradeFocus::AiInsights => self.trade.toggle_ai_detail(),
@@ -3067,6 +7096,7 @@ impl TuiApp {
                 TradeFocus::Orders => self.trade.page_scroll_orders(-1),
                 TradeFocus::Logs => self.trade.page_scroll_logs(-1),
                 TradeFocus::AiInsights => self.trade.page_scroll_ai(-1),
+                TradeFocus::History => self.trade.page_scroll_history(-1),
                 TradeFocus::Instruments => {}
             },
             KeyCode::PageDown => match self.trade.focus {
@@ -3074,6 +7104,7 @@ impl TuiApp {
                 TradeFocus::Orders => self.trade.page_scroll_orders(1),
                 TradeFocus::Logs => self.trade.page_scroll_logs(1),
                 TradeFocus::AiInsights => self.trade.page_scroll_ai(1),
+                TradeFocus::History => self.trade.page_scroll_history(1),
                 TradeFocus::Instruments => {}
             },
             KeyCode::Home => match self.trade.focus {
@@ -3081,6 +7112,7 @@ impl TuiApp {
                 TradeFocus::Orders => self.trade.scroll_orders_to_start(),
                 TradeFocus::Logs => self.trade.scroll_logs_to_start(),
                 TradeFocus::AiInsights => self.trade.scroll_ai_to_start(),
+                TradeFocus::History => self.trade.scroll_history_to_start(),
                 TradeFocus::Instruments => {}
             },
             KeyCode::End => match self.trade.focus {
@@ -3088,12 +7120,64 @@ impl TuiApp {
                 TradeFocus::Orders => self.trade.scroll_orders_to_end(),
                 TradeFocus::Logs => self.trade.scroll_logs_to_end(),
                 TradeFocus::AiInsights => self.trade.scroll_ai_to_end(),
+                TradeFocus::History => self.trade.scroll_history_to_end(),
                 TradeFocus::Instruments => {}
             },
             _ => {}
         }
     }
 
+    fn request_ai_analysis(&mut self) {
+        if !self.trade.ai_panel_enabled() {
+            self.set_error_status_message("未启用 AI，无法立即分析");
+            return;
+        }
+        if self.trade.ai_pending.is_some() {
+            self.set_status_message(format!("{} 正在分析，请稍候", self.trade.ai_label()));
+            return;
+        }
+        let sent = self
+            .command_tx
+            .as_ref()
+            .is_some_and(|tx| tx.send(Command::AiAnalyzeNow).is_ok());
+        if sent {
+            self.set_status_message(format!("已请求 {} 立即分析", self.trade.ai_label()));
+        } else {
+            self.set_error_status_message("AI 分析通道不可用");
+        }
+    }
+
+    fn load_history_on_focus(&mut self) {
+        if self.trade.focus == TradeFocus::History
+            && self.trade.history.is_none()
+            && !self.trade.history_loading
+        {
+            self.request_order_history();
+        }
+    }
+
+    fn request_order_history(&mut self) {
+        if !self.trade.trading_enabled() {
+            self.set_error_status_message("未配置 OKX API，无法查询历史记录");
+            return;
+        }
+        let Some(tx) = self.command_tx.as_ref() else {
+            return;
+        };
+        let end_ms = Utc::now().timestamp_millis();
+        let begin_ms = end_ms - self.trade.history_range.days() * 24 * 60 * 60 * 1000;
+        let _ = tx.send(Command::FetchOrderHistory(HistoryRequest {
+            inst_ids: self.inst_ids.clone(),
+            begin_ms,
+            end_ms,
+        }));
+        self.trade.history_loading = true;
+        self.set_status_message(format!(
+            "正在加载{}历史记录…",
+            self.trade.history_range.label()
+        ));
+    }
+
     fn start_order_entry(&mut self, side: TradeSide) {
         if !self.trade.trading_enabled() {
             self.set_error_status_message("未配置 OKX API，无法下单");
@@ -3118,13 +7202,64 @@ impl TuiApp {
             .latest_prices
             .get(&inst_id)
             .map(|value| self.format_price_for(&inst_id, *value))
-            .unwrap_or_else(|| "".to_string());
+            .unwrap_or_else(|| "".to_string());
+        self.open_order_dialog(
+            inst_id,
+            side,
+            price,
+            "1".to_string(),
+            None,
+            OrderIntent::Manual,
+            TradeOrderKind::Regular,
+            false,
+            None,
+            None,
+            leverage,
+        );
+    }
+
+    fn start_position_close(&mut self, intent: OrderIntent) {
+        self.open_position_close_dialog(intent, 1.0);
+    }
+
+    fn start_position_quick_close(&mut self, percent: u8) {
+        if self.open_position_close_dialog(OrderIntent::Manual, f64::from(percent) / 100.0) {
+            self.set_status_message(format!("快速平仓 {percent}%"));
+        }
+    }
+
+    // 按选中持仓的合约、方向、杠杆与数量预填同向加仓委托
+    fn start_position_copy_order(&mut self) {
+        if !self.trade.trading_enabled() {
+            self.set_error_status_message("未配置 OKX API，无法下单");
+            return;
+        }
+        let Some(position) = self.trade.selected_position().cloned() else {
+            self.set_error_status_message("当前无可操作的持仓");
+            return;
+        };
+        let mode = self.trade.position_mode;
+        let side = match Self::closing_side_for_position(&position, mode) {
+            TradeSide::Buy => TradeSide::Sell,
+            TradeSide::Sell => TradeSide::Buy,
+        };
+        let inst_id = position.inst_id.clone();
+        let price = self
+            .latest_prices
+            .get(&inst_id)
+            .map(|value| self.format_price_for(&inst_id, *value))
+            .unwrap_or_default();
+        let size = self.format_contract_size(&inst_id, position.size.abs());
+        let pos_side = Self::pos_side_for_position(&position, mode);
+        let leverage = position
+            .lever
+            .or_else(|| self.trade.leverage_for_inst(&inst_id, pos_side.as_deref()));
         self.open_order_dialog(
             inst_id,
             side,
             price,
-            "1".to_string(),
-            None,
+            size,
+            pos_side,
             OrderIntent::Manual,
             TradeOrderKind::Regular,
             false,
@@ -3132,32 +7267,45 @@ impl TuiApp {
             None,
             leverage,
         );
+        self.set_status_message(format!(
+            "已按持仓预填{}委托，确认后提交",
+            Self::side_label(side)
+        ));
     }
 
-    fn start_position_close(&mut self, intent: OrderIntent) {
+    fn open_position_close_dialog(&mut self, intent: OrderIntent, fraction: f64) -> bool {
         if !self.trade.trading_enabled() {
             self.set_error_status_message("未配置 OKX API，无法下单");
-            return;
+            return false;
         }
         let position = match self.trade.selected_position() {
             Some(position) => position.clone(),
             None => {
                 self.set_error_status_message("当前无可操作的持仓");
-                return;
+                return false;
             }
         };
-        let side = Self::closing_side_for_position(&position);
+        let side = Self::closing_side_for_position(&position, self.trade.position_mode);
         let inst_id = position.inst_id.clone();
-        let price = self
-            .latest_prices
-            .get(&inst_id)
-            .map(|value| self.format_price_for(&inst_id, *value))
-            .unwrap_or_else(|| "".to_string());
-        let size = self.format_contract_size(&position.inst_id, position.size.abs());
-        let pos_side = Self::pos_side_for_position(&position);
+        // 移动止损的激活价可选，默认留空即立即生效
+        let price = if intent == OrderIntent::TrailingStop {
+            String::new()
+        } else {
+            self.latest_prices
+                .get(&inst_id)
+                .map(|value| self.format_price_for(&inst_id, *value))
+                .unwrap_or_else(|| "".to_string())
+        };
+        let mut contracts = position.size.abs() * fraction;
+        if let Some(market) = self.trade.markets.get(&inst_id) {
+            contracts = market.round_size(contracts);
+        }
+        let size = self.format_contract_size(&position.inst_id, contracts);
+        let pos_side = Self::pos_side_for_position(&position, self.trade.position_mode);
         let tag = match intent {
             OrderIntent::TakeProfit => Some("tp".to_string()),
             OrderIntent::StopLoss => Some("sl".to_string()),
+            OrderIntent::TrailingStop => Some("ts".to_string()),
             _ => None,
         };
         let leverage = position.lever;
@@ -3171,6 +7319,7 @@ impl TuiApp {
             match intent {
                 OrderIntent::TakeProfit => TradeOrderKind::TakeProfit,
                 OrderIntent::StopLoss => TradeOrderKind::StopLoss,
+                OrderIntent::TrailingStop => TradeOrderKind::TrailingStop,
                 _ => TradeOrderKind::Regular,
             },
             true,
@@ -3178,6 +7327,7 @@ impl TuiApp {
             None,
             leverage,
         );
+        true
     }
 
     fn start_order_replace(&mut self) {
@@ -3231,6 +7381,19 @@ impl TuiApp {
             Some(order.ord_id.clone()),
             leverage,
         );
+        if let (Some(input), Some(callback)) = (self.trade.input.as_mut(), order.callback) {
+            input.callback = match callback {
+                TrailingCallback::Ratio(ratio) => {
+                    format!(
+                        "{}%",
+                        Self::trim_formatted_number(format!("{:.4}", ratio * 100.0))
+                    )
+                }
+                TrailingCallback::Spread(spread) => {
+                    Self::trim_formatted_number(format!("{spread:.8}"))
+                }
+            };
+        }
     }
 
     fn cancel_selected_order(&mut self) {
@@ -3275,6 +7438,443 @@ impl TuiApp {
         }
     }
 
+    fn open_leverage_dialog(&mut self) {
+        if !self.trade.trading_enabled() {
+            self.set_error_status_message("未配置 OKX API，无法调整杠杆");
+            return;
+        }
+        let (inst_id, pos_side, margin_mode) = if self.trade.focus == TradeFocus::Positions {
+            let Some(position) = self.trade.selected_position() else {
+                self.set_error_status_message("当前无持仓可调整杠杆");
+                return;
+            };
+            // 仅双向持仓需要 posSide，net 模式下不传
+            let pos_side = position
+                .pos_side
+                .clone()
+                .filter(|side| matches!(side.as_str(), "long" | "short"));
+            let margin_mode = position
+                .mgn_mode
+                .or_else(|| self.trade.margin_modes.get(&position.inst_id).copied());
+            (position.inst_id.clone(), pos_side, margin_mode)
+        } else {
+            let Some(inst_id) = self.inst_ids.get(self.trade.selected_inst_idx).cloned() else {
+                self.set_error_status_message("当前无合约可调整杠杆");
+                return;
+            };
+            let margin_mode = self.trade.margin_modes.get(&inst_id).copied();
+            (inst_id, None, margin_mode)
+        };
+        if okx::is_spot_inst(&inst_id) {
+            self.set_error_status_message(format!("{inst_id} 为现货，不支持调整杠杆"));
+            return;
+        }
+        let value = self
+            .trade
+            .leverage_for_inst(&inst_id, pos_side.as_deref())
+            .map(Self::leverage_input_value)
+            .unwrap_or_default();
+        self.leverage_input = Some(LeverageInputState {
+            inst_id,
+            pos_side,
+            margin_mode,
+            value,
+            error: None,
+        });
+    }
+
+    fn handle_leverage_input_key(&mut self, key: KeyEvent) {
+        let Some(input) = self.leverage_input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.leverage_input = None;
+                self.set_status_message("已取消调整杠杆");
+            }
+            KeyCode::Enter => self.submit_leverage_input(),
+            KeyCode::Backspace => {
+                input.value.pop();
+                input.error = None;
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => {
+                if c == '.' && input.value.contains('.') {
+                    return;
+                }
+                input.value.push(c);
+                input.error = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn submit_leverage_input(&mut self) {
+        let Some(input) = self.leverage_input.as_mut() else {
+            return;
+        };
+        let lever = match input.value.trim().parse::<f64>() {
+            Ok(value) if value > 0.0 => value,
+            Ok(_) => {
+                input.error = Some("杠杆必须为正数".to_string());
+                return;
+            }
+            Err(_) => {
+                input.error = Some("杠杆格式无效".to_string());
+                return;
+            }
+        };
+        if let Err(err) = self.trade.order_limits.check(0.0, Some(lever)) {
+            input.error = Some(err);
+            return;
+        }
+        let Some(sender) = self.trade.order_sender() else {
+            input.error = Some("交易通道不可用".to_string());
+            return;
+        };
+        let request = TradingCommand::SetLeverage(SetLeverageRequest {
+            inst_id: input.inst_id.clone(),
+            lever,
+            pos_side: input.pos_side.clone(),
+            margin_mode: input.margin_mode,
+        });
+        match sender.try_send(request) {
+            Ok(_) => {
+                let inst_id = input.inst_id.clone();
+                self.leverage_input = None;
+                self.set_status_message(format!(
+                    "已提交杠杆调整 {inst_id} → {}x",
+                    Self::leverage_input_value(lever)
+                ));
+            }
+            Err(TrySendError::Closed(_)) => {
+                input.error = Some("交易通道已关闭".to_string());
+            }
+            Err(TrySendError::Full(_)) => {
+                input.error = Some("交易请求过多，请稍后再试".to_string());
+            }
+        }
+    }
+
+    fn closable_positions(&self) -> impl Iterator<Item = &PositionInfo> {
+        self.trade
+            .positions
+            .iter()
+            .filter(|position| position.size.abs() > f64::EPSILON)
+    }
+
+    fn prompt_close_all_confirmation(&mut self) {
+        if !self.trade.trading_enabled() {
+            self.set_error_status_message("未配置 OKX API，无法平仓");
+            return;
+        }
+        if self.closable_positions().next().is_none() {
+            self.set_error_status_message("当前无持仓可平");
+            return;
+        }
+        self.close_all_confirmation = true;
+        self.set_status_message("确认一键平仓？Y/Enter 确认 · N/Esc 取消");
+    }
+
+    fn prompt_reverse_confirmation(&mut self) {
+        if !self.trade.trading_enabled() {
+            self.set_error_status_message("未配置 OKX API，无法反手");
+            return;
+        }
+        let Some(position) = self.trade.selected_position().cloned() else {
+            self.set_error_status_message("当前无可操作的持仓");
+            return;
+        };
+        if position.size == 0.0 {
+            self.set_error_status_message("持仓数量为 0，无法反手");
+            return;
+        }
+        if okx::is_spot_inst(&position.inst_id) {
+            self.set_error_status_message("现货不支持反手");
+            return;
+        }
+        self.reverse_confirmation = Some(position);
+        self.set_status_message("确认反手？Y/Enter 确认 · N/Esc 取消");
+    }
+
+    // 手动开仓方向的委托统一按下单上限校验；price 为 0（市价）时按最新价估算名义价值
+    fn check_order_limits(
+        limits: &OrderLimits,
+        price: f64,
+        latest: Option<f64>,
+        contracts: f64,
+        ct_val: Option<f64>,
+        leverage: Option<f64>,
+    ) -> Result<(), String> {
+        let price = if price > 0.0 {
+            price
+        } else {
+            latest.unwrap_or_default()
+        };
+        // 名义价值按 0 计算会绕过上限，缺少价格或面值时直接拒绝
+        let notional = match ct_val.filter(|ct_val| *ct_val > 0.0) {
+            _ if limits.max_notional.is_none() => 0.0,
+            _ if price <= 0.0 => return Err("暂无最新价，无法校验名义价值上限".to_string()),
+            Some(ct_val) => price * contracts * ct_val,
+            None => return Err("无法获取合约面值，无法校验名义价值上限".to_string()),
+        };
+        limits.check(notional, leverage)
+    }
+
+    fn is_hedge_position(position: &PositionInfo) -> bool {
+        matches!(position.pos_side.as_deref(), Some("long" | "short"))
+    }
+
+    // 双向持仓：先只减仓平掉原方向，再在反方向开同等数量；单向持仓：一笔两倍数量的反向单
+    fn reverse_position(&mut self, position: &PositionInfo) {
+        let Some(sender) = self.trade.order_sender().cloned() else {
+            self.set_error_status_message("交易通道不可用");
+            return;
+        };
+        let inst_id = position.inst_id.clone();
+        let mut size = position.size.abs();
+        if let Some(market) = self.trade.markets.get(&inst_id) {
+            size = market.round_size(size);
+        }
+        let price = self
+            .latest_prices
+            .get(&inst_id)
+            .copied()
+            .or(position.avg_px)
+            .unwrap_or_default();
+        // 反手新开的敞口与原持仓数量相同（单向持仓的两倍数量单有一半用于平仓）
+        let ct_val = self.trade.markets.get(&inst_id).map(|market| market.ct_val);
+        let limits = Self::check_order_limits(
+            &self.trade.order_limits,
+            price,
+            None,
+            size,
+            ct_val,
+            position.lever,
+        );
+        if let Err(err) = limits {
+            self.set_error_status_message(format!("反手被拒绝：{err}"));
+            return;
+        }
+        let side = Self::closing_side_for_position(position, self.trade.position_mode);
+        let order = |size: f64, pos_side: Option<String>, reduce_only: bool| TradeRequest {
+            inst_id: inst_id.clone(),
+            side,
+            price,
+            size,
+            ord_type: Some(TradeOrderType::Market),
+            pos_side,
+            reduce_only,
+            tag: None,
+            operator: TradeOperator::Manual,
+            leverage: position.lever,
+            kind: TradeOrderKind::Regular,
+            margin_mode: position.mgn_mode,
+            callback: None,
+            bracket: None,
+            cl_ord_id: None,
+        };
+        let hedge_side = match self.trade.position_mode {
+            PositionMode::LongShort => position.pos_side.as_deref(),
+            PositionMode::Net => None,
+        };
+        let requests = match hedge_side {
+            Some("long") => vec![
+                order(size, Some("long".to_string()), true),
+                order(size, Some("short".to_string()), false),
+            ],
+            Some("short") => vec![
+                order(size, Some("short".to_string()), true),
+                order(size, Some("long".to_string()), false),
+            ],
+            pos_side => vec![order(size * 2.0, pos_side.map(str::to_string), false)],
+        };
+        for request in requests {
+            if sender.try_send(TradingCommand::Place(request)).is_err() {
+                self.set_error_status_message("交易通道繁忙，反手未完整提交，请检查持仓");
+                return;
+            }
+        }
+        self.set_status_message(format!(
+            "已提交 {inst_id} 反手市价单，保证金不足时 OKX 拒单原因会显示在委托日志"
+        ));
+    }
+
+    fn close_all_positions(&mut self) {
+        let Some(sender) = self.trade.order_sender().cloned() else {
+            self.set_error_status_message("交易通道不可用");
+            return;
+        };
+        let positions = self.closable_positions().cloned().collect::<Vec<_>>();
+        let mut submitted = 0;
+        let mut failed = 0;
+        for position in positions {
+            let inst_id = position.inst_id.clone();
+            let mut size = position.size.abs();
+            if let Some(market) = self.trade.markets.get(&inst_id) {
+                size = market.round_size(size);
+            }
+            let price = self
+                .latest_prices
+                .get(&inst_id)
+                .copied()
+                .or(position.avg_px)
+                .unwrap_or_default();
+            let request = TradeRequest {
+                inst_id,
+                side: Self::closing_side_for_position(&position, self.trade.position_mode),
+                price,
+                size,
+                ord_type: Some(TradeOrderType::Market),
+                pos_side: Self::pos_side_for_position(&position, self.trade.position_mode),
+                reduce_only: true,
+                tag: None,
+                operator: TradeOperator::Manual,
+                leverage: position.lever,
+                kind: TradeOrderKind::Regular,
+                margin_mode: position.mgn_mode,
+                callback: None,
+                bracket: None,
+                cl_ord_id: None,
+            };
+            match sender.try_send(TradingCommand::Place(request)) {
+                Ok(_) => submitted += 1,
+                Err(_) => failed += 1,
+            }
+        }
+        if failed > 0 {
+            self.set_error_status_message(format!(
+                "一键平仓：已提交 {submitted} 笔市价平仓，{failed} 笔因交易通道繁忙未提交"
+            ));
+        } else {
+            self.set_status_message(format!("一键平仓：已提交 {submitted} 笔市价平仓"));
+        }
+    }
+
+    fn cancel_all_orders(&mut self) {
+        if !self.trade.trading_enabled() {
+            self.set_error_status_message("未配置 OKX API，无法撤单");
+            return;
+        }
+        let inst_id = match self
+            .trade
+            .selected_order()
+            .map(|order| order.inst_id.clone())
+            .or_else(|| self.trade.selected_inst(&self.inst_ids).map(str::to_string))
+        {
+            Some(inst_id) => inst_id,
+            None => {
+                self.set_error_status_message("当前无挂单可撤");
+                return;
+            }
+        };
+        let sender = match self.trade.order_sender() {
+            Some(sender) => sender,
+            None => {
+                self.set_error_status_message("交易通道不可用");
+                return;
+            }
+        };
+        let request = TradingCommand::CancelAll(CancelAllRequest {
+            inst_id: inst_id.clone(),
+            operator: TradeOperator::Manual,
+        });
+        match sender.try_send(request) {
+            Ok(_) => {
+                self.set_status_message(format!("已提交 {inst_id} 全部挂单的批量撤单请求"));
+            }
+            Err(TrySendError::Closed(_)) => {
+                self.set_error_status_message("交易通道已关闭");
+            }
+            Err(TrySendError::Full(_)) => {
+                self.set_error_status_message("交易请求过多，请稍后再试");
+            }
+        }
+    }
+
+    // 退出前撤销所有跟踪合约的挂单，返回的摘要在恢复终端后打印并写入日志
+    pub async fn cancel_orders_on_exit(
+        &mut self,
+        mut rx: broadcast::Receiver<Command>,
+        timeout: Duration,
+    ) -> Vec<String> {
+        let Some(sender) = self.trade.order_sender().cloned() else {
+            return vec!["退出撤单：未配置 OKX API，已跳过".to_string()];
+        };
+        let mut summary = Vec::new();
+        let mut pending = HashSet::new();
+        for inst_id in &self.inst_ids {
+            let request = TradingCommand::CancelAll(CancelAllRequest {
+                inst_id: inst_id.clone(),
+                operator: TradeOperator::Custom(EXIT_CANCEL_OPERATOR.to_string()),
+            });
+            match sender.try_send(request) {
+                Ok(_) => {
+                    pending.insert(inst_id.clone());
+                }
+                Err(err) => summary.push(format!("退出撤单：{inst_id} 批量撤单未提交: {err}")),
+            }
+        }
+        if pending.is_empty() {
+            return summary;
+        }
+        summary.insert(
+            0,
+            format!("退出撤单：已提交 {} 个合约的批量撤单请求", pending.len()),
+        );
+        let mut cancelled = 0usize;
+        let mut failed = 0usize;
+        let deadline = tokio::time::Instant::now() + timeout;
+        while !pending.is_empty() {
+            let message = match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Ok(message)) => message,
+                Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                Ok(Err(broadcast::error::RecvError::Closed)) | Err(_) => break,
+            };
+            match message {
+                Command::CancelAllCompleted(inst_id) => {
+                    pending.remove(&inst_id);
+                }
+                Command::TradeResult(TradeEvent::Cancel(response))
+                    if pending.contains(&response.inst_id) =>
+                {
+                    if response.success {
+                        cancelled += 1;
+                    } else {
+                        failed += 1;
+                        summary.push(format!(
+                            "退出撤单：{} 订单 {} 撤销失败: {}",
+                            response.inst_id, response.ord_id, response.message
+                        ));
+                    }
+                    if let Err(err) = self.trade.record_result(TradeEvent::Cancel(response)) {
+                        summary.push(format!("退出撤单：记录委托日志失败: {err}"));
+                    }
+                }
+                Command::Error(message)
+                    if pending
+                        .iter()
+                        .any(|inst_id| message.starts_with(&format!("{inst_id} 批量撤单失败"))) =>
+                {
+                    summary.push(format!("退出撤单：{message}"));
+                }
+                _ => {}
+            }
+        }
+        summary.push(format!(
+            "退出撤单：已撤销 {cancelled} 笔挂单，失败 {failed} 笔"
+        ));
+        if !pending.is_empty() {
+            let mut waiting: Vec<_> = pending.into_iter().collect();
+            waiting.sort();
+            summary.push(format!(
+                "退出撤单：等待 {:.1}s 超时，以下合约未确认撤单结果，请到 OKX 核对挂单: {}",
+                timeout.as_secs_f64(),
+                waiting.join(", ")
+            ));
+        }
+        summary
+    }
+
     fn open_order_dialog(
         &mut self,
         inst_id: String,
@@ -3289,9 +7889,30 @@ impl TuiApp {
         replace_order_id: Option<String>,
         initial_leverage: Option<f64>,
     ) {
+        let spot = okx::is_spot_inst(&inst_id);
+        let (pos_side, initial_leverage) = if spot {
+            (None, None)
+        } else {
+            (pos_side, initial_leverage)
+        };
         let leverage_value = initial_leverage
             .map(Self::leverage_input_value)
             .unwrap_or_default();
+        let position_margin_mode = self
+            .trade
+            .positions
+            .iter()
+            .find(|pos| {
+                pos.inst_id == inst_id
+                    && (pos_side.is_none()
+                        || Self::pos_side_for_position(pos, self.trade.position_mode) == pos_side)
+            })
+            .and_then(|pos| pos.mgn_mode);
+        let margin_mode = if spot {
+            None
+        } else {
+            position_margin_mode.or_else(|| self.trade.margin_modes.get(&inst_id).copied())
+        };
         self.trade.input = Some(OrderInputState {
             side,
             inst_id: inst_id.clone(),
@@ -3307,17 +7928,42 @@ impl TuiApp {
             reduce_only,
             tag,
             replace_order_id,
+            ord_type: TradeOrderType::Limit,
+            callback: if order_kind == TradeOrderKind::TrailingStop {
+                "1%".to_string()
+            } else {
+                String::new()
+            },
+            margin_mode,
+            initial_margin_mode: position_margin_mode,
+            default_margin_mode: if spot {
+                None
+            } else {
+                self.trade.default_margin_mode
+            },
+            spot,
+            take_profit: String::new(),
+            stop_loss: String::new(),
+            post_only_confirmed: false,
+            calculator: false,
+            risk: String::new(),
         });
         self.clear_status_message();
     }
 
     fn handle_order_input_key(&mut self, key: KeyEvent) {
         if let Some(input) = self.trade.input.as_mut() {
+            if key.code != KeyCode::Enter {
+                input.post_only_confirmed = false;
+            }
             match key.code {
                 KeyCode::Esc => {
                     self.trade.input = None;
                     self.set_status_message("已取消下单");
                 }
+                KeyCode::Enter if input.calculator => {
+                    self.apply_risk_sizing();
+                }
                 KeyCode::Enter => {
                     self.finalize_order_input();
                 }
@@ -3333,10 +7979,34 @@ impl TuiApp {
                 KeyCode::Right => {
                     input.focus_next_field();
                 }
+                KeyCode::Up if input.active_field == OrderInputField::Price => {
+                    self.nudge_order_price(1);
+                }
+                KeyCode::Down if input.active_field == OrderInputField::Price => {
+                    self.nudge_order_price(-1);
+                }
+                KeyCode::PageUp if input.active_field == OrderInputField::Price => {
+                    self.nudge_order_price(PRICE_NUDGE_PAGE_TICKS);
+                }
+                KeyCode::PageDown if input.active_field == OrderInputField::Price => {
+                    self.nudge_order_price(-PRICE_NUDGE_PAGE_TICKS);
+                }
                 KeyCode::Backspace => {
                     let field = input.active_value_mut();
                     field.pop();
                 }
+                KeyCode::Char('m') | KeyCode::Char('M') => {
+                    input.toggle_ord_type();
+                }
+                KeyCode::Char('i') | KeyCode::Char('I') => {
+                    input.toggle_margin_mode();
+                }
+                KeyCode::Char('r') | KeyCode::Char('R') => {
+                    input.toggle_calculator();
+                }
+                KeyCode::Char('o') | KeyCode::Char('O') => {
+                    self.toggle_order_reduce_only();
+                }
                 KeyCode::Char(c) => {
                     if c.is_ascii_digit() || c == '.' {
                         let field = input.active_value_mut();
@@ -3344,6 +8014,11 @@ impl TuiApp {
                             return;
                         }
                         field.push(c);
+                    } else if c == '%' && input.active_field == OrderInputField::Callback {
+                        let field = input.active_value_mut();
+                        if !field.contains('%') {
+                            field.push(c);
+                        }
                     }
                 }
                 _ => {}
@@ -3351,6 +8026,138 @@ impl TuiApp {
         }
     }
 
+    // 手动委托切换只减仓时绑定到可被该方向减少的持仓
+    fn toggle_order_reduce_only(&mut self) {
+        let mode = self.trade.position_mode;
+        let Some(input) = self.trade.input.as_mut() else {
+            return;
+        };
+        if input.intent != OrderIntent::Manual
+            || input.order_kind != TradeOrderKind::Regular
+            || input.replace_order_id.is_some()
+        {
+            input.error = Some("仅手动下单支持切换只减仓".to_string());
+            return;
+        }
+        if input.spot {
+            input.error = Some("现货交易不支持只减仓".to_string());
+            return;
+        }
+        if input.reduce_only {
+            input.reduce_only = false;
+            input.pos_side = None;
+            input.error = None;
+            return;
+        }
+        let Some(position) = self.trade.positions.iter().find(|pos| {
+            pos.inst_id == input.inst_id
+                && pos.size != 0.0
+                && Self::closing_side_for_position(pos, mode) == input.side
+        }) else {
+            input.error = Some(format!(
+                "{} 没有可被{}减少的持仓，无法设为只减仓",
+                input.inst_id,
+                Self::side_label(input.side)
+            ));
+            return;
+        };
+        input.reduce_only = true;
+        input.pos_side = Self::pos_side_for_position(position, mode);
+        input.calculator = false;
+        input.take_profit.clear();
+        input.stop_loss.clear();
+        if matches!(
+            input.active_field,
+            OrderInputField::TakeProfit | OrderInputField::StopLoss | OrderInputField::Risk
+        ) {
+            input.active_field = OrderInputField::Size;
+        }
+        input.error = None;
+    }
+
+    // 以当前输入（为空时取最新价）为基准按最小变动价位调整
+    fn nudge_order_price(&mut self, ticks: i32) {
+        let Some(input) = self.trade.input.as_ref() else {
+            return;
+        };
+        let Some(market) = self
+            .trade
+            .markets
+            .get(&input.inst_id)
+            .filter(|market| market.tick_sz > 0.0)
+        else {
+            return;
+        };
+        let Some(base) = input
+            .price
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|price| *price > 0.0)
+            .or_else(|| self.latest_prices.get(&input.inst_id).copied())
+        else {
+            return;
+        };
+        let next = market.round_price(base + market.tick_sz * ticks as f64);
+        if next <= 0.0 {
+            return;
+        }
+        let formatted = self.format_price_for(&input.inst_id, next);
+        if let Some(input) = self.trade.input.as_mut() {
+            input.price = formatted;
+            input.error = None;
+        }
+    }
+
+    fn order_entry_price(&self, input: &OrderInputState) -> Option<f64> {
+        let latest = self.latest_prices.get(&input.inst_id).copied();
+        if input.is_market() {
+            return latest;
+        }
+        input
+            .price
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|price| *price > 0.0)
+            .or(latest)
+    }
+
+    fn order_risk_sizing(&self, input: &OrderInputState) -> Result<RiskSizing, &'static str> {
+        input.risk_sizing(
+            self.order_entry_price(input),
+            self.trade.markets.get(&input.inst_id),
+        )
+    }
+
+    fn apply_risk_sizing(&mut self) {
+        let Some(sizing) = self
+            .trade
+            .input
+            .as_ref()
+            .map(|input| self.order_risk_sizing(input))
+        else {
+            return;
+        };
+        let Some(input) = self.trade.input.as_mut() else {
+            return;
+        };
+        let unit = self.size_unit;
+        match sizing {
+            Ok(sizing) => {
+                let size = match unit {
+                    SizeUnit::Coin => sizing.size,
+                    SizeUnit::Contracts => sizing.contracts,
+                };
+                input.size = Self::trim_formatted_number(format!("{size:.8}"));
+                input.calculator = false;
+                input.active_field = OrderInputField::Size;
+                input.error = None;
+            }
+            Err(err) => input.error = Some(err.to_string()),
+        }
+    }
+
     fn finalize_order_input(&mut self) {
         let (request, intent, replace_ord_id, leverage_request) = {
             let input = match self.trade.input.as_mut() {
@@ -3358,9 +8165,20 @@ impl TuiApp {
                 None => return,
             };
             let order_kind = input.order_kind;
+            let ord_type = input.ord_type;
 
-            let price = match input.price.trim().parse::<f64>() {
-                Ok(value) if value > 0.0 => value,
+            let price = match (ord_type, input.price.trim().parse::<f64>()) {
+                (_, Ok(value)) if value > 0.0 => value,
+                _ if input.is_trailing() && input.price.trim().is_empty() => 0.0,
+                (TradeOrderType::Market, _) => self
+                    .latest_prices
+                    .get(&input.inst_id)
+                    .copied()
+                    .unwrap_or_default(),
+                (TradeOrderType::Ioc | TradeOrderType::Fok, _) => {
+                    input.error = Some(format!("{} 委托必须填写价格", ord_type.label()));
+                    return;
+                }
                 _ => {
                     input.error = Some("价格必须为正数".to_string());
                     return;
@@ -3373,24 +8191,87 @@ impl TuiApp {
                     return;
                 }
             };
-            let size = if let Some(market) = self.trade.markets.get(&input.inst_id) {
-                size / market.ct_val
-            } else {
+            let Some(market) = self.trade.markets.get(&input.inst_id) else {
                 input.error = Some("无法获取合约信息，无法提交平仓单".to_string());
                 return;
             };
-            if let Some(position) = self.trade.positions.iter().find(|pos| {
-                pos.inst_id == input.inst_id && Self::pos_side_for_position(pos) == input.pos_side
-            }) {
-                if size > position.size.abs() {
-                    input.error = Some("平仓数量不能大于持仓数量".to_string());
-                    return;
-                }
+            let (size, unit_val) = match self.size_unit {
+                SizeUnit::Coin => (market.round_size(size / market.ct_val), market.ct_val),
+                SizeUnit::Contracts => (market.round_size(size), 1.0),
+            };
+            if size <= 0.0 || (market.min_sz > 0.0 && size < market.min_sz) {
+                let min_size = market.min_sz.max(market.lot_sz) * unit_val;
+                input.error = Some(format!(
+                    "数量低于最小下单量 {}",
+                    Self::trim_formatted_number(format!("{min_size:.8}"))
+                ));
+                return;
+            }
+            let price = if ord_type == TradeOrderType::Market {
+                price
             } else {
-                input.error = Some("未找到对应持仓，无法提交平仓单".to_string());
+                market.round_price(price)
+            };
+            // 只挂单价格会立即成交时 OKX 会直接撤单，先提醒一次，再次 Enter 仍然提交
+            let crossed_last = self
+                .latest_prices
+                .get(&input.inst_id)
+                .copied()
+                .filter(|last| match input.side {
+                    TradeSide::Buy => price >= *last,
+                    TradeSide::Sell => price <= *last,
+                });
+            if let Some(last) = crossed_last
+                .filter(|_| ord_type == TradeOrderType::PostOnly && !input.post_only_confirmed)
+            {
+                input.post_only_confirmed = true;
+                input.error = Some(format!(
+                    "只挂单价格已穿过最新价 {}，提交后会被撤销；再次 Enter 仍提交",
+                    Self::trim_formatted_number(format!("{last:.8}"))
+                ));
                 return;
             }
-            let leverage_value = {
+            if let Err(err) = input.check_reduce_only_position(
+                &self.trade.positions,
+                self.trade.position_mode,
+                size,
+            ) {
+                input.error = Some(err);
+                return;
+            }
+            let callback = if input.is_trailing() {
+                match input.parse_callback() {
+                    Some(callback) => Some(callback),
+                    None => {
+                        input.error =
+                            Some("回调幅度无效，比例请输入如 1.5%，价差请输入正数".to_string());
+                        return;
+                    }
+                }
+            } else {
+                None
+            };
+            let bracket = match input.parse_bracket() {
+                Ok(Some(bracket)) => {
+                    let bracket = BracketOrder {
+                        take_profit: bracket.take_profit.map(|px| market.round_price(px)),
+                        stop_loss: bracket.stop_loss.map(|px| market.round_price(px)),
+                    };
+                    if let Err(err) = bracket.validate(input.side, price) {
+                        input.error = Some(err.to_string());
+                        return;
+                    }
+                    Some(bracket)
+                }
+                Ok(None) => None,
+                Err(err) => {
+                    input.error = Some(err.to_string());
+                    return;
+                }
+            };
+            let leverage_value = if input.spot {
+                None
+            } else {
                 let trimmed = input.leverage.trim();
                 if trimmed.is_empty() {
                     input.initial_leverage
@@ -3408,16 +8289,34 @@ impl TuiApp {
                     }
                 }
             };
+            // 只减仓委托不受上限约束，避免大仓位无法平掉
+            if !input.reduce_only
+                && let Err(err) = Self::check_order_limits(
+                    &self.trade.order_limits,
+                    price,
+                    self.latest_prices.get(&input.inst_id).copied(),
+                    size,
+                    Some(market.ct_val),
+                    leverage_value,
+                )
+            {
+                input.error = Some(err);
+                return;
+            }
+            let margin_mode = input.effective_margin_mode();
+            let margin_changed = input.margin_mode.is_some()
+                && input.margin_mode != input.initial_margin_mode.or(input.default_margin_mode);
             let leverage_request = leverage_value.and_then(|value| {
                 let changed = input
                     .initial_leverage
                     .map(|prev| (prev - value).abs() > LEVERAGE_EPSILON)
                     .unwrap_or(true);
-                if changed {
+                if changed || margin_changed {
                     Some(SetLeverageRequest {
                         inst_id: input.inst_id.clone(),
                         lever: value,
                         pos_side: input.pos_side.clone(),
+                        margin_mode,
                     })
                 } else {
                     None
@@ -3429,20 +8328,49 @@ impl TuiApp {
                     side: input.side,
                     price,
                     size,
-                    ord_type: Some(TradeOrderType::Limit),
+                    ord_type: Some(ord_type),
                     pos_side: input.pos_side.clone(),
                     reduce_only: input.reduce_only,
                     tag: input.tag.clone(),
                     operator: TradeOperator::Manual,
                     leverage: leverage_value,
                     kind: order_kind,
+                    margin_mode,
+                    callback,
+                    bracket,
+                    cl_ord_id: Some(okx::new_cl_ord_id()),
                 },
                 input.intent,
                 input.replace_order_id.clone(),
                 leverage_request,
             )
         };
+        let staged = StagedOrder {
+            request,
+            intent,
+            replace_ord_id,
+            leverage_request,
+        };
+        if self.confirm_orders {
+            self.order_confirmation = Some(staged);
+            return;
+        }
+        self.submit_order(staged);
+    }
+
+    fn submit_order(&mut self, staged: StagedOrder) {
+        let StagedOrder {
+            request,
+            intent,
+            replace_ord_id,
+            leverage_request,
+        } = staged;
         self.trade.input = None;
+        if let Some(mode) = request.margin_mode {
+            self.trade
+                .margin_modes
+                .insert(request.inst_id.clone(), mode);
+        }
         if let Some(tx) = self.trade.order_sender() {
             if let Some(leverage_req) = leverage_request {
                 match tx.try_send(TradingCommand::SetLeverage(leverage_req)) {
@@ -3457,6 +8385,45 @@ impl TuiApp {
                     }
                 }
             }
+            if let Some(ord_id) = replace_ord_id.as_ref()
+                && matches!(
+                    request.kind,
+                    TradeOrderKind::Regular | TradeOrderKind::TakeProfit | TradeOrderKind::StopLoss
+                )
+            {
+                let amend_request = TradingCommand::Amend(AmendOrderRequest {
+                    inst_id: request.inst_id.clone(),
+                    ord_id: ord_id.clone(),
+                    side: request.side,
+                    new_price: request.price,
+                    new_size: request.size,
+                    operator: TradeOperator::Manual,
+                    pos_side: request.pos_side.clone(),
+                    leverage: request.leverage,
+                    kind: request.kind,
+                });
+                match tx.try_send(amend_request) {
+                    Ok(_) => {
+                        let price_fmt = self.format_price_for(&request.inst_id, request.price);
+                        let size_fmt = self.format_contract_size(&request.inst_id, request.size);
+                        self.set_status_message(format!(
+                            "{} 已发送{} {} {} @ {}",
+                            intent.action_label(),
+                            Self::side_label(request.side),
+                            size_fmt,
+                            request.inst_id,
+                            price_fmt
+                        ));
+                    }
+                    Err(TrySendError::Full(_)) => {
+                        self.set_error_status_message("交易请求繁忙，请稍候再试 (改单)");
+                    }
+                    Err(TrySendError::Closed(_)) => {
+                        self.set_error_status_message("交易通道已关闭，无法提交改单请求");
+                    }
+                }
+                return;
+            }
             if let Some(ord_id) = replace_ord_id {
                 let cancel_request = TradingCommand::Cancel(CancelOrderRequest {
                     inst_id: request.inst_id.clone(),
@@ -3482,13 +8449,28 @@ impl TuiApp {
                     let price_fmt = self.format_price_for(&request.inst_id, request.price);
                     let size_fmt = self.format_contract_size(&request.inst_id, request.size);
                     self.set_status_message(format!(
-                        "{} 已发送{} {} {} @ {}",
+                        "{} 已发送{} {} {} @ {}{} · {} 秒内按 u 撤销",
                         intent.action_label(),
                         Self::side_label(request.side),
                         size_fmt,
                         request.inst_id,
-                        price_fmt
+                        price_fmt,
+                        if request.bracket.is_some() {
+                            " · 附带止盈/止损"
+                        } else {
+                            ""
+                        },
+                        ORDER_UNDO_WINDOW.as_secs()
                     ));
+                    self.undo_order = Some(UndoableOrder {
+                        inst_id: request.inst_id.clone(),
+                        cl_ord_id: request.cl_ord_id.clone(),
+                        kind: request.kind,
+                        pos_side: request.pos_side.clone(),
+                        submitted_at: Instant::now(),
+                        ord_id: None,
+                        cancel_requested: false,
+                    });
                 }
                 Err(TrySendError::Full(_)) => {
                     self.set_error_status_message("交易请求繁忙，请稍候重试");
@@ -3502,6 +8484,88 @@ impl TuiApp {
         }
     }
 
+    // 用 clOrdId 匹配最近一次手动下单的回报，返回追加到状态栏的提示
+    fn correlate_undo_order(&mut self, response: &TradeResponse) -> Option<String> {
+        let undo = self.undo_order.as_mut()?;
+        if undo.ord_id.is_some()
+            || response.cl_ord_id.is_none()
+            || response.cl_ord_id != undo.cl_ord_id
+        {
+            return None;
+        }
+        let Some(ord_id) = response.order_id.clone().filter(|_| response.success) else {
+            self.undo_order = None;
+            return None;
+        };
+        undo.ord_id = Some(ord_id);
+        if undo.cancel_requested {
+            return Some(match self.send_undo_cancel() {
+                Ok(message) | Err(message) => format!(" · {message}"),
+            });
+        }
+        let remaining = ORDER_UNDO_WINDOW.saturating_sub(undo.submitted_at.elapsed());
+        if remaining.is_zero() {
+            self.undo_order = None;
+            return None;
+        }
+        Some(format!(
+            " · {} 秒内按 u 撤销",
+            remaining.as_secs_f64().ceil()
+        ))
+    }
+
+    fn undo_last_order(&mut self) {
+        let Some(undo) = self.undo_order.as_mut() else {
+            return;
+        };
+        if undo.submitted_at.elapsed() > ORDER_UNDO_WINDOW {
+            self.undo_order = None;
+            self.set_error_status_message(format!(
+                "已超过 {} 秒撤销窗口，请在挂单列表中撤单",
+                ORDER_UNDO_WINDOW.as_secs()
+            ));
+            return;
+        }
+        if undo.ord_id.is_none() {
+            undo.cancel_requested = true;
+            self.set_status_message("等待订单号回报，收到后立即撤单 (U)");
+            return;
+        }
+        match self.send_undo_cancel() {
+            Ok(message) => self.set_status_message(message),
+            Err(message) => self.set_error_status_message(message),
+        }
+    }
+
+    // 尽力而为：订单可能已经成交，撤单结果以 OKX 回报为准
+    fn send_undo_cancel(&mut self) -> Result<String, String> {
+        let Some(UndoableOrder {
+            inst_id,
+            kind,
+            pos_side,
+            ord_id: Some(ord_id),
+            ..
+        }) = self.undo_order.take()
+        else {
+            return Err("没有可撤销的订单".to_string());
+        };
+        let Some(tx) = self.trade.order_sender() else {
+            return Err("未配置 OKX API，无法撤单".to_string());
+        };
+        let request = CancelOrderRequest {
+            inst_id: inst_id.clone(),
+            ord_id: ord_id.clone(),
+            operator: TradeOperator::Manual,
+            pos_side,
+            kind,
+        };
+        match tx.try_send(TradingCommand::Cancel(request)) {
+            Ok(_) => Ok(format!("已发送撤单 {inst_id} {ord_id} (U)")),
+            Err(TrySendError::Full(_)) => Err("交易请求繁忙，撤销下单失败".to_string()),
+            Err(TrySendError::Closed(_)) => Err("交易通道已关闭，无法撤销下单".to_string()),
+        }
+    }
+
     fn chart_title_text(&self) -> String {
         let base = if self.normalize {
             "Mark Δ%"
@@ -3523,7 +8587,7 @@ impl TuiApp {
             badges.push(Span::styled(
                 "[Normalized]",
                 Style::default()
-                    .fg(Color::LightGreen)
+                    .fg(self.theme.success)
                     .add_modifier(Modifier::BOLD),
             ));
         }
@@ -3532,22 +8596,91 @@ impl TuiApp {
                 (
                     "[Multi Y]",
                     Style::default()
-                        .fg(Color::LightMagenta)
+                        .fg(self.theme.secondary)
                         .add_modifier(Modifier::BOLD),
                 )
             } else {
-                ("[Multi Y Pending]", Style::default().fg(Color::DarkGray))
+                ("[Multi Y Pending]", Style::default().fg(self.theme.muted))
+            };
+            badges.push(Span::styled(label, style));
+        }
+        if self.log_scale {
+            let style = if self.log_scale_active() {
+                Style::default()
+                    .fg(self.theme.info)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(self.theme.muted)
+            };
+            badges.push(Span::styled("[Log]", style));
+        }
+        if !self.follow_latest {
+            badges.push(Span::styled(
+                "[Frozen]",
+                Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if self.sma_enabled {
+            badges.push(Span::styled(
+                format!("[SMA {}]", self.sma_period),
+                Style::default()
+                    .fg(self.theme.subtle)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if self.bollinger_enabled {
+            let style = if self.normalize || self.multi_axis_active() {
+                Style::default().fg(self.theme.muted)
+            } else {
+                Style::default()
+                    .fg(self.theme.subtle)
+                    .add_modifier(Modifier::BOLD)
+            };
+            badges.push(Span::styled(
+                format!("[BB {},{}]", DEFAULT_BOLLINGER_PERIOD, DEFAULT_BOLLINGER_K),
+                style,
+            ));
+        }
+        if self.chart_style == ChartStyle::Candles {
+            let label = format!("[Candles {}]", self.candle_interval_label());
+            let style = if self.candles_active() {
+                Style::default()
+                    .fg(self.theme.emphasis)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(self.theme.muted)
             };
             badges.push(Span::styled(label, style));
         }
         badges
     }
 
+    fn candles_active(&self) -> bool {
+        self.chart_style == ChartStyle::Candles && !self.normalize && !self.multi_axis_active()
+    }
+
+    fn candle_interval_secs(&self) -> u64 {
+        CANDLE_INTERVALS_SECS[self.candle_interval_idx % CANDLE_INTERVALS_SECS.len()]
+    }
+
+    fn candle_interval_label(&self) -> String {
+        let secs = self.candle_interval_secs();
+        if secs.is_multiple_of(60) {
+            format!("{}m", secs / 60)
+        } else {
+            format!("{secs}s")
+        }
+    }
+
     fn axis_title(&self) -> String {
         if self.normalize {
             "Δ% (Relative Change)".to_string()
         } else if self.multi_axis_active() {
             "Scaled (0-1)".to_string()
+        } else if self.log_scale_active() {
+            "Mark Price (log)".to_string()
         } else {
             "Mark Price".to_string()
         }
@@ -3562,32 +8695,66 @@ impl TuiApp {
     }
 
     fn color_for(&self, inst_id: &str) -> Color {
-        self.colors.get(inst_id).copied().unwrap_or(Color::White)
+        self.colors.get(inst_id).copied().unwrap_or(self.theme.text)
     }
 
-    fn update_window(&mut self) {
+    fn data_bounds(&self) -> Option<(f64, f64)> {
         let mut min_x = f64::INFINITY;
         let mut max_x = f64::NEG_INFINITY;
         for series in self.data.values() {
-            if let Some((first_x, _)) = series.first() {
+            if let Some((first_x, _)) = series.front() {
                 min_x = min_x.min(*first_x);
             }
-            if let Some((last_x, _)) = series.last() {
+            if let Some((last_x, _)) = series.back() {
                 max_x = max_x.max(*last_x);
             }
         }
-        if min_x.is_finite() && max_x.is_finite() {
-            self.window = [min_x, max_x];
+        (min_x.is_finite() && max_x.is_finite()).then_some((min_x, max_x))
+    }
+
+    fn update_window(&mut self) {
+        if !self.follow_latest {
+            return;
+        }
+        self.window = match self.data_bounds() {
+            Some((min_x, max_x)) => [min_x, max_x],
+            None => [0.0, 100.0],
+        };
+    }
+
+    fn toggle_follow_latest(&mut self) {
+        self.follow_latest = !self.follow_latest;
+        if self.follow_latest {
+            self.update_window();
+            self.set_status_message("Auto-follow resumed (F)");
         } else {
-            self.window = [0.0, 100.0];
+            self.set_status_message("Chart frozen; ←/→ or h/l to pan, F to resume");
         }
     }
+
+    // 平移时自动冻结，窗口至少保留一半与数据重叠
+    fn pan_window(&mut self, fraction: f64) {
+        let Some((min_x, max_x)) = self.data_bounds() else {
+            return;
+        };
+        self.follow_latest = false;
+        let span = (self.window[1] - self.window[0]).max(1.0);
+        let lowest = min_x - span / 2.0;
+        let highest = (max_x - span / 2.0).max(lowest);
+        let start = (self.window[0] + span * fraction).clamp(lowest, highest);
+        self.window = [start, start + span];
+        self.set_status_message(format!(
+            "Viewing {} – {} (←/→ pan, F to follow latest)",
+            self.format_timestamp_label(self.window[0]),
+            self.format_timestamp_label(self.window[1])
+        ));
+    }
     fn render_status(&self, frame: &mut Frame, area: Rect) {
         if let Some(message) = &self.status_message {
             let color = if self.status_is_error {
-                Color::Red
+                self.theme.status_error
             } else {
-                Color::Yellow
+                self.theme.status_info
             };
             let block = Block::bordered().title("Status");
             let status = Paragraph::new(message.as_str())
@@ -3641,12 +8808,29 @@ impl TuiApp {
                     .fg(entry.color)
                     .add_modifier(Modifier::BOLD),
             )]));
-            lines.push(Line::from(format!(
+            let price_label = format!(
                 "Price {}",
                 self.format_price_for(&entry.inst_id, entry.price)
-            )));
+            );
+            match self.price_stale_for(&entry.inst_id) {
+                Some(elapsed) => lines.push(Line::styled(
+                    format!("{price_label} {}", Self::stale_price_label(elapsed)),
+                    Style::default().fg(self.theme.muted),
+                )),
+                None => lines.push(Line::from(price_label)),
+            }
             if let Some(change) = entry.change_pct {
-                lines.push(Line::from(format!("Δ {}", self.format_percent(change))));
+                let color = if change > 0.0 {
+                    self.theme.success
+                } else if change < 0.0 {
+                    self.theme.error
+                } else {
+                    self.theme.muted
+                };
+                lines.push(Line::styled(
+                    format!("Δ {}", self.format_percent(change)),
+                    Style::default().fg(color),
+                ));
             }
             lines.push(Line::from(" "));
         }
@@ -3658,6 +8842,7 @@ impl TuiApp {
     }
 
     fn update_precision(&mut self, inst_id: &str, precision: usize) {
+        let previous = self.price_precision.get(inst_id).copied();
         self.price_precision
             .entry(inst_id.to_string())
             .and_modify(|existing| {
@@ -3666,6 +8851,9 @@ impl TuiApp {
                 }
             })
             .or_insert(precision);
+        if previous != self.price_precision.get(inst_id).copied() {
+            self.label_cache.get_mut().prices.clear();
+        }
     }
 
     fn normalized_series(series: &[(f64, f64)]) -> Vec<(f64, f64)> {
@@ -3722,6 +8910,9 @@ impl TuiApp {
         if self.normalize {
             return (Cow::Owned(Self::normalized_series(source)), None);
         }
+        if self.log_scale_active() {
+            return (Cow::Owned(Self::log_series(source)), None);
+        }
         if multi_axis_active {
             if let Some((min, max)) = Self::series_bounds(source) {
                 let scaled = Self::scaled_series(source, min, max);
@@ -3746,10 +8937,23 @@ impl TuiApp {
         }
     }
 
+    // 以当前时间窗口内的第一个价格为基准，与相对涨跌模式无关
+    fn session_change_pct(&self, inst_id: &str) -> Option<f64> {
+        let series = price_series::as_slice(self.data.get(inst_id)?);
+        let (_, last) = series.last()?;
+        let start = series.partition_point(|(x, _)| *x < self.window[0]);
+        let (_, first) = series.get(start).or_else(|| series.first())?;
+        if first.abs() < f64::EPSILON {
+            None
+        } else {
+            Some(((last / first) - 1.0) * 100.0)
+        }
+    }
+
     fn normalized_latest_value(&self, inst_id: &str) -> Option<f64> {
         let series = self.data.get(inst_id)?;
-        let (_, first) = series.first()?;
-        let (_, last) = series.last()?;
+        let (_, first) = series.front()?;
+        let (_, last) = series.back()?;
         if first.abs() < f64::EPSILON {
             None
         } else {
@@ -3758,8 +8962,9 @@ impl TuiApp {
     }
 
     fn price_precision_for(&self, inst_id: &str) -> usize {
-        self.price_precision
+        self.precision_overrides
             .get(inst_id)
+            .or_else(|| self.price_precision.get(inst_id))
             .copied()
             .unwrap_or_else(|| self.price_precision())
     }
@@ -3769,6 +8974,40 @@ impl TuiApp {
         format!("{value:.prec$}", value = value, prec = precision)
     }
 
+    fn cached_price_label(&self, inst_id: &str, value: f64) -> Rc<str> {
+        let key = (value.to_bits(), self.price_precision_for(inst_id));
+        let mut cache = self.label_cache.borrow_mut();
+        if let Some(label) = cache
+            .prices
+            .get(inst_id)
+            .and_then(|labels| labels.get(&key))
+        {
+            return label.clone();
+        }
+        let label: Rc<str> = self.format_price_for(inst_id, value).into();
+        let labels = cache.prices.entry(inst_id.to_string()).or_default();
+        if labels.len() >= LABEL_CACHE_LIMIT {
+            labels.clear();
+        }
+        labels.insert(key, label.clone());
+        label
+    }
+
+    fn cached_size_label(&self, inst_id: &str, value: f64) -> Rc<str> {
+        let key = value.to_bits();
+        let mut cache = self.label_cache.borrow_mut();
+        if let Some(label) = cache.sizes.get(inst_id).and_then(|labels| labels.get(&key)) {
+            return label.clone();
+        }
+        let label: Rc<str> = self.format_contract_size(inst_id, value).into();
+        let labels = cache.sizes.entry(inst_id.to_string()).or_default();
+        if labels.len() >= LABEL_CACHE_LIMIT {
+            labels.clear();
+        }
+        labels.insert(key, label.clone());
+        label
+    }
+
     fn format_value(&self, inst_id: &str, value: f64) -> String {
         if self.normalize {
             self.format_percent(value)
@@ -3780,6 +9019,8 @@ impl TuiApp {
     fn format_axis_value(&self, value: f64) -> String {
         if self.normalize {
             self.format_percent(value)
+        } else if self.log_scale_active() {
+            self.format_axis_price(10f64.powf(value))
         } else if self.multi_axis_active() {
             format!("{value:.2}")
         } else {
@@ -3791,12 +9032,6 @@ impl TuiApp {
         format!("{value:+.2}%", value = value)
     }
 
-    fn format_pnl(value: f64) -> String {
-        const PNL_PRECISION: usize = 8;
-        let formatted = format!("{value:+.prec$}", value = value, prec = PNL_PRECISION);
-        Self::trim_formatted_number(formatted)
-    }
-
     fn format_pnl_ratio(value: f64) -> String {
         let percent = value * 100.0;
         format!("{percent:+.2}%", percent = percent)
@@ -3804,10 +9039,11 @@ impl TuiApp {
 
     fn format_contract_size(&self, inst_id: &str, value: f64) -> String {
         let mut value = value;
-        if let Some(mkt) = self.trade.markets.get(inst_id) {
-            if mkt.ct_val > 0.0 {
-                value *= mkt.ct_val;
-            }
+        if self.size_unit == SizeUnit::Coin
+            && let Some(mkt) = self.trade.markets.get(inst_id)
+            && mkt.ct_val > 0.0
+        {
+            value *= mkt.ct_val;
         }
         const SIZE_PRECISION: usize = 8;
         let formatted = format!("{value:.prec$}", value = value, prec = SIZE_PRECISION);
@@ -3850,14 +9086,29 @@ impl TuiApp {
         self.multi_axis && !self.normalize
     }
 
+    fn log_scale_active(&self) -> bool {
+        self.log_scale && !self.normalize && !self.multi_axis_active()
+    }
+
+    // 非正价格无法取对数，直接跳过
+    fn log_series(series: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        series
+            .iter()
+            .filter(|(_, y)| *y > 0.0)
+            .map(|(x, y)| (*x, y.log10()))
+            .collect()
+    }
+
     fn poll_input(&mut self) -> Result<bool> {
         while event::poll(Duration::from_millis(0))? {
+            self.last_activity = Instant::now();
             match event::read()? {
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
                     if self.handle_key_event(key)? {
                         return Ok(true);
                     }
                 }
+                Event::Mouse(mouse) => self.handle_mouse_event(mouse),
                 _ => {}
             }
         }
@@ -3898,7 +9149,17 @@ impl TuiApp {
     }
 
     fn price_precision(&self) -> usize {
-        self.price_precision.values().copied().max().unwrap_or(2)
+        self.price_precision
+            .keys()
+            .chain(self.precision_overrides.keys())
+            .filter_map(|inst_id| {
+                self.precision_overrides
+                    .get(inst_id)
+                    .or_else(|| self.price_precision.get(inst_id))
+            })
+            .copied()
+            .max()
+            .unwrap_or(2)
     }
 
     fn format_axis_price(&self, value: f64) -> String {
@@ -3913,14 +9174,71 @@ enum ColumnAlign {
     Right,
 }
 
-fn row_style(selected: bool) -> Style {
-    if selected {
-        Style::default()
-            .bg(Color::LightCyan)
-            .fg(Color::Black)
-            .add_modifier(Modifier::BOLD)
+fn format_volume(value: f64) -> String {
+    if value >= 1_000_000.0 {
+        format!("{:.2}M", value / 1_000_000.0)
+    } else if value >= 1_000.0 {
+        format!("{:.1}k", value / 1_000.0)
+    } else {
+        format!("{value:.2}")
+    }
+}
+
+fn format_token_count(tokens: u64) -> String {
+    if tokens >= 1_000_000 {
+        format!("{:.2}M", tokens as f64 / 1_000_000.0)
+    } else if tokens >= 1_000 {
+        format!("{:.1}k", tokens as f64 / 1_000.0)
     } else {
-        Style::default()
+        tokens.to_string()
+    }
+}
+
+fn bucket_ohlc(series: &[(f64, f64)], interval_ms: f64, window: [f64; 2]) -> Vec<OhlcBucket> {
+    let mut buckets: Vec<OhlcBucket> = Vec::new();
+    if interval_ms <= 0.0 {
+        return buckets;
+    }
+    for &(ts, price) in series {
+        if ts < window[0] || ts > window[1] || !price.is_finite() {
+            continue;
+        }
+        let start = (ts / interval_ms).floor() * interval_ms;
+        match buckets.last_mut() {
+            Some(bucket) if (bucket.start - start).abs() < f64::EPSILON => {
+                bucket.high = bucket.high.max(price);
+                bucket.low = bucket.low.min(price);
+                bucket.close = price;
+            }
+            _ => buckets.push(OhlcBucket {
+                start,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+            }),
+        }
+    }
+    buckets
+}
+
+// 在 x 处从 range 一端到另一端按 step 取点，两端总会包含在内；超出 clip 的部分不取点
+fn push_vertical(points: &mut Vec<(f64, f64)>, x: f64, range: [f64; 2], clip: [f64; 2], step: f64) {
+    let low = range[0].min(range[1]).max(clip[0]);
+    let high = range[0].max(range[1]).min(clip[1]);
+    if low > high {
+        return;
+    }
+    points.push((x, low));
+    if step.is_finite() && step > 0.0 {
+        let mut y = low + step;
+        while y < high {
+            points.push((x, y));
+            y += step;
+        }
+    }
+    if high > low {
+        points.push((x, high));
     }
 }
 
@@ -3963,6 +9281,25 @@ fn clip_to_width(value: &str, width: usize) -> String {
     result
 }
 
+fn wrap_to_width(text: &str, width: usize) -> Vec<String> {
+    let mut rows = Vec::new();
+    for line in text.lines() {
+        let mut row = String::new();
+        let mut row_width = 0;
+        for ch in line.chars() {
+            let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
+            if row_width + ch_width > width && !row.is_empty() {
+                rows.push(std::mem::take(&mut row));
+                row_width = 0;
+            }
+            row.push(ch);
+            row_width += ch_width;
+        }
+        rows.push(row);
+    }
+    rows
+}
+
 fn pad_to_width(value: &str, width: usize, align: ColumnAlign) -> String {
     let current = UnicodeWidthStr::width(value);
     if current >= width {
@@ -3991,3 +9328,194 @@ fn visible_range(len: usize, visible: usize, selected: usize) -> (usize, usize)
     let start = clamped.min(max_start);
     (start, start + visible)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order_input(side: TradeSide, pos_side: &str, reduce_only: bool) -> OrderInputState {
+        OrderInputState {
+            side,
+            inst_id: "BTC-USDT-SWAP".to_string(),
+            price: String::new(),
+            size: String::new(),
+            leverage: String::new(),
+            initial_leverage: None,
+            active_field: OrderInputField::Size,
+            error: None,
+            pos_side: Some(pos_side.to_string()),
+            intent: OrderIntent::Manual,
+            order_kind: TradeOrderKind::Regular,
+            reduce_only,
+            tag: None,
+            replace_order_id: None,
+            ord_type: TradeOrderType::Limit,
+            callback: String::new(),
+            margin_mode: None,
+            initial_margin_mode: None,
+            default_margin_mode: None,
+            spot: false,
+            take_profit: String::new(),
+            stop_loss: String::new(),
+            post_only_confirmed: false,
+            calculator: false,
+            risk: String::new(),
+        }
+    }
+
+    fn long_position(size: f64) -> PositionInfo {
+        PositionInfo {
+            inst_id: "BTC-USDT-SWAP".to_string(),
+            pos_side: Some("long".to_string()),
+            size,
+            avg_px: None,
+            lever: None,
+            upl: None,
+            upl_ratio: None,
+            imr: 0.0,
+            create_time: None,
+            mgn_mode: None,
+        }
+    }
+
+    #[test]
+    fn test_position_guard_only_applies_to_reduce_only_orders() {
+        let mode = PositionMode::LongShort;
+        let positions = vec![long_position(2.0)];
+        // 开仓委托没有对应持仓也可提交
+        let open_short = order_input(TradeSide::Sell, "short", false);
+        assert!(
+            open_short
+                .check_reduce_only_position(&[], mode, 5.0)
+                .is_ok()
+        );
+        let close_long = order_input(TradeSide::Sell, "long", true);
+        assert!(
+            close_long
+                .check_reduce_only_position(&positions, mode, 2.0)
+                .is_ok()
+        );
+        assert_eq!(
+            close_long.check_reduce_only_position(&positions, mode, 3.0),
+            Err("平仓数量不能大于持仓数量".to_string())
+        );
+        assert!(
+            close_long
+                .check_reduce_only_position(&[], mode, 1.0)
+                .is_err()
+        );
+        let mut tp_short = order_input(TradeSide::Buy, "short", true);
+        tp_short.intent = OrderIntent::TakeProfit;
+        assert_eq!(
+            tp_short.check_reduce_only_position(&positions, mode, 1.0),
+            Err("未找到对应持仓，无法提交平仓单".to_string())
+        );
+    }
+
+    #[test]
+    fn test_net_pnl_ratio_uses_margin() {
+        let mut long = long_position(2.0);
+        long.imr = 30.0;
+        let mut short = long_position(1.0);
+        short.pos_side = Some("short".to_string());
+        short.imr = 10.0;
+        let ratio = TuiApp::net_pnl_ratio(&[&long, &short], 8.0).unwrap();
+        assert!((ratio - 0.2).abs() < 1e-12);
+
+        short.imr = 0.0;
+        assert_eq!(TuiApp::net_pnl_ratio(&[&long, &short], 8.0), None);
+    }
+
+    #[test]
+    fn test_order_limits_reject_unpriced_orders() {
+        let limits = OrderLimits {
+            max_notional: Some(1000.0),
+            max_leverage: Some(10.0),
+        };
+        let check = TuiApp::check_order_limits;
+        // 500 张 × 0.01 × 100 = 500
+        assert!(check(&limits, 0.0, Some(100.0), 500.0, Some(0.01), Some(5.0)).is_ok());
+        assert!(check(&limits, 300.0, Some(100.0), 500.0, Some(0.01), Some(5.0)).is_err());
+        assert!(check(&limits, 100.0, None, 500.0, Some(0.01), Some(20.0)).is_err());
+        assert_eq!(
+            check(&limits, 0.0, None, 500.0, Some(0.01), None),
+            Err("暂无最新价，无法校验名义价值上限".to_string())
+        );
+        assert!(check(&limits, 100.0, None, 500.0, None, None).is_err());
+        let leverage_only = OrderLimits {
+            max_notional: None,
+            max_leverage: Some(10.0),
+        };
+        assert!(check(&leverage_only, 0.0, None, 500.0, None, Some(5.0)).is_ok());
+    }
+
+    #[test]
+    fn test_size_unit_to_coins() {
+        let market = MarketInfo {
+            ct_val: 0.01,
+            lever: 100.0,
+            lot_sz: 0.01,
+            min_sz: 0.01,
+            tick_sz: 0.1,
+        };
+        assert_eq!(SizeUnit::Coin.to_coins(2.0, Some(&market)), Some(2.0));
+        assert_eq!(SizeUnit::Coin.to_coins(2.0, None), Some(2.0));
+        assert_eq!(
+            SizeUnit::Contracts.to_coins(200.0, Some(&market)),
+            Some(2.0)
+        );
+        assert_eq!(SizeUnit::Contracts.to_coins(200.0, None), None);
+    }
+
+    #[test]
+    fn test_bucket_ohlc_per_interval() {
+        let series = vec![
+            (500.0, 9.0),
+            (1000.0, 10.0),
+            (1200.0, 12.0),
+            (1500.0, f64::NAN),
+            (1800.0, 8.0),
+            (1900.0, 11.0),
+            // 2000~3000 区间没有数据，不生成空 K 线
+            (3100.0, 20.0),
+            (3600.0, 21.0),
+            (4500.0, 30.0),
+        ];
+        let buckets = bucket_ohlc(&series, 1000.0, [1000.0, 4000.0]);
+        assert_eq!(
+            buckets,
+            vec![
+                OhlcBucket {
+                    start: 1000.0,
+                    open: 10.0,
+                    high: 12.0,
+                    low: 8.0,
+                    close: 11.0,
+                },
+                OhlcBucket {
+                    start: 3000.0,
+                    open: 20.0,
+                    high: 21.0,
+                    low: 20.0,
+                    close: 21.0,
+                },
+            ]
+        );
+        assert!(bucket_ohlc(&series, 0.0, [0.0, 5000.0]).is_empty());
+        assert!(bucket_ohlc(&series, 1000.0, [5000.0, 6000.0]).is_empty());
+    }
+
+    #[test]
+    fn test_push_vertical_clips_and_keeps_ends() {
+        let mut points = Vec::new();
+        push_vertical(&mut points, 1.0, [4.0, 2.0], [0.0, 10.0], 0.5);
+        let ys: Vec<f64> = points.iter().map(|(_, y)| *y).collect();
+        assert_eq!(ys, vec![2.0, 2.5, 3.0, 3.5, 4.0]);
+        points.clear();
+        push_vertical(&mut points, 1.0, [0.0, 20.0], [5.0, 6.0], 1.0);
+        assert_eq!(points, vec![(1.0, 5.0), (1.0, 6.0)]);
+        points.clear();
+        push_vertical(&mut points, 1.0, [11.0, 12.0], [5.0, 6.0], 1.0);
+        assert!(points.is_empty());
+    }
+}